@@ -0,0 +1,104 @@
+//!
+//! Compares throughput of a single accept loop against multiple accept loops sharing the same
+//! listener. Every request uses a new connection, so accepting connections dominates the cost.
+//!
+//! Run with `cargo bench --bench accept_workers`.
+//!
+
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use racoon::core::path::Path;
+use racoon::core::request::Request;
+use racoon::core::response::status::ResponseStatus;
+use racoon::core::response::{HttpResponse, Response};
+use racoon::core::server::Server;
+use racoon::view;
+
+const CONNECTIONS: usize = 20_000;
+const CONCURRENCY: usize = 256;
+
+async fn home(_request: Request) -> Response {
+    HttpResponse::ok().body("Hello World")
+}
+
+async fn run_clients(address: String) -> Duration {
+    let started = Instant::now();
+
+    let mut clients = Vec::with_capacity(CONCURRENCY);
+    for _ in 0..CONCURRENCY {
+        let address = address.clone();
+
+        clients.push(tokio::spawn(async move {
+            for _ in 0..CONNECTIONS / CONCURRENCY {
+                let mut stream = TcpStream::connect(&address).await.unwrap();
+                stream
+                    .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+
+                let mut response = vec![];
+                let _ = stream.read_to_end(&mut response).await;
+                assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+            }
+        }));
+    }
+
+    for client in clients {
+        client.await.unwrap();
+    }
+
+    started.elapsed()
+}
+
+async fn benchmark(accept_workers: usize) -> Duration {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let mut server = Server::from_tcp_listener(listener);
+    server
+        .urls(vec![Path::new("/", view!(home))])
+        .accept_workers(accept_workers);
+
+    let shutdown_lock = server.shutdown_lock();
+    tokio::spawn(async move {
+        let _ = server.run().await;
+    });
+
+    // Warm up
+    run_clients(address.clone()).await;
+    let elapsed = run_clients(address).await;
+
+    let (_, condvar) = &*shutdown_lock;
+    condvar.notify_all();
+    elapsed
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let parallelism = std::thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+        .max(4);
+
+    runtime.block_on(async {
+        for accept_workers in [1, parallelism] {
+            let elapsed = benchmark(accept_workers).await;
+            let requests_per_second = CONNECTIONS as f64 / elapsed.as_secs_f64();
+
+            println!(
+                "accept_workers={:<3} {:>8.0} connections/s ({:?} for {} connections)",
+                accept_workers, requests_per_second, elapsed, CONNECTIONS
+            );
+        }
+    });
+
+    // Accept loops may still be waiting for the shutdown signal in blocking threads.
+    runtime.shutdown_background();
+}
@@ -93,19 +93,240 @@ pub fn parse_cookie_header_value(cookie_header_value: String, cookies: &mut Cook
     }
 }
 
+///
+/// Attributes of the cookie sent in `Set-Cookie` header.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use racoon::core::cookie::CookieOptions;
+///
+/// let options = CookieOptions {
+///     max_age: Some(Duration::from_secs(3600)),
+///     ..CookieOptions::default()
+/// };
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct CookieOptions {
+    /// Cookie expires after the duration. Without max age, cookie is removed when the browser is
+    /// closed.
+    pub max_age: Option<Duration>,
+    pub path: String,
+    pub http_only: bool,
+}
+
+impl Default for CookieOptions {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            path: "/".to_string(),
+            http_only: true,
+        }
+    }
+}
+
 pub fn set_cookie<S: AsRef<str>>(headers: &mut Headers, name: S, value: S, max_age: Duration) {
-    let now = SystemTime::now();
-    let expire_time = now + max_age;
-    let datetime = DateTime::<Utc>::from(expire_time);
-    let expires_date = datetime.format("%a, %d-%b-%Y %H:%M:%S GMT");
+    let options = CookieOptions {
+        max_age: Some(max_age),
+        ..CookieOptions::default()
+    };
+    set_cookie_with_options(headers, name, value, &options);
+}
 
+///
+/// Adds `Set-Cookie` header with the given attributes. Existing `Set-Cookie` headers are kept.
+///
+pub fn set_cookie_with_options<S: AsRef<str>>(
+    headers: &mut Headers,
+    name: S,
+    value: S,
+    options: &CookieOptions,
+) {
     let encoded_name = urlencoding::encode(name.as_ref());
     let encoded_value = urlencoding::encode(value.as_ref());
 
-    let header_value = format!(
-        "{}={}; Expires={}; Path=/; HttpOnly",
-        encoded_name, encoded_value, expires_date
-    );
+    let mut header_value = format!("{}={}", encoded_name, encoded_value);
+
+    if let Some(max_age) = options.max_age {
+        let expire_time = SystemTime::now() + max_age;
+        let datetime = DateTime::<Utc>::from(expire_time);
+        let expires_date = datetime.format("%a, %d-%b-%Y %H:%M:%S GMT");
+        header_value.push_str(&format!("; Expires={}", expires_date));
+    }
+
+    header_value.push_str(&format!("; Path={}", options.path));
+
+    if options.http_only {
+        header_value.push_str("; HttpOnly");
+    }
+
     headers.set_multiple("Set-Cookie", header_value);
 }
 
+///
+/// Value of the `SameSite` cookie attribute.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    /// Cookie is sent with cross-site requests. Browsers accept it only with `Secure` attribute.
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+///
+/// Builds value of the `Set-Cookie` header. By default cookie is `HttpOnly` and sent for all the
+/// paths, same as `CookieOptions::default()`.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use racoon::core::cookie::{CookieBuilder, SameSite};
+///
+/// let cookie = CookieBuilder::new("theme", "dark")
+///     .path("/app")
+///     .secure(true)
+///     .same_site(SameSite::Strict)
+///     .build();
+///
+/// assert_eq!(cookie, "theme=dark; Path=/app; Secure; HttpOnly; SameSite=Strict");
+///
+/// let cookie = CookieBuilder::new("token", "abc")
+///     .domain("example.com")
+///     .max_age(Duration::from_secs(3600))
+///     .build();
+///
+/// assert_eq!(true, cookie.contains("; Max-Age=3600; Domain=example.com; Path=/; HttpOnly"));
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct CookieBuilder {
+    name: String,
+    value: String,
+    max_age: Option<Duration>,
+    domain: Option<String>,
+    path: String,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl CookieBuilder {
+    pub fn new<S: AsRef<str>>(name: S, value: S) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            value: value.as_ref().to_string(),
+            max_age: None,
+            domain: None,
+            path: "/".to_string(),
+            secure: false,
+            http_only: true,
+            same_site: None,
+        }
+    }
+
+    ///
+    /// Cookie expires after the duration. Without max age, cookie is removed when the browser is
+    /// closed.
+    ///
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    ///
+    /// Host to which the cookie is sent, including its subdomains. Without domain, cookie is sent
+    /// only to the host which set it.
+    ///
+    pub fn domain<S: AsRef<str>>(mut self, domain: S) -> Self {
+        self.domain = Some(domain.as_ref().to_string());
+        self
+    }
+
+    ///
+    /// Path prefix for which the cookie is sent. Default is `/`.
+    ///
+    pub fn path<S: AsRef<str>>(mut self, path: S) -> Self {
+        self.path = path.as_ref().to_string();
+        self
+    }
+
+    ///
+    /// If true, cookie is sent only over HTTPS.
+    ///
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    ///
+    /// If true, cookie is not accessible from JavaScript. Default is true.
+    ///
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    ///
+    /// Returns value of the `Set-Cookie` header.
+    ///
+    pub fn build(&self) -> String {
+        let encoded_name = urlencoding::encode(&self.name);
+        let encoded_value = urlencoding::encode(&self.value);
+
+        let mut header_value = format!("{}={}", encoded_name, encoded_value);
+
+        if let Some(max_age) = self.max_age {
+            let expire_time = SystemTime::now() + max_age;
+            let datetime = DateTime::<Utc>::from(expire_time);
+            let expires_date = datetime.format("%a, %d-%b-%Y %H:%M:%S GMT");
+            header_value.push_str(&format!("; Expires={}", expires_date));
+            header_value.push_str(&format!("; Max-Age={}", max_age.as_secs()));
+        }
+
+        if let Some(domain) = &self.domain {
+            header_value.push_str(&format!("; Domain={}", domain));
+        }
+
+        header_value.push_str(&format!("; Path={}", self.path));
+
+        if self.secure {
+            header_value.push_str("; Secure");
+        }
+
+        if self.http_only {
+            header_value.push_str("; HttpOnly");
+        }
+
+        if let Some(same_site) = self.same_site {
+            header_value.push_str(&format!("; SameSite={}", same_site.as_str()));
+        }
+
+        header_value
+    }
+}
+
+///
+/// Adds `Set-Cookie` header built from the cookie builder. Existing `Set-Cookie` headers are kept.
+///
+pub fn set_cookie_builder(headers: &mut Headers, cookie: &CookieBuilder) {
+    headers.set_multiple("Set-Cookie", cookie.build());
+}
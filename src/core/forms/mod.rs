@@ -1,10 +1,14 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 
 use async_tempfile::TempFile;
+use tokio::sync::{Semaphore, SemaphorePermit};
 
 #[derive(Debug)]
 pub struct FileField {
     pub name: String,
+    /// `Content-Type` of the file part sent by the client, e.g. `image/png`. The client may send
+    /// any value, so do not trust it without checking the file content.
+    pub content_type: Option<String>,
     temp_file: TempFile,
     pub temp_path: PathBuf,
 }
@@ -15,16 +19,44 @@ impl FileField {
 
         Self {
             name: name.as_ref().to_string(),
+            content_type: None,
             temp_file,
             temp_path,
         }
     }
 
+    pub fn content_type(mut self, content_type: Option<String>) -> Self {
+        self.content_type = content_type;
+        self
+    }
+
     pub fn temp_file(&self) -> &TempFile {
         &self.temp_file
     }
 }
 
+///
+/// Progress of the file upload reported while multipart form is being parsed.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadProgress {
+    /// Name of the form field whose file is being written.
+    pub field: String,
+    /// Request body bytes received so far.
+    pub bytes_so_far: usize,
+    /// Request body size from `Content-Length` header if present.
+    pub content_length: Option<usize>,
+}
+
+///
+/// Value of the multipart form part, returned in the order the parts were sent.
+///
+#[derive(Debug)]
+pub enum PartValue {
+    Text(String),
+    File(FileField),
+}
+
 pub type Files = HashMap<String, Vec<FileField>>;
 pub type FormData = HashMap<String, Vec<String>>;
 
@@ -50,6 +82,12 @@ impl FileFieldShortcut for Files {
     }
 }
 
+/// Default maximum number of fields and files in the form.
+const DEFAULT_MAX_FIELDS: usize = 1000;
+
+/// Default time an upload waits for a permit when concurrent uploads are limited.
+const DEFAULT_UPLOAD_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
 ///
 /// The form constraint works as a security measure while parsing request body.
 /// It can be set globally while creating the `Server` instance.
@@ -75,6 +113,15 @@ pub struct FormConstraints {
     max_value_size: usize,
     /// Map of field name and maximum allowed size.
     custom_max_sizes: HashMap<String, usize>,
+    /// Maximum number of fields and files in the form.
+    max_fields: usize,
+    /// Permits for writing uploaded files. Shared by all the requests using these constraints.
+    upload_permits: Option<Arc<Semaphore>>,
+    /// Maximum time an upload waits for a permit before it is rejected.
+    upload_wait_timeout: Duration,
+    /// Size of blocks written on the blocking thread pool. Files are written with async IO if
+    /// not set.
+    blocking_write_size: Option<usize>,
 }
 
 impl FormConstraints {
@@ -91,6 +138,99 @@ impl FormConstraints {
             max_file_size,
             max_value_size,
             custom_max_sizes,
+            max_fields: DEFAULT_MAX_FIELDS,
+            upload_permits: None,
+            upload_wait_timeout: DEFAULT_UPLOAD_WAIT_TIMEOUT,
+            blocking_write_size: None,
+        }
+    }
+
+    ///
+    /// Limits the number of fields and files parsed from the form, so a body with many small
+    /// fields cannot grow the form data without bound. Default is 1000.
+    ///
+    pub fn set_max_fields(&mut self, count: usize) {
+        self.max_fields = count;
+    }
+
+    pub fn max_fields(&self) -> usize {
+        self.max_fields
+    }
+
+    ///
+    /// Limits the number of form files written to temporary files at the same time. Excess
+    /// uploads wait until one of the running uploads is completed, up to the upload wait timeout.
+    /// Zero means unlimited, since no file could be uploaded otherwise.
+    ///
+    pub fn set_max_concurrent_uploads(&mut self, count: usize) {
+        if count == 0 {
+            self.upload_permits = None;
+        } else {
+            self.upload_permits = Some(Arc::new(Semaphore::new(count)));
+        }
+    }
+
+    ///
+    /// Maximum time an upload waits for a permit when concurrent uploads are limited. Uploads
+    /// waiting longer fail with `FormFieldError::UploadsBusy`, which `FormValidator` responds to
+    /// with `503 Service Unavailable`. Default is 30 seconds.
+    ///
+    pub fn set_upload_wait_timeout(&mut self, timeout: Duration) {
+        self.upload_wait_timeout = timeout;
+    }
+
+    ///
+    /// Writes uploaded files with `tokio::task::spawn_blocking` in blocks of `block_size` bytes
+    /// instead of writing every received chunk with async file IO. Received bytes are collected in
+    /// memory until the block is full, so fewer and larger writes reach the disk, which keeps
+    /// upload-heavy workloads from stalling the runtime. Blocking threads are limited by the
+    /// `max_blocking_threads` setting of the Tokio runtime.
+    ///
+    pub fn set_blocking_file_writes(&mut self, block_size: usize) {
+        self.blocking_write_size = Some(block_size.max(1));
+    }
+
+    ///
+    /// Returns the block size if uploaded files are written on the blocking thread pool.
+    ///
+    pub fn blocking_write_size(&self) -> Option<usize> {
+        self.blocking_write_size
+    }
+
+    ///
+    /// Returns copy of these constraints with the concurrent upload limit, upload wait timeout and
+    /// file write settings of `other`. Upload permits are shared with `other`.
+    ///
+    pub(crate) fn with_upload_settings(&self, other: &FormConstraints) -> FormConstraints {
+        Self {
+            max_body_size: self.max_body_size,
+            max_header_size: self.max_header_size,
+            max_file_size: self.max_file_size,
+            max_value_size: self.max_value_size,
+            custom_max_sizes: self.custom_max_sizes.clone(),
+            max_fields: self.max_fields,
+            upload_permits: other.upload_permits.clone(),
+            upload_wait_timeout: other.upload_wait_timeout,
+            blocking_write_size: other.blocking_write_size,
+        }
+    }
+
+    ///
+    /// Waits for permission to write uploaded file. Returns `None` if concurrent uploads are not
+    /// limited and `FormFieldError::UploadsBusy` if no permit is available within the upload wait
+    /// timeout. The permit must be kept until the file is written completely.
+    ///
+    pub async fn acquire_upload_permit(
+        &self,
+    ) -> Result<Option<SemaphorePermit<'_>>, FormFieldError> {
+        let semaphore = match &self.upload_permits {
+            Some(semaphore) => semaphore,
+            None => return Ok(None),
+        };
+
+        match tokio::time::timeout(self.upload_wait_timeout, semaphore.acquire()).await {
+            Ok(permit) => Ok(permit.ok()),
+            Err(_) => Err(FormFieldError::UploadsBusy),
         }
     }
 
@@ -155,6 +295,10 @@ pub enum FormFieldError {
     MaxFileSizeExceed(String),
     /// Maximum length of text length exceeded.
     MaxValueSizeExceed(String),
+    /// Maximum number of form fields exceeded.
+    MaxFieldsExceed,
+    /// Maximum number of concurrent uploads is reached and no upload completed in time.
+    UploadsBusy,
     /// (field_name, error, is_criticial)
     /// If error is critical, don't expose to client.
     Others(Option<String>, String, bool),
@@ -1,7 +1,13 @@
 use std::collections::HashMap;
+use std::time::SystemTime;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 pub type Headers = HashMap<String, Vec<Vec<u8>>>;
 
+/// Headers in the order they were received, with original name casing and raw value bytes.
+pub type RawHeaders = Vec<(String, Vec<u8>)>;
+
 pub trait HeaderValue {
     /// Performs case-insensitive lookup and returns first value.
     fn value<S: AsRef<str>>(&self, name: S) -> Option<String>;
@@ -78,6 +84,124 @@ impl HeaderValue for Headers {
     }
 }
 
+///
+/// Typed accessors for the commonly used headers, parsing the values on each call.
+///
+/// # Example
+///
+/// ```
+/// use racoon::core::headers::{HeaderValue, Headers, TypedHeaders};
+///
+/// let mut headers = Headers::new();
+/// headers.set("Content-Type", "text/html; charset=utf-8");
+/// headers.set("Accept-Encoding", "gzip;q=0.5, br");
+///
+/// let content_type = headers.content_type().unwrap();
+/// assert_eq!("text/html", content_type.essence());
+/// assert_eq!(Some("utf-8"), content_type.param("charset"));
+/// assert_eq!(vec!["br", "gzip"], headers.accept_encodings());
+/// ```
+///
+pub trait TypedHeaders {
+    /// Returns `Content-Length` header value. `None` if missing or not a valid number.
+    fn content_length(&self) -> Option<u64>;
+
+    /// Returns parsed `Content-Type` header value. `None` if missing or not a valid media type.
+    fn content_type(&self) -> Option<Mime>;
+
+    ///
+    /// Returns lowercase content codings accepted in `Accept-Encoding` header, most preferred
+    /// first. Codings with the same quality keep the order sent by the client, and codings with
+    /// `q=0` are left out.
+    ///
+    fn accept_encodings(&self) -> Vec<String>;
+}
+
+impl TypedHeaders for Headers {
+    fn content_length(&self) -> Option<u64> {
+        self.value("Content-Length")?.trim().parse().ok()
+    }
+
+    fn content_type(&self) -> Option<Mime> {
+        Mime::parse(self.value("Content-Type")?)
+    }
+
+    fn accept_encodings(&self) -> Vec<String> {
+        let accept_encoding = match self.value("Accept-Encoding") {
+            Some(accept_encoding) => accept_encoding,
+            None => return vec![],
+        };
+
+        let mut codings: Vec<(String, f32)> = coding_qualities(&accept_encoding)
+            .into_iter()
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+
+        // Stable sort keeps the client order of codings with the same quality.
+        codings.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        codings.into_iter().map(|(coding, _)| coding).collect()
+    }
+}
+
+///
+/// Media type parsed from header values like `Content-Type`, e.g. `text/html; charset=utf-8`.
+/// Type, subtype and parameter names are lowercase.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mime {
+    pub top_level: String,
+    pub subtype: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl Mime {
+    ///
+    /// Parses media type with its parameters. Returns `None` if type or subtype is missing.
+    ///
+    pub fn parse<S: AsRef<str>>(value: S) -> Option<Self> {
+        let value = value.as_ref();
+        let mut parts = value.split(';');
+
+        let essence = parts.next()?.trim().to_lowercase();
+        let (top_level, subtype) = essence.split_once('/')?;
+        if top_level.is_empty() || subtype.is_empty() || subtype.contains('/') {
+            return None;
+        }
+
+        let mut params = vec![];
+        for param in parts {
+            if let Some((name, param_value)) = param.split_once('=') {
+                let name = name.trim().to_lowercase();
+                let param_value = param_value.trim().trim_matches('"').to_string();
+                params.push((name, param_value));
+            }
+        }
+
+        Some(Self {
+            top_level: top_level.to_string(),
+            subtype: subtype.to_string(),
+            params,
+        })
+    }
+
+    ///
+    /// Returns media type without parameters, e.g. `text/html`.
+    ///
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.top_level, self.subtype)
+    }
+
+    ///
+    /// Returns value of the parameter. Name is case-insensitive.
+    ///
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param_name, _)| param_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
 ///
 /// # Example
 ///
@@ -103,9 +227,246 @@ pub fn multipart_boundary(content_type: &String) -> std::io::Result<String> {
     return Err(std::io::Error::other("Boundary missing."));
 }
 
+///
+/// Returns value of the parameter from header values like `text/html; charset=utf-8`.
+/// Parameter name is case-insensitive and surrounding quotes are removed from the value.
+///
+pub fn header_param<S: AsRef<str>>(header_value: S, name: &str) -> Option<String> {
+    for param in header_value.as_ref().split(";").skip(1) {
+        if let Some((param_name, value)) = param.split_once("=") {
+            if param_name.trim().eq_ignore_ascii_case(name) {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+
+    None
+}
+
+///
+/// Returns media type of header values like `Content-Type` without parameters.
+///
+/// # Example
+///
+/// ```
+/// use racoon::core::headers::media_type;
+///
+/// assert_eq!(media_type(" Multipart/Form-Data; boundary=----123456"), "multipart/form-data");
+/// ```
+///
+pub fn media_type<S: AsRef<str>>(header_value: S) -> String {
+    let header_value = header_value.as_ref();
+    let media_type = match header_value.split_once(";") {
+        Some((media_type, _)) => media_type,
+        None => header_value,
+    };
+
+    media_type.trim().to_lowercase()
+}
+
+///
+/// Returns true if the content coding is acceptable according to `Accept-Encoding` header value.
+/// Codings with `q=0` are not acceptable.
+///
+pub fn accepts_encoding(accept_encoding: &str, coding: &str) -> bool {
+    encoding_quality(accept_encoding, coding) > 0.0
+}
+
+///
+/// Returns quality value of the content coding in `Accept-Encoding` header value, falling back to
+/// the quality of `*`. Codings not listed have quality 0.
+///
+pub fn encoding_quality(accept_encoding: &str, coding: &str) -> f32 {
+    let mut wildcard_quality = 0.0;
+
+    for (name, quality) in coding_qualities(accept_encoding) {
+        let is_alias = coding == "gzip" && name == "x-gzip";
+        if name.eq_ignore_ascii_case(coding) || is_alias {
+            return quality;
+        }
+
+        if name == "*" {
+            wildcard_quality = quality;
+        }
+    }
+
+    wildcard_quality
+}
+
+///
+/// Parses `Accept-Encoding` header value to lowercase codings with their quality values in the
+/// order sent. Quality defaults to 1 and invalid quality values are treated as 0.
+///
+fn coding_qualities(accept_encoding: &str) -> Vec<(String, f32)> {
+    let mut codings = vec![];
+
+    for value in accept_encoding.split(',') {
+        let mut parts = value.split(';');
+        let name = parts.next().unwrap_or("").trim();
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut quality = 1.0;
+        for param in parts {
+            if let Some((key, value)) = param.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("q") {
+                    quality = value.trim().parse::<f32>().unwrap_or(0.0);
+                }
+            }
+        }
+
+        codings.push((name.to_lowercase(), quality));
+    }
+
+    codings
+}
+
+///
+/// Appends header name to the `Vary` header unless it is already listed, so caches store separate
+/// responses for the values of the request header.
+///
+pub fn add_vary(headers: &mut Headers, name: &str) {
+    match headers.value("Vary") {
+        Some(vary) => {
+            let is_listed = vary
+                .split(',')
+                .any(|value| value.trim().eq_ignore_ascii_case(name) || value.trim() == "*");
+
+            if !is_listed {
+                headers.retain(|header_name, _| !header_name.eq_ignore_ascii_case("Vary"));
+                headers.set("Vary", format!("{}, {}", vary, name));
+            }
+        }
+        None => headers.set("Vary", name),
+    }
+}
+
+///
+/// Byte range of a partial body sent in `Content-Range` header, e.g. `bytes 0-1023/5000`.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentRange {
+    /// Offset of the first byte.
+    pub start: u64,
+    /// Offset of the last byte, inclusive.
+    pub end: u64,
+    /// Size of the complete body. `None` if the size is unknown, e.g. `bytes 0-1023/*`.
+    pub complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    ///
+    /// Parses `Content-Range` header value. Returns `None` for other units, unsatisfied ranges
+    /// like `bytes */5000` and ranges ending before they start or after the complete length.
+    ///
+    /// # Example
+    /// ```
+    /// use racoon::core::headers::ContentRange;
+    ///
+    /// let range = ContentRange::parse("bytes 0-1023/5000").unwrap();
+    /// assert_eq!(0, range.start);
+    /// assert_eq!(1023, range.end);
+    /// assert_eq!(Some(5000), range.complete_length);
+    /// assert_eq!(1024, range.length());
+    ///
+    /// assert_eq!(None, ContentRange::parse("bytes */5000"));
+    /// ```
+    ///
+    pub fn parse<S: AsRef<str>>(value: S) -> Option<Self> {
+        let range = value.as_ref().trim().strip_prefix("bytes ")?;
+        let (range, complete_length) = range.split_once("/")?;
+        let (start, end) = range.split_once("-")?;
+
+        let start = start.trim().parse::<u64>().ok()?;
+        let end = end.trim().parse::<u64>().ok()?;
+
+        let complete_length = match complete_length.trim() {
+            "*" => None,
+            length => Some(length.parse::<u64>().ok()?),
+        };
+
+        if end < start {
+            return None;
+        }
+
+        if let Some(complete_length) = complete_length {
+            if end >= complete_length {
+                return None;
+            }
+        }
+
+        Some(Self {
+            start,
+            end,
+            complete_length,
+        })
+    }
+
+    ///
+    /// Number of bytes in the range.
+    ///
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    ///
+    /// Returns true if the range contains the last byte of the complete body.
+    ///
+    pub fn is_last(&self) -> bool {
+        self.complete_length == Some(self.end + 1)
+    }
+}
+
+///
+/// Formats time as HTTP date in IMF-fixdate format defined in RFC 7231, used in headers like
+/// `Date` and `Last-Modified`.
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use racoon::core::headers::http_date;
+///
+/// let time = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+/// assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", http_date(time));
+/// ```
+///
+pub fn http_date(time: SystemTime) -> String {
+    let datetime = DateTime::<Utc>::from(time);
+    datetime.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+///
+/// Parses HTTP date such as `Sun, 06 Nov 1994 08:49:37 GMT`. Obsolete RFC 850
+/// (`Sunday, 06-Nov-94 08:49:37 GMT`) and asctime (`Sun Nov  6 08:49:37 1994`) formats are
+/// accepted as well. Returns `None` if the date is malformed.
+///
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    const FORMATS: [&str; 3] = [
+        "%a, %d %b %Y %H:%M:%S GMT",
+        "%A, %d-%b-%y %H:%M:%S GMT",
+        "%a %b %e %H:%M:%S %Y",
+    ];
+
+    let value = value.trim();
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|datetime| datetime.and_utc())
+}
+
 #[cfg(test)]
 pub mod tests {
-    use crate::core::headers::{multipart_boundary, HeaderValue, Headers};
+    use std::time::{Duration, SystemTime};
+
+    use chrono::{DateTime, Utc};
+
+    use crate::core::headers::{
+        accepts_encoding, add_vary, encoding_quality, header_param, http_date, media_type,
+        multipart_boundary, parse_http_date, ContentRange, HeaderValue, Headers, Mime,
+        TypedHeaders,
+    };
 
     #[test]
     pub fn test_header_value() {
@@ -136,4 +497,140 @@ pub mod tests {
             "----123456"
         );
     }
+
+    #[test]
+    pub fn test_header_param() {
+        let value = "text/plain; Charset=\"ISO-8859-1\"; format=flowed";
+        assert_eq!(Some("ISO-8859-1".to_string()), header_param(value, "charset"));
+        assert_eq!(Some("flowed".to_string()), header_param(value, "format"));
+        assert_eq!(None, header_param("text/plain", "charset"));
+    }
+
+    #[test]
+    pub fn test_media_type() {
+        assert_eq!("text/html", media_type("text/html"));
+        assert_eq!("application/json", media_type("Application/JSON; charset=utf-8"));
+        assert_eq!("", media_type(""));
+    }
+
+    #[test]
+    pub fn test_content_range() {
+        let range = ContentRange::parse("bytes 4000-4999/5000").unwrap();
+        assert_eq!(1000, range.length());
+        assert_eq!(true, range.is_last());
+
+        let range = ContentRange::parse("bytes 0-1023/*").unwrap();
+        assert_eq!(None, range.complete_length);
+        assert_eq!(false, range.is_last());
+
+        for value in [
+            "bytes */5000",
+            "bytes 10-5/5000",
+            "bytes 0-5000/5000",
+            "items 0-10/20",
+            "bytes 0-10",
+            "bytes a-10/20",
+        ] {
+            assert_eq!(None, ContentRange::parse(value));
+        }
+    }
+
+    #[test]
+    pub fn test_http_date() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", http_date(time));
+
+        // Day of month is zero padded.
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(86400);
+        assert_eq!("Fri, 02 Jan 1970 00:00:00 GMT", http_date(time));
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+        let expected = Some(DateTime::<Utc>::from(time));
+        assert_eq!(expected, parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"));
+        assert_eq!(expected, parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"));
+        assert_eq!(expected, parse_http_date("Sun Nov  6 08:49:37 1994"));
+
+        assert_eq!(None, parse_http_date(""));
+        assert_eq!(None, parse_http_date("yesterday"));
+        assert_eq!(None, parse_http_date("Sun, 06 Nov 1994 08:49:37"));
+        assert_eq!(None, parse_http_date("Sun, 31 Nov 1994 08:49:37 GMT"));
+        assert_eq!(None, parse_http_date("Sun, 06 Nov 1994 25:49:37 GMT"));
+    }
+
+    #[test]
+    fn test_accepts_encoding() {
+        assert_eq!(true, accepts_encoding("gzip, deflate, br", "br"));
+        assert_eq!(true, accepts_encoding("GZIP;q=0.5", "gzip"));
+        assert_eq!(true, accepts_encoding("x-gzip", "gzip"));
+        assert_eq!(true, accepts_encoding("*", "br"));
+        assert_eq!(false, accepts_encoding("br;q=0, *", "br"));
+        assert_eq!(false, accepts_encoding("gzip, *;q=0", "br"));
+        assert_eq!(false, accepts_encoding("", "gzip"));
+        assert_eq!(false, accepts_encoding("identity", "gzip"));
+    }
+
+    #[test]
+    fn test_encoding_quality() {
+        assert_eq!(1.0, encoding_quality("gzip, br", "br"));
+        assert_eq!(0.5, encoding_quality("br;q=0.5, gzip", "br"));
+        assert_eq!(0.2, encoding_quality("gzip, *;q=0.2", "br"));
+        assert_eq!(0.0, encoding_quality("gzip", "br"));
+    }
+
+    #[test]
+    fn test_typed_headers() {
+        let mut headers = Headers::new();
+        assert_eq!(None, headers.content_length());
+        assert_eq!(None, headers.content_type());
+        assert_eq!(true, headers.accept_encodings().is_empty());
+
+        headers.set("content-length", " 1024 ");
+        headers.set("Content-Type", "Multipart/Form-Data; Boundary=\"----123\"");
+        let accept_encoding = "gzip;q=0.8, deflate;q=0, BR, identity;q=0.8, *;q=0.1";
+        headers.set("Accept-Encoding", accept_encoding);
+
+        assert_eq!(Some(1024), headers.content_length());
+
+        let content_type = headers.content_type().unwrap();
+        assert_eq!("multipart/form-data", content_type.essence());
+        assert_eq!(Some("----123"), content_type.param("boundary"));
+
+        assert_eq!(
+            vec!["br", "gzip", "identity", "*"],
+            headers.accept_encodings()
+        );
+
+        headers.set("content-length", "-1");
+        assert_eq!(None, headers.content_length());
+    }
+
+    #[test]
+    fn test_mime() {
+        let mime = Mime::parse("application/json").unwrap();
+        assert_eq!("application", mime.top_level);
+        assert_eq!("json", mime.subtype);
+        assert_eq!(None, mime.param("charset"));
+
+        assert_eq!(None, Mime::parse("text"));
+        assert_eq!(None, Mime::parse("/html"));
+        assert_eq!(None, Mime::parse("text/"));
+        assert_eq!(None, Mime::parse(""));
+    }
+
+    #[test]
+    fn test_add_vary() {
+        let mut headers = Headers::new();
+        add_vary(&mut headers, "Origin");
+        add_vary(&mut headers, "Accept-Encoding");
+        add_vary(&mut headers, "origin");
+        assert_eq!(Some("Origin, Accept-Encoding".to_string()), headers.value("Vary"));
+
+        let mut headers = Headers::new();
+        headers.set("vary", "*");
+        add_vary(&mut headers, "Origin");
+        assert_eq!(Some("*".to_string()), headers.value("Vary"));
+    }
 }
@@ -13,47 +13,77 @@ pub mod condition {
     }
 }
 
+pub mod request_id {
+    use std::future::Future;
+
+    tokio::task_local! {
+        static REQUEST_ID: String;
+    }
+
+    ///
+    /// Returns id of the request currently handled by this task, if the server is configured to
+    /// assign request ids. Tasks spawned by the view do not inherit the request id.
+    ///
+    pub fn current() -> Option<String> {
+        REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+    }
+
+    ///
+    /// Runs the future with the request id available from `current`, so log records written while
+    /// handling the request carry the request id.
+    ///
+    pub async fn scope<F: Future>(request_id: String, future: F) -> F::Output {
+        REQUEST_ID.scope(request_id, future).await
+    }
+}
+
+/// Logs the record with the id of the current request prepended, if any.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! racoon_debug {
-    ($($arg:tt)*) => {
+macro_rules! racoon_log {
+    ($level:expr, $($arg:tt)*) => {
         if crate::core::logging::condition::is_logging_enabled() {
-            log::debug!($($arg)*);
+            match crate::core::logging::request_id::current() {
+                Some(request_id) => {
+                    log::log!($level, "[request_id={}] {}", request_id, format_args!($($arg)*));
+                }
+                None => log::log!($level, $($arg)*),
+            }
         }
     }
 }
 
+#[macro_export]
+macro_rules! racoon_debug {
+    ($($arg:tt)*) => {
+        $crate::racoon_log!(log::Level::Debug, $($arg)*)
+    }
+}
+
 #[macro_export]
 macro_rules! racoon_info {
     ($($arg:tt)*) => {
-        if crate::core::logging::condition::is_logging_enabled() {
-            log::info!($($arg)*);
-        }
+        $crate::racoon_log!(log::Level::Info, $($arg)*)
     }
 }
 
 #[macro_export]
 macro_rules! racoon_warn {
     ($($arg:tt)*) => {
-        if use crate::core::logging::condition::is_logging_enabled() {
-            log::warn!($($arg)*);
-        }
+        $crate::racoon_log!(log::Level::Warn, $($arg)*)
     }
 }
 
 #[macro_export]
 macro_rules! racoon_trace {
     ($($arg:tt)*) => {
-        if use crate::core::logging::condition::is_logging_enabled() {
-            log::trace!($($arg)*);
-        }
+        $crate::racoon_log!(log::Level::Trace, $($arg)*)
     }
 }
 
 #[macro_export]
 macro_rules! racoon_error {
     ($($arg:tt)*) => {
-        if crate::core::logging::condition::is_logging_enabled() {
-            log::error!($($arg)*);
-        }
+        $crate::racoon_log!(log::Level::Error, $($arg)*)
     }
 }
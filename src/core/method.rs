@@ -0,0 +1,112 @@
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+///
+/// HTTP request method. Method names are case-sensitive, so `get` is parsed as
+/// `Method::Other("get")` rather than `Method::Get`.
+///
+/// # Examples
+/// ```
+/// use racoon::core::method::Method;
+///
+/// let method: Method = "PATCH".parse().unwrap();
+/// assert_eq!(Method::Patch, method);
+/// assert_eq!(Method::Other("PROPFIND".to_string()), Method::from("PROPFIND"));
+///
+/// // Methods can be compared with the raw method names.
+/// assert_eq!(true, method == "PATCH");
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Head,
+    Options,
+    /// Methods without own variant such as `CONNECT`, `TRACE` or WebDAV extension methods.
+    Other(String),
+}
+
+impl Method {
+    ///
+    /// Returns method name as sent in the request line.
+    ///
+    pub fn as_str(&self) -> &str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+            Method::Other(method) => method,
+        }
+    }
+}
+
+impl From<&str> for Method {
+    fn from(method: &str) -> Self {
+        match method {
+            "GET" => Method::Get,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "PATCH" => Method::Patch,
+            "DELETE" => Method::Delete,
+            "HEAD" => Method::Head,
+            "OPTIONS" => Method::Options,
+            _ => Method::Other(method.to_string()),
+        }
+    }
+}
+
+impl FromStr for Method {
+    type Err = Infallible;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        Ok(Method::from(method))
+    }
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq<str> for Method {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Method {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Method;
+
+    #[test]
+    fn test_method_parse() {
+        let methods = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+        for method in methods {
+            let parsed: Method = method.parse().unwrap();
+            assert_eq!(false, matches!(parsed, Method::Other(_)));
+            assert_eq!(method, parsed.to_string());
+        }
+
+        assert_eq!(Method::Other("get".to_string()), Method::from("get"));
+        assert_eq!("TRACE", Method::from("TRACE").as_str());
+        assert_eq!(true, Method::Delete == "DELETE");
+        assert_eq!(false, Method::Delete == "delete");
+    }
+}
@@ -0,0 +1,274 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::Level;
+
+use crate::core::middleware::{next, MiddlewareFuture};
+use crate::core::path::View;
+use crate::core::request::Request;
+use crate::core::response::Response;
+
+/// Target of the access log records, so they can be filtered separately from the other logs.
+pub const ACCESS_LOG_TARGET: &str = "racoon::access";
+
+///
+/// Function returning extra fields logged for the request, for example user id or route name.
+///
+pub type LogFields =
+    Arc<dyn Fn(&Request, &mut Response) -> Vec<(&'static str, String)> + Send + Sync>;
+
+///
+/// Format of the access log line.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `method=GET path=/ status=200`. Values containing spaces or quotes are quoted.
+    KeyValue,
+    /// `{"method":"GET","path":"/","status":"200"}`
+    Json,
+}
+
+///
+/// Builds middleware logging every request with the `log` crate after the response is returned
+/// from the view. Each line contains `remote_addr`, `method`, `path`, `status`, `bytes` and
+/// `duration_ms` fields, `request_id` if enabled with `Server::request_id`, followed by the fields
+/// returned from the `fields` function.
+///
+/// Records are logged with `racoon::access` target at `Info` level by default.
+///
+/// # Examples
+/// ```
+/// use log::Level;
+/// use racoon::core::headers::HeaderValue;
+/// use racoon::core::middleware::access_log::{AccessLog, LogFormat};
+/// use racoon::core::server::Server;
+///
+/// let access_log = AccessLog::new()
+///     .format(LogFormat::Json)
+///     .level(Level::Info)
+///     .fields(|request, _| {
+///         let user_agent = request.headers.value("User-Agent").unwrap_or_default();
+///         vec![("user_agent", user_agent)]
+///     });
+///
+/// let mut server = Server::bind("127.0.0.1:8080");
+/// server.wrap(access_log.middleware());
+/// ```
+///
+#[derive(Clone)]
+pub struct AccessLog {
+    level: Level,
+    format: LogFormat,
+    fields: Option<LogFields>,
+}
+
+impl AccessLog {
+    pub fn new() -> Self {
+        Self {
+            level: Level::Info,
+            format: LogFormat::KeyValue,
+            fields: None,
+        }
+    }
+
+    ///
+    /// Level of the access log records. Default is `Info`.
+    ///
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    ///
+    /// Format of the access log line. Default is `LogFormat::KeyValue`.
+    ///
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    ///
+    /// Function returning extra fields appended to the log line. It is called after the view
+    /// returns, with the request as it was received by this middleware.
+    ///
+    pub fn fields<F>(mut self, fields: F) -> Self
+    where
+        F: Fn(&Request, &mut Response) -> Vec<(&'static str, String)> + Send + Sync + 'static,
+    {
+        self.fields = Some(Arc::new(fields));
+        self
+    }
+
+    ///
+    /// Returns middleware using this configuration. Each middleware keeps its own configuration,
+    /// for example to log API and admin routes at different levels.
+    ///
+    pub fn middleware(
+        self,
+    ) -> impl Fn(Request, Option<View>) -> MiddlewareFuture + Send + Sync + 'static {
+        let access_log = Arc::new(self);
+        move |request, view| {
+            let access_log = access_log.clone();
+            Box::pin(async move { access_log.handle(request, view).await })
+        }
+    }
+
+    ///
+    /// Passes request to the next middleware and logs the request with its response.
+    ///
+    pub async fn handle(&self, request: Request, view: Option<View>) -> Response {
+        let started = Instant::now();
+        let remote_addr = match request.remote_addr().await {
+            Some(remote_addr) => remote_addr.to_string(),
+            None => "-".to_string(),
+        };
+        let method = request.method.to_string();
+        let path = request.path.clone();
+        let request_id = request.request_id().map(str::to_string);
+
+        // Request is consumed by the view, so it is kept only if needed by the fields function.
+        let logged_request = self.fields.as_ref().map(|_| request.clone());
+        let mut response = next(request, view).await;
+
+        let (status_code, _) = response.status();
+        let mut fields = vec![
+            ("remote_addr", remote_addr),
+            ("method", method),
+            ("path", path),
+            ("status", status_code.to_string()),
+            ("bytes", response.get_body().len().to_string()),
+            ("duration_ms", started.elapsed().as_millis().to_string()),
+        ];
+
+        if let Some(request_id) = request_id {
+            fields.push(("request_id", request_id));
+        }
+
+        if let (Some(extra_fields), Some(request)) = (&self.fields, &logged_request) {
+            fields.extend(extra_fields(request, &mut response));
+        }
+
+        log::log!(target: ACCESS_LOG_TARGET, self.level, "{}", self.format_line(&fields));
+        response
+    }
+
+    ///
+    /// Formats fields to a single log line.
+    ///
+    pub fn format_line(&self, fields: &[(&str, String)]) -> String {
+        match self.format {
+            LogFormat::KeyValue => fields
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, key_value_escape(value)))
+                .collect::<Vec<String>>()
+                .join(" "),
+            LogFormat::Json => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, value)| {
+                        let name = serde_json::Value::from(*name);
+                        let value = serde_json::Value::from(value.as_str());
+                        format!("{}:{}", name, value)
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",");
+                format!("{{{}}}", fields)
+            }
+        }
+    }
+}
+
+impl Default for AccessLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn key_value_escape(value: &str) -> String {
+    if value.is_empty() {
+        return "\"\"".to_string();
+    }
+
+    let needs_quotes = value
+        .chars()
+        .any(|char| char.is_whitespace() || char.is_control() || char == '"' || char == '=');
+
+    if needs_quotes {
+        format!("{:?}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+
+    use crate::core::headers::HeaderValue;
+    use crate::core::middleware::from_fn;
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::{HttpResponse, Response};
+    use crate::core::server::tests::test_state;
+    use crate::core::server::Server;
+    use crate::core::stream::TestStreamWrapper;
+
+    use super::{AccessLog, LogFormat};
+
+    #[test]
+    fn test_format_line() {
+        let fields = vec![
+            ("method", "GET".to_string()),
+            ("path", "/search".to_string()),
+            ("user_agent", "curl/8.0 \"test\"".to_string()),
+            ("user_id", "".to_string()),
+        ];
+
+        let access_log = AccessLog::new();
+        assert_eq!(
+            "method=GET path=/search user_agent=\"curl/8.0 \\\"test\\\"\" user_id=\"\"",
+            access_log.format_line(&fields)
+        );
+
+        let access_log = AccessLog::new().format(LogFormat::Json);
+        assert_eq!(
+            "{\"method\":\"GET\",\"path\":\"/search\",\"user_agent\":\"curl/8.0 \\\"test\\\"\",\
+            \"user_id\":\"\"}",
+            access_log.format_line(&fields)
+        );
+    }
+
+    async fn home(_: Request) -> Response {
+        HttpResponse::ok().body("Hello World")
+    }
+
+    #[tokio::test]
+    async fn test_access_log_middleware() {
+        let view: View = |request| Box::pin(home(request));
+        let middleware = from_fn(
+            AccessLog::new()
+                .fields(|request, response| {
+                    response.get_headers().set("X-Logged", "true");
+                    let user_agent = request.headers.value("User-Agent").unwrap_or_default();
+                    vec![("user_agent", user_agent)]
+                })
+                .middleware(),
+        );
+
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![middleware];
+
+        let request = b"GET / HTTP/1.1\r\nUser-Agent: test\r\n\r\n";
+        let mut stream = TestStreamWrapper::new(request.to_vec(), 1024);
+        stream.set_peer_addr("127.0.0.1:5000".parse::<std::net::SocketAddr>().unwrap());
+        let written_data = stream.written_data();
+        Server::handle_stream(Box::new(stream), Arc::new(state)).await;
+
+        let written_data = written_data.lock().await;
+        let response = String::from_utf8_lossy(&written_data);
+        assert_eq!(true, response.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(true, response.contains("X-Logged: true\r\n"));
+        assert_eq!(true, response.ends_with("Hello World"));
+    }
+}
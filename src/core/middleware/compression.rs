@@ -0,0 +1,393 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use flate2::write::GzEncoder;
+
+use crate::core::headers::{add_vary, encoding_quality, media_type, HeaderValue};
+use crate::core::middleware::{next, MiddlewareFuture};
+use crate::core::path::View;
+use crate::core::request::Request;
+use crate::core::response::Response;
+use crate::racoon_debug;
+
+/// Content codings supported by the server in order of preference.
+#[cfg(feature = "brotli")]
+const SUPPORTED_ENCODINGS: &[&str] = &["br", "gzip"];
+#[cfg(not(feature = "brotli"))]
+const SUPPORTED_ENCODINGS: &[&str] = &["gzip"];
+
+///
+/// Builds middleware compressing response bodies with the best encoding accepted by the client.
+/// Brotli (`br`) is preferred over `gzip` when the `brotli` feature is enabled.
+///
+/// Coding with the highest quality value in `Accept-Encoding` header is used and codings with
+/// `q=0` are never used.
+///
+/// Only responses with compressible content types such as text, JSON, JavaScript or XML and body
+/// of at least minimum size are compressed. Streaming responses, responses already having
+/// `Content-Encoding` and responses with `Cache-Control: no-transform` are sent as they are.
+/// `Vary: Accept-Encoding` is added to the compressible responses, so caches do not serve
+/// compressed body to clients not supporting it.
+///
+/// # Examples
+/// ```
+/// use racoon::core::middleware::compression::Compression;
+/// use racoon::core::server::Server;
+///
+/// let mut server = Server::bind("127.0.0.1:8080");
+/// server.wrap(Compression::new().min_size(512).middleware());
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Compression {
+    min_size: usize,
+    gzip_level: u32,
+    #[cfg_attr(not(feature = "brotli"), allow(dead_code))]
+    brotli_quality: u32,
+}
+
+impl Compression {
+    pub fn new() -> Self {
+        Self {
+            min_size: 1024,
+            gzip_level: 6,
+            brotli_quality: 5,
+        }
+    }
+
+    ///
+    /// Minimum body size in bytes to compress. Default is 1024 bytes, since small bodies do not
+    /// benefit from compression.
+    ///
+    pub fn min_size(mut self, bytes: usize) -> Self {
+        self.min_size = bytes;
+        self
+    }
+
+    ///
+    /// Gzip compression level from 0 to 9. Default is 6.
+    ///
+    pub fn gzip_level(mut self, level: u32) -> Self {
+        self.gzip_level = level.min(9);
+        self
+    }
+
+    ///
+    /// Brotli compression quality from 0 to 11. Default is 5. Used only with `brotli` feature.
+    ///
+    pub fn brotli_quality(mut self, quality: u32) -> Self {
+        self.brotli_quality = quality.min(11);
+        self
+    }
+
+    ///
+    /// Returns middleware using this configuration. Each middleware keeps its own configuration.
+    ///
+    pub fn middleware(
+        self,
+    ) -> impl Fn(Request, Option<View>) -> MiddlewareFuture + Send + Sync + 'static {
+        let compression = Arc::new(self);
+        move |request, view| {
+            let compression = compression.clone();
+            Box::pin(async move { compression.handle(request, view).await })
+        }
+    }
+
+    ///
+    /// Passes request to the next middleware and compresses its response.
+    ///
+    pub async fn handle(&self, request: Request, view: Option<View>) -> Response {
+        let accept_encoding = request.headers.value("Accept-Encoding").unwrap_or_default();
+        let mut response = next(request, view).await;
+        self.compress(&mut response, &accept_encoding);
+        response
+    }
+
+    ///
+    /// Compresses response body with the encoding accepted in `Accept-Encoding` header value.
+    /// Returns true if the body is compressed.
+    ///
+    pub fn compress(&self, response: &mut Response, accept_encoding: &str) -> bool {
+        if !self.is_compressible(response) {
+            return false;
+        }
+
+        add_vary(response.get_headers(), "Accept-Encoding");
+
+        let encoding = match select_encoding(accept_encoding) {
+            Some(encoding) => encoding,
+            None => return false,
+        };
+
+        let body = response.get_body();
+        let result = match encoding {
+            #[cfg(feature = "brotli")]
+            "br" => brotli_compress(body, self.brotli_quality),
+            _ => gzip_compress(body, self.gzip_level),
+        };
+
+        let compressed_body = match result {
+            Ok(compressed_body) => compressed_body,
+            Err(error) => {
+                racoon_debug!("Failed to compress response body. Error: {}", error);
+                return false;
+            }
+        };
+
+        if compressed_body.len() >= body.len() {
+            return false;
+        }
+
+        response.set_body(compressed_body);
+        let headers = response.get_headers();
+        headers.set("Content-Encoding", encoding);
+
+        // Compressed body is a different representation, so strong validator no longer matches.
+        if let Some(etag) = headers.value("ETag") {
+            if !etag.starts_with("W/") {
+                headers.retain(|name, _| !name.eq_ignore_ascii_case("ETag"));
+                headers.set("ETag", format!("W/{}", etag));
+            }
+        }
+        true
+    }
+
+    fn is_compressible(&self, response: &mut Response) -> bool {
+        // Streaming, WebSocket and other custom served responses write their own body.
+        if response.was_custom_served() || !response.serve_default() {
+            return false;
+        }
+
+        let (status_code, _) = response.status();
+        if status_code < 200 || status_code == 204 || status_code == 206 || status_code == 304 {
+            return false;
+        }
+
+        if response.get_body().len() < self.min_size {
+            return false;
+        }
+
+        let headers = response.get_headers();
+        if headers.value("Content-Encoding").is_some() {
+            return false;
+        }
+
+        if let Some(cache_control) = headers.value("Cache-Control") {
+            if cache_control.to_lowercase().contains("no-transform") {
+                return false;
+            }
+        }
+
+        match headers.value("Content-Type") {
+            Some(content_type) => is_compressible_type(&media_type(content_type)),
+            None => false,
+        }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// Returns the content coding supported by the server with the highest quality value in
+/// `Accept-Encoding` header value. Brotli is preferred when the quality values are equal.
+///
+pub fn select_encoding(accept_encoding: &str) -> Option<&'static str> {
+    let mut selected = None;
+    let mut selected_quality = 0.0;
+
+    for encoding in SUPPORTED_ENCODINGS {
+        let quality = encoding_quality(accept_encoding, encoding);
+        if quality > selected_quality {
+            selected = Some(*encoding);
+            selected_quality = quality;
+        }
+    }
+
+    selected
+}
+
+fn is_compressible_type(media_type: &str) -> bool {
+    if media_type.starts_with("text/")
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+    {
+        return true;
+    }
+
+    matches!(
+        media_type,
+        "application/json"
+            | "application/javascript"
+            | "application/xml"
+            | "application/wasm"
+            | "image/svg+xml"
+    )
+}
+
+fn gzip_compress(bytes: &[u8], level: u32) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+#[cfg(feature = "brotli")]
+fn brotli_compress(bytes: &[u8], quality: u32) -> std::io::Result<Vec<u8>> {
+    // Window size of 4 MB
+    const LG_WINDOW_SIZE: u32 = 22;
+
+    let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, quality, LG_WINDOW_SIZE);
+    writer.write_all(bytes)?;
+    writer.flush()?;
+    Ok(writer.into_inner())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use crate::core::headers::HeaderValue;
+    use crate::core::middleware::from_fn;
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::streaming::StreamingResponse;
+    use crate::core::response::{HttpResponse, Response};
+    use crate::core::server::tests::{serve_raw, test_state};
+
+    use super::{select_encoding, Compression};
+
+    fn html_response() -> Response {
+        HttpResponse::ok()
+            .content_type("text/html; charset=utf-8")
+            .body("<p>Hello World</p>".repeat(100))
+    }
+
+    #[test]
+    fn test_select_encoding() {
+        assert_eq!(Some("gzip"), select_encoding("gzip, deflate"));
+        assert_eq!(None, select_encoding("gzip;q=0, deflate"));
+        assert_eq!(None, select_encoding(""));
+        assert_eq!(Some("gzip"), select_encoding("br;q=0, gzip"));
+        assert_eq!(Some("gzip"), select_encoding("br;q=0.5, gzip"));
+        assert_eq!(Some("gzip"), select_encoding("*;q=0.1, gzip;q=0.2"));
+
+        #[cfg(feature = "brotli")]
+        assert_eq!(Some("br"), select_encoding("br, gzip"));
+        #[cfg(feature = "brotli")]
+        assert_eq!(Some("br"), select_encoding("gzip;q=0.5, *"));
+        #[cfg(not(feature = "brotli"))]
+        assert_eq!(Some("gzip"), select_encoding("br, gzip"));
+    }
+
+    #[test]
+    fn test_gzip_compression() {
+        let compression = Compression::new();
+
+        let mut response = html_response();
+        response.get_headers().set("ETag", "\"v1\"");
+        assert_eq!(true, compression.compress(&mut response, "gzip"));
+
+        let headers = response.get_headers();
+        assert_eq!(Some("gzip".to_string()), headers.value("Content-Encoding"));
+        assert_eq!(Some("Accept-Encoding".to_string()), headers.value("Vary"));
+        assert_eq!(Some("W/\"v1\"".to_string()), headers.value("ETag"));
+
+        let content_length = headers.value("Content-Length").unwrap();
+        assert_eq!(response.get_body().len().to_string(), content_length);
+
+        let mut decoded = String::new();
+        let mut decoder = GzDecoder::new(response.get_body().as_slice());
+        decoder.read_to_string(&mut decoded).unwrap();
+        assert_eq!("<p>Hello World</p>".repeat(100), decoded);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli_compression() {
+        let compression = Compression::new();
+
+        let mut response = html_response();
+        assert_eq!(true, compression.compress(&mut response, "br, gzip"));
+        assert_eq!(
+            Some("br".to_string()),
+            response.get_headers().value("Content-Encoding")
+        );
+
+        let mut decoded = String::new();
+        let mut decoder = brotli::Decompressor::new(response.get_body().as_slice(), 4096);
+        decoder.read_to_string(&mut decoded).unwrap();
+        assert_eq!("<p>Hello World</p>".repeat(100), decoded);
+    }
+
+    #[test]
+    fn test_skip_compression() {
+        let compression = Compression::new();
+
+        // Client does not accept compression.
+        let mut response = html_response();
+        assert_eq!(false, compression.compress(&mut response, "identity"));
+        assert_eq!(None, response.get_headers().value("Content-Encoding"));
+        assert_eq!(
+            Some("Accept-Encoding".to_string()),
+            response.get_headers().value("Vary")
+        );
+
+        let mut response: Response = HttpResponse::ok().content_type("text/plain").body("Hi");
+        assert_eq!(false, compression.compress(&mut response, "gzip"));
+
+        let mut response: Response = HttpResponse::ok()
+            .content_type("image/png")
+            .body("0".repeat(2000));
+        assert_eq!(false, compression.compress(&mut response, "gzip"));
+        assert_eq!(None, response.get_headers().value("Vary"));
+
+        let mut response = html_response();
+        response.get_headers().set("Cache-Control", "no-transform");
+        assert_eq!(false, compression.compress(&mut response, "gzip"));
+
+        // Streaming response headers are not modified.
+        let mut response: Response = StreamingResponse::ok()
+            .content_type("text/html")
+            .body(|mut writer| async move { writer.write("<p>Hello World</p>").await });
+        assert_eq!(false, compression.compress(&mut response, "gzip"));
+        assert_eq!(None, response.get_headers().value("Vary"));
+        assert_eq!(None, response.get_headers().value("Content-Encoding"));
+    }
+
+    async fn home(_: Request) -> Response {
+        html_response()
+    }
+
+    #[tokio::test]
+    async fn test_compression_middleware() {
+        let view: View = |request| Box::pin(home(request));
+        let compression = from_fn(Compression::new().min_size(100).middleware());
+
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![compression.clone()];
+        let request = b"GET / HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.contains("Content-Encoding: gzip\r\n"));
+        assert_eq!(true, response.contains("Vary: Accept-Encoding\r\n"));
+
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![compression];
+        let response = serve_raw(state, b"GET / HTTP/1.1\r\n\r\n").await;
+        assert_eq!(false, response.contains("Content-Encoding"));
+        assert_eq!(true, response.ends_with("<p>Hello World</p>"));
+
+        // Other middleware keeps its own configuration.
+        let compression = from_fn(Compression::new().min_size(1_000_000).middleware());
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![compression];
+        let request = b"GET / HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(false, response.contains("Content-Encoding"));
+    }
+}
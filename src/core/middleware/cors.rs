@@ -0,0 +1,391 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::headers::{add_vary, HeaderValue};
+use crate::core::method::Method;
+use crate::core::middleware::{next, MiddlewareFuture};
+use crate::core::path::View;
+use crate::core::request::Request;
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::{HttpResponse, Response};
+
+///
+/// Builds middleware adding Cross-Origin Resource Sharing headers to the responses. Preflight
+/// `OPTIONS` requests are answered with `204 No Content` without calling the view.
+///
+/// No origin is allowed by default. Origins are compared case-insensitively and the matched origin
+/// is echoed in `Access-Control-Allow-Origin` header.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use racoon::core::middleware::cors::Cors;
+/// use racoon::core::server::Server;
+///
+/// let cors = Cors::new()
+///     .allow_origin("https://example.com")
+///     .allow_origin("https://admin.example.com")
+///     .allow_headers(&["Content-Type", "Authorization"])
+///     .allow_credentials(true)
+///     .max_age(Duration::from_secs(3600));
+///
+/// let mut server = Server::bind("127.0.0.1:8080");
+/// server.wrap(cors.middleware());
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Cors {
+    /// `None` allows any origin.
+    allowed_origins: Option<Vec<String>>,
+    allowed_methods: Vec<String>,
+    /// `None` allows headers requested in the preflight request.
+    allowed_headers: Option<Vec<String>>,
+    exposed_headers: Vec<String>,
+    allow_credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl Cors {
+    pub fn new() -> Self {
+        let allowed_methods = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"];
+
+        Self {
+            allowed_origins: Some(vec![]),
+            allowed_methods: allowed_methods.iter().map(|method| method.to_string()).collect(),
+            allowed_headers: None,
+            exposed_headers: vec![],
+            allow_credentials: false,
+            max_age: None,
+        }
+    }
+
+    ///
+    /// Adds origin to the allowlist, e.g. `https://example.com`.
+    ///
+    pub fn allow_origin<S: AsRef<str>>(mut self, origin: S) -> Self {
+        let origin = origin.as_ref().trim_end_matches('/').to_string();
+
+        match self.allowed_origins.as_mut() {
+            Some(allowed_origins) => allowed_origins.push(origin),
+            None => self.allowed_origins = Some(vec![origin]),
+        }
+        self
+    }
+
+    ///
+    /// Allows requests from all the origins with `Access-Control-Allow-Origin: *`. With
+    /// credentials enabled, the request origin is echoed instead because browsers reject `*`.
+    ///
+    pub fn allow_any_origin(mut self) -> Self {
+        self.allowed_origins = None;
+        self
+    }
+
+    ///
+    /// Replaces allowed methods. Default is `GET`, `HEAD`, `POST`, `PUT`, `PATCH` and `DELETE`.
+    ///
+    pub fn allow_methods<S: AsRef<str>>(mut self, methods: &[S]) -> Self {
+        self.allowed_methods = methods
+            .iter()
+            .map(|method| method.as_ref().to_uppercase())
+            .collect();
+        self
+    }
+
+    ///
+    /// Replaces allowed request headers. By default, headers requested by the browser in the
+    /// preflight request are allowed.
+    ///
+    pub fn allow_headers<S: AsRef<str>>(mut self, headers: &[S]) -> Self {
+        self.allowed_headers = Some(
+            headers
+                .iter()
+                .map(|header| header.as_ref().to_string())
+                .collect(),
+        );
+        self
+    }
+
+    ///
+    /// Response headers readable by the browser scripts in addition to the safelisted ones.
+    ///
+    pub fn expose_headers<S: AsRef<str>>(mut self, headers: &[S]) -> Self {
+        self.exposed_headers = headers
+            .iter()
+            .map(|header| header.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    ///
+    /// Allows cookies and `Authorization` header in cross-origin requests.
+    ///
+    pub fn allow_credentials(mut self, allow: bool) -> Self {
+        self.allow_credentials = allow;
+        self
+    }
+
+    ///
+    /// Duration for which browsers may cache the preflight response.
+    ///
+    pub fn max_age(mut self, duration: Duration) -> Self {
+        self.max_age = Some(duration);
+        self
+    }
+
+    ///
+    /// Returns middleware using this configuration. Each middleware keeps its own configuration,
+    /// so servers in the same process can allow different origins.
+    ///
+    pub fn middleware(
+        self,
+    ) -> impl Fn(Request, Option<View>) -> MiddlewareFuture + Send + Sync + 'static {
+        let cors = Arc::new(self);
+        move |request, view| {
+            let cors = cors.clone();
+            Box::pin(async move { cors.handle(request, view).await })
+        }
+    }
+
+    ///
+    /// Handles preflight request or passes request to the next middleware and adds CORS headers to
+    /// its response.
+    ///
+    pub async fn handle(&self, request: Request, view: Option<View>) -> Response {
+        let origin = match request.headers.value("Origin") {
+            Some(origin) => origin,
+            None => return next(request, view).await,
+        };
+
+        let is_preflight = request.method == Method::Options
+            && request.headers.value("Access-Control-Request-Method").is_some();
+
+        if is_preflight {
+            let mut response: Response = HttpResponse::no_content().empty();
+            let headers = response.get_headers();
+            add_vary(headers, "Origin");
+
+            if let Some(allow_origin) = self.allow_origin_value(&origin) {
+                headers.set("Access-Control-Allow-Origin", allow_origin);
+                headers.set(
+                    "Access-Control-Allow-Methods",
+                    self.allowed_methods.join(", "),
+                );
+
+                let allowed_headers = match &self.allowed_headers {
+                    Some(allowed_headers) => Some(allowed_headers.join(", ")),
+                    None => {
+                        add_vary(headers, "Access-Control-Request-Headers");
+                        request.headers.value("Access-Control-Request-Headers")
+                    }
+                };
+
+                if let Some(allowed_headers) = allowed_headers {
+                    if !allowed_headers.is_empty() {
+                        headers.set("Access-Control-Allow-Headers", allowed_headers);
+                    }
+                }
+
+                if self.allow_credentials {
+                    headers.set("Access-Control-Allow-Credentials", "true");
+                }
+
+                if let Some(max_age) = self.max_age {
+                    headers.set("Access-Control-Max-Age", max_age.as_secs().to_string());
+                }
+            }
+            return response;
+        }
+
+        let allow_origin = self.allow_origin_value(&origin);
+        let mut response = next(request, view).await;
+        let headers = response.get_headers();
+
+        if allow_origin.as_deref() != Some("*") {
+            add_vary(headers, "Origin");
+        }
+
+        if let Some(allow_origin) = allow_origin {
+            headers.set("Access-Control-Allow-Origin", allow_origin);
+
+            if self.allow_credentials {
+                headers.set("Access-Control-Allow-Credentials", "true");
+            }
+
+            if !self.exposed_headers.is_empty() {
+                headers.set(
+                    "Access-Control-Expose-Headers",
+                    self.exposed_headers.join(", "),
+                );
+            }
+        }
+        response
+    }
+
+    ///
+    /// Returns value of `Access-Control-Allow-Origin` header if the origin is allowed.
+    ///
+    fn allow_origin_value(&self, origin: &str) -> Option<String> {
+        match &self.allowed_origins {
+            None if self.allow_credentials => Some(origin.to_string()),
+            None => Some("*".to_string()),
+            Some(allowed_origins) => {
+                let is_allowed = allowed_origins
+                    .iter()
+                    .any(|allowed_origin| allowed_origin.eq_ignore_ascii_case(origin));
+
+                if is_allowed {
+                    Some(origin.to_string())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use crate::core::middleware::from_fn;
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::{HttpResponse, Response};
+    use crate::core::server::tests::{serve_raw, test_state};
+
+    use super::Cors;
+
+    async fn home(_: Request) -> Response {
+        HttpResponse::ok().body("Home")
+    }
+
+    #[tokio::test]
+    async fn test_cors() {
+        let view: View = |request| Box::pin(home(request));
+        let cors = from_fn(
+            Cors::new()
+                .allow_origin("https://Example.com")
+                .allow_methods(&["GET", "post"])
+                .expose_headers(&["X-Total-Count"])
+                .allow_credentials(true)
+                .max_age(Duration::from_secs(600))
+                .middleware(),
+        );
+
+        // Preflight request does not reach the view.
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![cors.clone()];
+        let request = b"OPTIONS / HTTP/1.1\r\nOrigin: https://example.com\r\n\
+            Access-Control-Request-Method: POST\r\n\
+            Access-Control-Request-Headers: content-type\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 204"));
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Allow-Origin: https://example.com\r\n")
+        );
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Allow-Methods: GET, POST\r\n")
+        );
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Allow-Headers: content-type\r\n")
+        );
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Allow-Credentials: true\r\n")
+        );
+        assert_eq!(true, response.contains("Access-Control-Max-Age: 600\r\n"));
+        assert_eq!(false, response.contains("Home"));
+
+        // Preflight request to the path without OPTIONS view.
+        let mut state = test_state(vec![Path::route("/").post(view)]).await;
+        state.middlewares = vec![cors.clone()];
+        let request = b"OPTIONS / HTTP/1.1\r\nOrigin: https://example.com\r\n\
+            Access-Control-Request-Method: POST\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 204"));
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Allow-Origin: https://example.com\r\n")
+        );
+
+        // Actual request
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![cors.clone()];
+        let request = b"GET / HTTP/1.1\r\nOrigin: https://example.com\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 200"));
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Allow-Origin: https://example.com\r\n")
+        );
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Expose-Headers: X-Total-Count\r\n")
+        );
+        assert_eq!(true, response.contains("Vary: Origin\r\n"));
+        assert_eq!(true, response.ends_with("Home"));
+
+        // Origin not in the allowlist
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![cors.clone()];
+        let request = b"OPTIONS / HTTP/1.1\r\nOrigin: https://evil.com\r\n\
+            Access-Control-Request-Method: POST\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 204"));
+        assert_eq!(false, response.contains("Access-Control-Allow-Origin"));
+
+        // Requests without Origin header are not modified.
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![cors.clone()];
+        let response = serve_raw(state, b"GET / HTTP/1.1\r\n\r\n").await;
+        assert_eq!(false, response.contains("Access-Control"));
+        assert_eq!(true, response.ends_with("Home"));
+
+        // Other middleware keeps its own configuration.
+        let other = from_fn(Cors::new().allow_origin("https://other.com").middleware());
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![other];
+        let request = b"GET / HTTP/1.1\r\nOrigin: https://example.com\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(false, response.contains("Access-Control-Allow-Origin"));
+
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.middlewares = vec![cors];
+        let response = serve_raw(state, request).await;
+        assert_eq!(
+            true,
+            response.contains("Access-Control-Allow-Origin: https://example.com\r\n")
+        );
+    }
+
+    #[test]
+    fn test_allow_origin_value() {
+        let cors = Cors::new().allow_any_origin();
+        assert_eq!(
+            Some("*".to_string()),
+            cors.allow_origin_value("https://example.com")
+        );
+
+        // Wildcard is not allowed with credentials.
+        let cors = Cors::new().allow_any_origin().allow_credentials(true);
+        assert_eq!(
+            Some("https://example.com".to_string()),
+            cors.allow_origin_value("https://example.com")
+        );
+
+        let cors = Cors::new();
+        assert_eq!(None, cors.allow_origin_value("https://example.com"));
+    }
+}
@@ -1,11 +1,72 @@
+pub mod access_log;
+pub mod compression;
+pub mod cors;
+
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 
-use crate::core::path::View;
+use crate::core::path::{Path, View};
 use crate::core::request::Request;
-use crate::core::response::AbstractResponse;
+use crate::core::response::Response;
+
+///
+/// Function receiving request before the view. Call `next` (or `Path::resolve`) to pass the
+/// request to the following middleware or the view, or return response directly to stop the
+/// request from reaching the view.
+///
+/// # Examples
+/// ```
+/// use racoon::core::middleware::next;
+/// use racoon::core::path::View;
+/// use racoon::core::request::Request;
+/// use racoon::core::response::{HttpResponse, Response};
+/// use racoon::core::response::status::ResponseStatus;
+/// use racoon::core::server::Server;
+/// use racoon::wrap_view;
+///
+/// async fn logger(request: Request, view: Option<View>) -> Response {
+///     println!("{} {}", request.method, request.path);
+///     next(request, view).await
+/// }
+///
+/// async fn auth(request: Request, view: Option<View>) -> Response {
+///     if request.session.get("user_id").await.is_none() {
+///         return HttpResponse::unauthorized().body("Login required.");
+///     }
+///     next(request, view).await
+/// }
+///
+/// let mut server = Server::bind("127.0.0.1:8080");
+/// // Logger wraps auth middleware.
+/// server.wrap(wrap_view!(logger)).wrap(wrap_view!(auth));
+/// ```
+///
+/// Middleware may keep state, for example the configuration of the middleware returned from
+/// `Cors::middleware`, so servers in the same process can use different configurations.
+///
+pub type Middleware = Arc<dyn Fn(Request, Option<View>) -> MiddlewareFuture + Send + Sync>;
+
+/// Future returned from the middleware.
+pub type MiddlewareFuture = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+///
+/// Creates `Middleware` from the function, for example to keep the middleware before passing it
+/// to the server. `Server::wrap` accepts the functions directly.
+///
+pub fn from_fn<F>(middleware: F) -> Middleware
+where
+    F: Fn(Request, Option<View>) -> MiddlewareFuture + Send + Sync + 'static,
+{
+    Arc::new(middleware)
+}
 
-pub type Middleware = fn(Request, Option<View>) -> Pin<Box<dyn Future<Output=Box<dyn AbstractResponse>> + Send>>;
+///
+/// Passes request to the next middleware in the chain, or to the view if no middleware is left.
+///
+pub async fn next(request: Request, view: Option<View>) -> Response {
+    Path::resolve(request, view).await
+}
 
 #[macro_export]
 macro_rules! wrap_view {
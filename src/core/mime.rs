@@ -0,0 +1,60 @@
+use std::path::Path;
+
+///
+/// Returns content type for the file extension. Unknown extensions are served as
+/// `application/octet-stream`.
+///
+pub fn from_extension<S: AsRef<str>>(extension: S) -> &'static str {
+    match extension.as_ref().to_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "webmanifest" => "application/manifest+json",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+///
+/// Returns content type for the file path based on its extension.
+///
+pub fn from_path<P: AsRef<Path>>(path: P) -> &'static str {
+    match path.as_ref().extension() {
+        Some(extension) => from_extension(extension.to_string_lossy()),
+        None => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::from_path;
+
+    #[test]
+    fn test_mime_from_path() {
+        assert_eq!("text/html; charset=utf-8", from_path("dist/index.html"));
+        assert_eq!("image/png", from_path("logo.PNG"));
+        assert_eq!("application/octet-stream", from_path("LICENSE"));
+    }
+}
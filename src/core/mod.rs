@@ -9,7 +9,12 @@ pub mod stream;
 pub mod logging;
 pub mod middleware;
 pub mod headers;
+pub mod method;
 pub mod forms;
 
 pub mod websocket;
 pub mod shortcuts;
+pub mod mime;
+pub mod static_files;
+pub mod testing;
+pub mod transaction;
@@ -0,0 +1,75 @@
+/// Characters for windows-1252 bytes from 0x80 to 0x9F. Remaining bytes are same as latin-1.
+/// Undefined positions are mapped to the C1 control characters same as browsers do.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Utf8,
+    Latin1,
+    Windows1252,
+}
+
+impl Charset {
+    ///
+    /// Returns charset for the given label. Returns `None` if the charset is not supported.
+    ///
+    pub fn from_label<S: AsRef<str>>(label: S) -> Option<Self> {
+        let label = label.as_ref().trim().trim_matches('"').to_lowercase();
+
+        match label.as_str() {
+            "utf-8" | "utf8" | "unicode-1-1-utf-8" | "us-ascii" | "ascii" => Some(Self::Utf8),
+            "iso-8859-1" | "iso8859-1" | "latin1" | "latin-1" | "l1" => Some(Self::Latin1),
+            "windows-1252" | "cp1252" | "x-cp1252" => Some(Self::Windows1252),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Decodes bytes to text. Returns error if the bytes are not valid for the charset.
+    ///
+    pub fn decode(&self, bytes: &[u8]) -> std::io::Result<String> {
+        match self {
+            Self::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(text) => Ok(text.to_string()),
+                Err(error) => Err(std::io::Error::other(format!(
+                    "Invalid UTF-8 value. Error: {}",
+                    error
+                ))),
+            },
+            Self::Latin1 => Ok(bytes.iter().map(|byte| *byte as char).collect()),
+            Self::Windows1252 => Ok(bytes
+                .iter()
+                .map(|byte| match byte {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+                    _ => *byte as char,
+                })
+                .collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Charset;
+
+    #[test]
+    fn test_charset_from_label() {
+        assert_eq!(Some(Charset::Utf8), Charset::from_label("UTF-8"));
+        assert_eq!(Some(Charset::Latin1), Charset::from_label("\"ISO-8859-1\""));
+        assert_eq!(Some(Charset::Windows1252), Charset::from_label("windows-1252"));
+        assert_eq!(None, Charset::from_label("shift_jis"));
+    }
+
+    #[test]
+    fn test_charset_decode() {
+        assert_eq!("José", Charset::Latin1.decode(b"Jos\xe9").unwrap());
+        assert_eq!("€5", Charset::Windows1252.decode(b"\x805").unwrap());
+        assert_eq!("José", Charset::Utf8.decode("José".as_bytes()).unwrap());
+        assert_eq!(true, Charset::Utf8.decode(b"Jos\xe9").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use crate::core::forms::FormFieldError;
+use crate::core::headers::{HeaderValue, Headers};
+use crate::core::stream::Stream;
+
+/// Maximum length of the chunk size line including chunk extensions.
+const MAX_CHUNK_SIZE_LINE: usize = 1024;
+
+///
+/// Returns true if the request body is sent with `Transfer-Encoding: chunked`. Chunked must be
+/// the last transfer coding applied to the body.
+///
+pub fn is_chunked(headers: &Headers) -> bool {
+    match headers.value("Transfer-Encoding") {
+        Some(value) => value
+            .rsplit(',')
+            .next()
+            .map(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+///
+/// Reads body sent with chunked transfer coding from the stream and returns the decoded body
+/// with the bytes received after the body, which belong to the next request. Chunk extensions
+/// and trailer fields are ignored.
+///
+/// Returns `FormFieldError::MaxBodySizeExceed` if the decoded body is larger than
+/// `max_body_size`.
+///
+pub async fn read_chunked_body(
+    stream: &Arc<Stream>,
+    max_body_size: usize,
+) -> Result<(Vec<u8>, Vec<u8>), FormFieldError> {
+    let mut decoder = ChunkedDecoder {
+        stream,
+        buffer: vec![],
+    };
+    let mut body = vec![];
+
+    loop {
+        let size_line = decoder.read_line().await?;
+
+        // Chunk extensions after ";" are ignored.
+        let size_text = size_line.split(';').next().unwrap_or_default().trim();
+        let chunk_size = match usize::from_str_radix(size_text, 16) {
+            Ok(chunk_size) => chunk_size,
+            Err(_) => {
+                return Err(invalid_chunk(format!("Invalid chunk size: {}", size_text)));
+            }
+        };
+
+        if chunk_size == 0 {
+            break;
+        }
+
+        // Chunk size is sent by the client, so it must not be used in unchecked arithmetic.
+        if chunk_size > max_body_size.saturating_sub(body.len()) {
+            return Err(FormFieldError::MaxBodySizeExceed);
+        }
+
+        let chunk_len = match chunk_size.checked_add(2) {
+            Some(chunk_len) => chunk_len,
+            None => return Err(FormFieldError::MaxBodySizeExceed),
+        };
+        decoder.fill(chunk_len).await?;
+        if !decoder.buffer[chunk_size..].starts_with(b"\r\n") {
+            return Err(invalid_chunk(
+                "Chunk data is not followed by CRLF.".to_string(),
+            ));
+        }
+
+        body.extend(decoder.buffer.drain(..chunk_size));
+        decoder.buffer.drain(..2);
+    }
+
+    // Trailer fields end with an empty line.
+    loop {
+        let line = decoder.read_line().await?;
+        if line.is_empty() {
+            break;
+        }
+    }
+
+    Ok((body, decoder.buffer))
+}
+
+struct ChunkedDecoder<'a> {
+    stream: &'a Arc<Stream>,
+    /// Bytes received but not decoded yet.
+    buffer: Vec<u8>,
+}
+
+impl ChunkedDecoder<'_> {
+    ///
+    /// Reads from the stream until the buffer contains at least `size` bytes.
+    ///
+    async fn fill(&mut self, size: usize) -> Result<(), FormFieldError> {
+        while self.buffer.len() < size {
+            let chunk = match self.stream.read_chunk().await {
+                Ok(chunk) => chunk,
+                Err(error) => {
+                    return Err(FormFieldError::Others(None, error.to_string(), true));
+                }
+            };
+            self.buffer.extend(chunk);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Removes line ending with CRLF from the buffer and returns it without CRLF.
+    ///
+    async fn read_line(&mut self) -> Result<String, FormFieldError> {
+        loop {
+            if let Some(position) = self.buffer.windows(2).position(|bytes| bytes == b"\r\n") {
+                let line: Vec<u8> = self.buffer.drain(..position + 2).take(position).collect();
+                return Ok(String::from_utf8_lossy(&line).to_string());
+            }
+
+            if self.buffer.len() > MAX_CHUNK_SIZE_LINE {
+                return Err(invalid_chunk("Chunk size line is too long.".to_string()));
+            }
+
+            self.fill(self.buffer.len() + 1).await?;
+        }
+    }
+}
+
+fn invalid_chunk(message: String) -> FormFieldError {
+    FormFieldError::Others(None, message, false)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+
+    use crate::core::forms::FormFieldError;
+    use crate::core::headers::{HeaderValue, Headers};
+    use crate::core::stream::{AbstractStream, TestStreamWrapper};
+
+    use super::{is_chunked, read_chunked_body};
+
+    #[test]
+    fn test_is_chunked() {
+        let mut headers = Headers::new();
+        assert_eq!(false, is_chunked(&headers));
+
+        headers.set("Transfer-Encoding", "gzip, Chunked");
+        assert_eq!(true, is_chunked(&headers));
+
+        headers.set("Transfer-Encoding", "chunked, gzip");
+        assert_eq!(false, is_chunked(&headers));
+    }
+
+    #[tokio::test]
+    async fn test_read_chunked_body() {
+        let test_data = b"5;name=value\r\nHello\r\n6\r\n World\r\n0\r\nX-Trailer: 1\r\n\r\nGET /";
+
+        for buffer_size in [1, 7, 1024] {
+            let stream: Box<dyn AbstractStream> =
+                Box::new(TestStreamWrapper::new(test_data.to_vec(), buffer_size));
+            let result = read_chunked_body(&Arc::new(stream), 1024).await;
+
+            let (body, remaining) = result.unwrap();
+            assert_eq!(b"Hello World".to_vec(), body);
+            // Rest of the bytes are not read from the stream yet with smaller buffer sizes.
+            assert_eq!(true, b"GET /".starts_with(&remaining));
+        }
+
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(test_data.to_vec(), 1024));
+        let result = read_chunked_body(&Arc::new(stream), 10).await;
+        assert_eq!(
+            true,
+            matches!(result, Err(FormFieldError::MaxBodySizeExceed))
+        );
+
+        // Oversized chunk size must not overflow the body size check.
+        let test_data = b"5\r\nHello\r\nffffffffffffffff\r\nWorld\r\n0\r\n\r\n".to_vec();
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result = read_chunked_body(&Arc::new(stream), usize::MAX).await;
+        assert_eq!(
+            true,
+            matches!(result, Err(FormFieldError::MaxBodySizeExceed))
+        );
+
+        let test_data = b"5\r\nHelloWorld\r\n0\r\n\r\n".to_vec();
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result = read_chunked_body(&Arc::new(stream), 1024).await;
+        assert_eq!(
+            true,
+            matches!(result, Err(FormFieldError::Others(_, _, false)))
+        );
+    }
+}
@@ -1,11 +1,14 @@
+pub mod charset;
+pub mod chunked;
 pub mod multipart;
 pub mod urlencoded;
 
 pub mod headers {
     use std::collections::HashMap;
+    use std::io::ErrorKind;
     use std::sync::Arc;
 
-    use crate::core::headers::{Headers, HeaderValue};
+    use crate::core::headers::{Headers, HeaderValue, RawHeaders};
     use crate::core::request::RequestError;
     use crate::core::server::RequestConstraints;
     use crate::core::stream::Stream;
@@ -16,6 +19,7 @@ pub mod headers {
         pub http_version: Option<u8>,
         pub raw_path: Option<String>,
         pub headers: Headers,
+        pub raw_headers: RawHeaders,
     }
 
     impl RequestHeaderResult {
@@ -25,6 +29,7 @@ pub mod headers {
                 http_version: None,
                 raw_path: None,
                 headers: HashMap::new(),
+                raw_headers: vec![],
             }
         }
     }
@@ -41,6 +46,9 @@ pub mod headers {
         loop {
             let chunk = match stream.read_chunk().await {
                 Ok(bytes) => bytes,
+                Err(error) if error.kind() == ErrorKind::TimedOut => {
+                    return Err(RequestError::TimedOut);
+                }
                 Err(error) => {
                     return Err(RequestError::Others(error.to_string()));
                 }
@@ -88,8 +96,10 @@ pub mod headers {
                     }
 
                     let mut headers = HashMap::new();
+                    let mut raw_headers = Vec::with_capacity(request.headers.len());
                     request.headers.iter().for_each(|header| {
                         headers.set_multiple(header.name, header.value);
+                        raw_headers.push((header.name.to_string(), header.value.to_vec()));
                     });
 
                     if status.is_complete() {
@@ -98,6 +108,7 @@ pub mod headers {
                             http_version,
                             raw_path: path,
                             headers,
+                            raw_headers,
                         });
                     }
                 }
@@ -139,6 +150,7 @@ pub mod path {
 
 pub mod params {
     use std::collections::HashMap;
+    use crate::core::parser::charset::Charset;
     use crate::core::parser::path::path_and_raw_query;
 
     ///
@@ -189,14 +201,114 @@ pub mod params {
                     Err(_) => value.to_string()
                 };
 
-                if !params.contains_key(&name_formatted) {
-                    params.insert(name.to_string(), Vec::new());
-                }
-
-                let values = params.get_mut(&name_formatted).unwrap();
-                values.push(value_formatted);
+                params
+                    .entry(name_formatted)
+                    .or_insert_with(Vec::new)
+                    .push(value_formatted);
             }
         }
         return params;
     }
+
+    ///
+    /// Parses `application/x-www-form-urlencoded` body. Percent decoded bytes are decoded with
+    /// the given charset. If charset is not specified, `_charset_` field value is used if present,
+    /// otherwise UTF-8 is used.
+    ///
+    /// Returns error if the charset is not supported or the values are invalid for the charset.
+    ///
+    pub fn parse_url_encoded_bytes(
+        bytes: &[u8],
+        charset: Option<Charset>,
+    ) -> std::io::Result<HashMap<String, Vec<String>>> {
+        let mut raw_params = vec![];
+
+        for pair in bytes.split(|byte| *byte == b'&') {
+            let mut key_value = pair.splitn(2, |byte| *byte == b'=');
+            let name = key_value.next().unwrap_or_default();
+
+            if let Some(value) = key_value.next() {
+                raw_params.push((form_decode(name), form_decode(value)));
+            }
+        }
+
+        let charset = match charset {
+            Some(charset) => charset,
+            None => {
+                let declared = raw_params
+                    .iter()
+                    .find(|(name, _)| name.as_slice() == b"_charset_");
+
+                if let Some((_, label)) = declared {
+                    let label = String::from_utf8_lossy(label);
+                    match Charset::from_label(label.as_ref()) {
+                        Some(charset) => charset,
+                        None => {
+                            return Err(std::io::Error::other(format!(
+                                "Unsupported charset: {}",
+                                label
+                            )));
+                        }
+                    }
+                } else {
+                    Charset::Utf8
+                }
+            }
+        };
+
+        let mut params = HashMap::new();
+        for (name, value) in raw_params {
+            params
+                .entry(charset.decode(&name)?)
+                .or_insert_with(Vec::new)
+                .push(charset.decode(&value)?);
+        }
+
+        Ok(params)
+    }
+
+    /// Replaces `+` with space and decodes percent encoded bytes.
+    fn form_decode(bytes: &[u8]) -> Vec<u8> {
+        let replaced: Vec<u8> = bytes
+            .iter()
+            .map(|byte| if *byte == b'+' { b' ' } else { *byte })
+            .collect();
+        urlencoding::decode_binary(&replaced).into_owned()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+
+    use crate::core::server::RequestConstraints;
+    use crate::core::stream::{AbstractStream, TestStreamWrapper};
+
+    use super::headers::read_request_headers;
+
+    #[tokio::test]
+    async fn test_raw_headers_order() {
+        let request_bytes = b"GET / HTTP/1.1\r\nX-Amz-Date: 20240101T000000Z\r\nhost: example.com\r\nX-Custom: a\r\nx-custom: b\r\n\r\n";
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(request_bytes.to_vec(), 1024));
+
+        let request_constraints = Arc::new(RequestConstraints {
+            max_request_header_size: 1024,
+            max_header_count: 10,
+        });
+
+        let result = read_request_headers(Arc::new(stream), request_constraints)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                ("X-Amz-Date".to_string(), b"20240101T000000Z".to_vec()),
+                ("host".to_string(), b"example.com".to_vec()),
+                ("X-Custom".to_string(), b"a".to_vec()),
+                ("x-custom".to_string(), b"b".to_vec()),
+            ],
+            result.raw_headers
+        );
+    }
 }
@@ -1,15 +1,20 @@
+use std::io::Write;
 use std::sync::Arc;
 
 use async_tempfile::TempFile;
 use regex::Regex;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::Sender;
 
 use crate::core::headers;
-use crate::core::headers::{HeaderValue, Headers};
+use crate::core::headers::{header_param, HeaderValue, Headers};
+use crate::core::parser::charset::Charset;
 
 use crate::core::stream::Stream;
 
-use crate::core::forms::{FileField, Files, FormConstraints, FormData, FormFieldError};
+use crate::core::forms::{
+    FileField, Files, FormConstraints, FormData, FormFieldError, PartValue, UploadProgress,
+};
 
 #[derive(Debug)]
 pub struct FormPart {
@@ -26,6 +31,15 @@ pub struct MultipartParser {
     boundary: String,
     allow_next_header_read: bool,
     first_header_scanned: bool,
+    /// Close delimiter is reached, so no parts are left.
+    completed: bool,
+    charset: Charset,
+    content_length: Option<usize>,
+    /// Body bytes received from the stream excluding bytes restored back to the stream.
+    bytes_received: usize,
+    /// Number of part headers read, limited by the max fields of the form constraints.
+    parts_read: usize,
+    progress_sender: Option<Sender<UploadProgress>>,
 }
 
 impl MultipartParser {
@@ -43,20 +57,105 @@ impl MultipartParser {
 
         let boundary = headers::multipart_boundary(&content_type)?;
 
+        let content_length = match headers.value("content-length") {
+            Some(value) => value.trim().parse().ok(),
+            None => None,
+        };
+
         Ok(MultipartParser {
             stream,
             form_constraints,
             boundary,
             allow_next_header_read: true,
             first_header_scanned: false,
+            completed: false,
+            charset: Charset::Utf8,
+            content_length,
+            bytes_received: 0,
+            parts_read: 0,
+            progress_sender: None,
         })
     }
 
+    ///
+    /// Sends `UploadProgress` to the channel while file parts are being written. Progress is not
+    /// reported if the channel is full, so the upload is never blocked by a slow receiver.
+    ///
+    pub fn set_progress_sender(&mut self, sender: Sender<UploadProgress>) {
+        self.progress_sender = Some(sender);
+    }
+
     pub async fn parse(
         stream: Arc<Stream>,
         form_constraints: Arc<FormConstraints>,
         headers: &Headers,
     ) -> Result<(FormData, Files), FormFieldError> {
+        Self::parse_with_progress(stream, form_constraints, headers, None).await
+    }
+
+    pub async fn parse_with_progress(
+        stream: Arc<Stream>,
+        form_constraints: Arc<FormConstraints>,
+        headers: &Headers,
+        progress_sender: Option<Sender<UploadProgress>>,
+    ) -> Result<(FormData, Files), FormFieldError> {
+        let parts =
+            Self::parse_ordered_with_progress(stream, form_constraints, headers, progress_sender)
+                .await?;
+
+        let mut form_data = FormData::new();
+        let mut files = Files::new();
+
+        for (field_name, value) in parts {
+            match value {
+                PartValue::Text(value) => form_data.entry(field_name).or_default().push(value),
+                PartValue::File(file) => files.entry(field_name).or_default().push(file),
+            }
+        }
+
+        Ok((form_data, files))
+    }
+
+    ///
+    /// Returns field names and values of the parts in the order they were sent, for protocols or
+    /// signature schemes depending on the field order. Parts without field name are skipped.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::forms::{FormFieldError, PartValue};
+    /// use racoon::core::parser::multipart::MultipartParser;
+    /// use racoon::core::request::Request;
+    ///
+    /// async fn signed_form(request: &Request) -> Result<(), FormFieldError> {
+    ///     let stream = request.stream.clone();
+    ///     let form_constraints = request.form_constraints.clone();
+    ///     let parts = MultipartParser::parse_ordered(stream, form_constraints, &request.headers)
+    ///         .await?;
+    ///
+    ///     for (name, value) in parts {
+    ///         match value {
+    ///             PartValue::Text(text) => println!("{} = {}", name, text),
+    ///             PartValue::File(file) => println!("{} = file {}", name, file.name),
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    pub async fn parse_ordered(
+        stream: Arc<Stream>,
+        form_constraints: Arc<FormConstraints>,
+        headers: &Headers,
+    ) -> Result<Vec<(String, PartValue)>, FormFieldError> {
+        Self::parse_ordered_with_progress(stream, form_constraints, headers, None).await
+    }
+
+    pub async fn parse_ordered_with_progress(
+        stream: Arc<Stream>,
+        form_constraints: Arc<FormConstraints>,
+        headers: &Headers,
+        progress_sender: Option<Sender<UploadProgress>>,
+    ) -> Result<Vec<(String, PartValue)>, FormFieldError> {
         let mut parser = match MultipartParser::from(stream, headers, form_constraints) {
             Ok(parser) => parser,
             Err(error) => {
@@ -64,8 +163,19 @@ impl MultipartParser {
             }
         };
 
-        let mut form_data = FormData::new();
-        let mut files = Files::new();
+        if let Some(sender) = progress_sender {
+            parser.set_progress_sender(sender);
+        }
+
+        // Rejects declared oversized body before reading it. Bytes actually received are limited
+        // in `read_chunk` since the body may not have Content-Length.
+        if let Some(content_length) = parser.content_length {
+            if content_length > parser.max_body_size().await {
+                return Err(FormFieldError::MaxBodySizeExceed);
+            }
+        }
+
+        let mut parts = vec![];
 
         loop {
             let mut form_part = parser.next_form_header().await?;
@@ -75,11 +185,13 @@ impl MultipartParser {
             if let Some(value) = form_part.name {
                 field_name = value;
             } else {
-                return Err(FormFieldError::Others(
-                    None,
-                    "Field name is missing.".to_owned(),
-                    true,
-                ));
+                // Parts without field name cannot be mapped to any field. Such parts are already
+                // consumed, so they are skipped instead of failing the whole form.
+                if parsing_completed {
+                    parser.check_body_size().await?;
+                    return Ok(parts);
+                }
+                continue;
             }
 
             if let Some(filename) = form_part.filename {
@@ -94,26 +206,133 @@ impl MultipartParser {
                     ));
                 }
 
-                let temp_file = FileField::from(filename, named_temp_file);
-                if let Some(files) = files.get_mut(&field_name) {
-                    files.push(temp_file);
-                } else {
-                    files.insert(field_name, vec![temp_file]);
-                }
+                let temp_file =
+                    FileField::from(filename, named_temp_file).content_type(form_part.content_type);
+                parts.push((field_name, PartValue::File(temp_file)));
             } else {
                 if let Some(field_value) = form_part.value {
-                    if let Some(values) = form_data.get_mut(&field_name) {
-                        values.push(field_value);
-                    } else {
-                        form_data.insert(field_name, vec![field_value]);
-                    }
+                    parts.push((field_name, PartValue::Text(field_value)));
                 }
             }
 
             if parsing_completed {
-                return Ok((form_data, files));
+                parser.check_body_size().await?;
+                return Ok(parts);
+            }
+        }
+    }
+
+    ///
+    /// Returns header of the next part, or `None` after the last part. Parts can be handled one by
+    /// one as they arrive instead of collecting the whole form with `parse`.
+    ///
+    /// After the header is returned, the part body can be read with `next_form_value`, which
+    /// stores the value or the file in the part applying the field size limits, or chunk by chunk
+    /// with `read_part_chunk`. Body not read completely is skipped by the next call. Body size
+    /// limit of the form constraints applies to all the bytes read.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::forms::FormFieldError;
+    /// use racoon::core::parser::multipart::MultipartParser;
+    /// use racoon::core::request::Request;
+    ///
+    /// async fn upload(request: &Request) -> Result<(), FormFieldError> {
+    ///     let stream = request.stream.clone();
+    ///     let form_constraints = request.form_constraints.clone();
+    ///     let mut parser = MultipartParser::from(stream, &request.headers, form_constraints)
+    ///         .map_err(|error| FormFieldError::Others(None, error.to_string(), true))?;
+    ///
+    ///     while let Some(mut part) = parser.next_part().await? {
+    ///         if part.filename.is_none() {
+    ///             parser.next_form_value(&mut part).await?;
+    ///             println!("{:?} = {:?}", part.name, part.value);
+    ///             continue;
+    ///         }
+    ///
+    ///         if part.content_type.as_deref() != Some("text/csv") {
+    ///             // Skipped without storing the body.
+    ///             continue;
+    ///         }
+    ///
+    ///         while let Some(chunk) = parser.read_part_chunk().await? {
+    ///             println!("Received {} bytes", chunk.len());
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    pub async fn next_part(&mut self) -> Result<Option<FormPart>, FormFieldError> {
+        if !self.first_header_scanned {
+            if let Some(content_length) = self.content_length {
+                if content_length > self.max_body_size().await {
+                    return Err(FormFieldError::MaxBodySizeExceed);
+                }
             }
         }
+
+        // Skips the body of the previous part if it is not read.
+        while !self.allow_next_header_read {
+            self.read_part_chunk().await?;
+        }
+
+        if self.completed {
+            self.check_body_size().await?;
+            return Ok(None);
+        }
+
+        Ok(Some(self.next_form_header().await?))
+    }
+
+    ///
+    /// Returns the next chunk of the body of the part returned from `next_part`, or `None` once
+    /// the body is read completely. Chunks are returned as they are received, so their sizes vary.
+    ///
+    pub async fn read_part_chunk(&mut self) -> Result<Option<Vec<u8>>, FormFieldError> {
+        if self.allow_next_header_read {
+            return Ok(None);
+        }
+
+        let delimiter = format!("\r\n--{}", self.boundary);
+        let delimiter = delimiter.as_bytes();
+
+        let mut buffer = self.read_chunk().await?;
+
+        loop {
+            // Number of bytes which can be returned without touching a possible delimiter.
+            let data_len = match scan_delimiter(&buffer, delimiter) {
+                DelimiterScan::Found(position, is_last) => {
+                    let mut remaining = buffer.split_off(position);
+                    remaining.drain(..delimiter.len());
+
+                    if is_last {
+                        self.completed = true;
+                    } else {
+                        // Skips line break \r\n before the next part header.
+                        remaining.drain(..2);
+                        self.restore_payload(&remaining).await;
+                    }
+                    self.allow_next_header_read = true;
+
+                    if buffer.is_empty() {
+                        return Ok(None);
+                    }
+                    return Ok(Some(buffer));
+                }
+                DelimiterScan::Incomplete(position) => position,
+                DelimiterScan::NotFound => buffer.len().saturating_sub(delimiter.len()),
+            };
+
+            if data_len > 0 {
+                let remaining = buffer.split_off(data_len);
+                self.restore_payload(&remaining).await;
+                return Ok(Some(buffer));
+            }
+
+            let chunk = self.read_chunk().await?;
+            buffer.extend(chunk);
+        }
     }
 
     pub async fn next_form_header(&mut self) -> Result<FormPart, FormFieldError> {
@@ -125,6 +344,11 @@ impl MultipartParser {
             ));
         }
 
+        self.parts_read += 1;
+        if self.parts_read > self.form_constraints.max_fields() {
+            return Err(FormFieldError::MaxFieldsExceed);
+        }
+
         let stream = self.stream.clone();
         let max_header_size = self
             .form_constraints
@@ -143,12 +367,7 @@ impl MultipartParser {
                     break;
                 }
 
-                let chunk = match stream.read_chunk().await {
-                    Ok(bytes) => bytes,
-                    Err(error) => {
-                        return Err(FormFieldError::Others(None, error.to_string(), true));
-                    }
-                };
+                let chunk = self.read_chunk().await?;
                 bytes_read += chunk.len();
                 buffer.extend(chunk);
             }
@@ -181,19 +400,14 @@ impl MultipartParser {
             if let Some(position) = scan_result {
                 let form_part_header_bytes = &buffer[..position];
                 let restore_bytes = &buffer[position + FORM_PART_HEADER_TERMINATOR.len()..];
-                let _ = stream.restore_payload(restore_bytes.as_ref()).await;
+                self.restore_payload(restore_bytes).await;
 
                 // Deny next time calling this method because form part body also must be read.
                 self.allow_next_header_read = false;
                 return Ok(parse_form_part_header(form_part_header_bytes)?);
             } else {
                 // Still form part not found. Collect more bytes.
-                let chunk = match stream.read_chunk().await {
-                    Ok(bytes) => bytes,
-                    Err(error) => {
-                        return Err(FormFieldError::Others(None, error.to_string(), true));
-                    }
-                };
+                let chunk = self.read_chunk().await?;
                 bytes_read += chunk.len();
                 buffer.extend(chunk);
             }
@@ -221,16 +435,8 @@ impl MultipartParser {
 
     pub async fn parse_file(&mut self, form_part: &mut FormPart) -> Result<bool, FormFieldError> {
         let form_constraints = self.form_constraints.clone();
-        let field_name;
-        if let Some(value) = &form_part.name {
-            field_name = value.to_owned();
-        } else {
-            return Err(FormFieldError::Others(
-                None,
-                "Field name is missing".to_owned(),
-                false,
-            ));
-        }
+        // Nameless parts are still read to reach the next part. Default constraints apply to them.
+        let field_name = form_part.name.clone().unwrap_or_default();
 
         // Form constraints
         let max_file_size =
@@ -240,7 +446,10 @@ impl MultipartParser {
         let value_terminator = format!("\r\n--{}", self.boundary);
         let value_terminator_bytes = value_terminator.as_bytes();
 
-        let mut temp_file = match TempFile::new().await {
+        // Waits if maximum number of files are already being uploaded.
+        let _upload_permit = form_constraints.acquire_upload_permit().await?;
+
+        let temp_file = match TempFile::new().await {
             Ok(file) => match file.open_rw().await {
                 Ok(result) => result,
                 Err(error) => {
@@ -251,8 +460,8 @@ impl MultipartParser {
                 return Err(FormFieldError::Others(None, error.to_string(), true));
             }
         };
+        let mut file_writer = FileWriter::new(temp_file, form_constraints.blocking_write_size());
         let mut scan_buffer = vec![];
-        const FORM_PART_END: &[u8; 4] = b"--\r\n";
         const CRLF_BREAK: &[u8; 2] = b"\r\n";
 
         loop {
@@ -260,96 +469,71 @@ impl MultipartParser {
                 return Err(FormFieldError::MaxFileSizeExceed(field_name.clone()));
             }
 
-            let scan_result = scan_buffer
-                .windows(value_terminator_bytes.len())
-                .position(|window| window == value_terminator_bytes);
-
-            if let Some(matched_position) = scan_result {
-                // File scan reached end
-                // Extra offset to check whether file ends or not
-                // If extra terminator byte offset is not present, it does not matter whether field
-                // end is found or not. Can be scanned again.
+            // Number of bytes which can be copied safely to the file without touching a possible
+            // boundary delimiter.
+            let to_copy_position = match scan_delimiter(&scan_buffer, value_terminator_bytes) {
+                DelimiterScan::Found(matched_position, is_last) => {
+                    // File scan reached end
+                    let to_copy = &scan_buffer[..matched_position];
 
-                if scan_buffer.len()
-                    >= matched_position + value_terminator_bytes.len() + FORM_PART_END.len()
-                {
-                    let to_copy_position = matched_position;
-                    let to_copy = &scan_buffer[..to_copy_position];
+                    if let Err(error) = file_writer.write_all(to_copy).await {
+                        return Err(file_write_error(&field_name, error));
+                    }
 
-                    match temp_file.write_all(to_copy).await {
-                        Ok(()) => {}
+                    let temp_file = match file_writer.finish().await {
+                        Ok(temp_file) => temp_file,
                         Err(error) => {
-                            return Err(FormFieldError::Others(
-                                Some(field_name.to_string()),
-                                format!("Failed to write file. Error: {}", error),
-                                true,
-                            ));
+                            return Err(file_write_error(&field_name, error));
                         }
-                    }
-
-                    let _ = temp_file.flush().await;
+                    };
 
-                    scan_buffer =
-                        (&scan_buffer[to_copy_position + value_terminator_bytes.len()..]).to_vec();
-                    return if &scan_buffer[..FORM_PART_END.len()] == FORM_PART_END {
+                    scan_buffer.drain(..matched_position + value_terminator_bytes.len());
+                    return if is_last {
                         // Request body completed
+                        self.report_progress(&field_name);
                         form_part.file = Some(temp_file);
                         self.allow_next_header_read = true;
+                        self.completed = true;
                         Ok(true)
                     } else {
                         // Form part completed but body is not ended yet
                         // Skips line break \r\n
                         scan_buffer.drain(..CRLF_BREAK.len());
-                        let _ = self.stream.restore_payload(&scan_buffer.as_ref()).await;
+                        self.restore_payload(&scan_buffer).await;
+                        self.report_progress(&field_name);
                         form_part.file = Some(temp_file);
                         self.allow_next_header_read = true;
                         Ok(false)
                     };
                 }
-            }
+                // Delimiter may be present but bytes following it are not received yet.
+                DelimiterScan::Incomplete(matched_position) => matched_position,
+                DelimiterScan::NotFound => scan_buffer
+                    .len()
+                    .saturating_sub(value_terminator_bytes.len()),
+            };
 
             // Copy data
-            if scan_buffer.len() > value_terminator_bytes.len() {
-                // This much amount of bytes can be copied safely from the file buffer
-                let to_copy_position = scan_buffer.len() - value_terminator_bytes.len();
-
-                match temp_file.write_all(&scan_buffer[..to_copy_position]).await {
-                    Ok(()) => {}
-                    Err(error) => {
-                        return Err(FormFieldError::Others(
-                            Some(field_name.to_string()),
-                            format!("Failed to write file. Error: {}", error),
-                            true,
-                        ));
-                    }
+            if to_copy_position > 0 {
+                let to_copy = &scan_buffer[..to_copy_position];
+                if let Err(error) = file_writer.write_all(to_copy).await {
+                    return Err(file_write_error(&field_name, error));
                 }
 
                 scan_buffer.drain(..to_copy_position);
+                self.report_progress(&field_name);
             }
 
             // File ending has not been reached
-            let chunk = match self.stream.read_chunk().await {
-                Ok(bytes) => bytes,
-                Err(error) => {
-                    return Err(FormFieldError::Others(None, error.to_string(), true));
-                }
-            };
+            let chunk = self.read_chunk().await?;
             bytes_read += chunk.len();
             scan_buffer.extend(chunk);
         }
     }
 
     pub async fn parse_value(&mut self, form_part: &mut FormPart) -> Result<bool, FormFieldError> {
-        let field_name;
-        if let Some(value) = &form_part.name {
-            field_name = value.to_owned();
-        } else {
-            return Err(FormFieldError::Others(
-                None,
-                "Field name is missing.".to_owned(),
-                false,
-            ));
-        }
+        // Nameless parts are still read to reach the next part. Default constraints apply to them.
+        let field_name = form_part.name.clone().unwrap_or_default();
 
         let max_value_size = self
             .form_constraints
@@ -359,7 +543,6 @@ impl MultipartParser {
 
         let mut buffer = vec![];
 
-        const FORM_PART_END: &[u8; 4] = b"--\r\n";
         const CRLF_BREAK: &[u8; 2] = b"\r\n";
 
         let mut bytes_read = 0;
@@ -368,52 +551,244 @@ impl MultipartParser {
             if bytes_read > max_value_size {
                 return Err(FormFieldError::MaxValueSizeExceed(field_name));
             }
-            let scan_result = buffer
-                .windows(scan_boundary_bytes.len())
-                .position(|window| window == scan_boundary_bytes);
+            if let DelimiterScan::Found(position, is_last) =
+                scan_delimiter(&buffer, scan_boundary_bytes)
+            {
+                let to_copy = &buffer[..position];
+                let mut to_copy_range = to_copy.len();
+
+                // Some clients sends single CRLF and some double CRLF line breaks
+                if to_copy.len() > 1 && &to_copy[..to_copy.len() - CRLF_BREAK.len()] == CRLF_BREAK
+                {
+                    to_copy_range -= 1;
+                }
 
-            if let Some(position) = scan_result {
-                if buffer.len() >= position + scan_boundary_bytes.len() + FORM_PART_END.len() {
-                    let to_copy = &buffer[..position];
-                    let mut to_copy_range = to_copy.len();
-
-                    // Some clients sends single CRLF and some double CRLF line breaks
-                    if to_copy.len() > 1
-                        && &to_copy[..to_copy.len() - CRLF_BREAK.len()] == CRLF_BREAK
-                    {
-                        to_copy_range -= 1;
+                // Charset specified in the part header takes precedence over the form charset.
+                let mut charset = self.charset;
+                if let Some(content_type) = &form_part.content_type {
+                    if let Some(label) = header_param(content_type, "charset") {
+                        charset = charset_from_label(&field_name, &label)?;
                     }
+                }
 
-                    let value = String::from_utf8_lossy(&to_copy[..to_copy_range]).to_string();
-
-                    // Removes copied bytes from the buffer
-                    buffer.drain(..position + scan_boundary_bytes.len());
-                    form_part.value = Some(value);
+                let value = match charset.decode(&to_copy[..to_copy_range]) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        return Err(FormFieldError::Others(
+                            Some(field_name),
+                            error.to_string(),
+                            false,
+                        ));
+                    }
+                };
 
-                    return if &buffer[..FORM_PART_END.len()] == FORM_PART_END {
-                        self.allow_next_header_read = true;
-                        Ok(true)
-                    } else {
-                        // Form part completed but body is not ended yet
-                        // Skips line break \r\n
-                        buffer.drain(..CRLF_BREAK.len());
-                        let _ = self.stream.restore_payload(buffer.as_ref()).await;
-                        self.allow_next_header_read = true;
-                        Ok(false)
-                    };
+                // Form charset is declared by browsers with `_charset_` hidden field.
+                if field_name == "_charset_" {
+                    self.charset = charset_from_label(&field_name, &value)?;
                 }
+
+                // Removes copied bytes from the buffer
+                buffer.drain(..position + scan_boundary_bytes.len());
+                form_part.value = Some(value);
+
+                return if is_last {
+                    self.allow_next_header_read = true;
+                    self.completed = true;
+                    Ok(true)
+                } else {
+                    // Form part completed but body is not ended yet
+                    // Skips line break \r\n
+                    buffer.drain(..CRLF_BREAK.len());
+                    self.restore_payload(&buffer).await;
+                    self.allow_next_header_read = true;
+                    Ok(false)
+                };
             }
 
-            let chunk = match self.stream.read_chunk().await {
-                Ok(bytes) => bytes,
-                Err(error) => {
-                    return Err(FormFieldError::Others(None, error.to_string(), true));
-                }
-            };
+            let chunk = self.read_chunk().await?;
             bytes_read += chunk.len();
             buffer.extend(chunk);
         }
     }
+
+    async fn max_body_size(&self) -> usize {
+        self.form_constraints
+            .max_body_size(self.stream.buffer_size().await)
+    }
+
+    /// Last chunk read may cross the limit, so the received body size is checked on completion.
+    async fn check_body_size(&self) -> Result<(), FormFieldError> {
+        if self.bytes_received > self.max_body_size().await {
+            return Err(FormFieldError::MaxBodySizeExceed);
+        }
+        Ok(())
+    }
+
+    async fn read_chunk(&mut self) -> Result<Vec<u8>, FormFieldError> {
+        // More bytes are needed only if the body is not completed yet, so all the bytes received
+        // so far including part headers and boundaries belong to the body.
+        if self.bytes_received >= self.max_body_size().await {
+            return Err(FormFieldError::MaxBodySizeExceed);
+        }
+
+        match self.stream.read_chunk().await {
+            Ok(bytes) => {
+                self.bytes_received += bytes.len();
+                Ok(bytes)
+            }
+            Err(error) => Err(FormFieldError::Others(None, error.to_string(), true)),
+        }
+    }
+
+    /// Returns bytes which belong to the next form part back to the stream.
+    async fn restore_payload(&mut self, bytes: &[u8]) {
+        self.bytes_received = self.bytes_received.saturating_sub(bytes.len());
+        let _ = self.stream.restore_payload(bytes).await;
+    }
+
+    fn report_progress(&self, field_name: &str) {
+        if let Some(sender) = &self.progress_sender {
+            let _ = sender.try_send(UploadProgress {
+                field: field_name.to_string(),
+                bytes_so_far: self.bytes_received,
+                content_length: self.content_length,
+            });
+        }
+    }
+}
+
+fn charset_from_label(field_name: &str, label: &str) -> Result<Charset, FormFieldError> {
+    match Charset::from_label(label) {
+        Some(charset) => Ok(charset),
+        None => Err(FormFieldError::Others(
+            Some(field_name.to_string()),
+            format!("Unsupported charset: {}", label),
+            false,
+        )),
+    }
+}
+
+fn file_write_error(field_name: &str, error: std::io::Error) -> FormFieldError {
+    FormFieldError::Others(
+        Some(field_name.to_string()),
+        format!("Failed to write file. Error: {}", error),
+        true,
+    )
+}
+
+///
+/// Writes uploaded file with async file IO, or in blocks on the blocking thread pool if
+/// `block_size` is set.
+///
+struct FileWriter {
+    temp_file: TempFile,
+    block_size: Option<usize>,
+    /// Bytes not written to the file yet.
+    block: Vec<u8>,
+    /// Handle used for blocking writes, opened with the first block.
+    blocking_file: Option<std::fs::File>,
+}
+
+impl FileWriter {
+    fn new(temp_file: TempFile, block_size: Option<usize>) -> Self {
+        Self {
+            temp_file,
+            block_size,
+            block: vec![],
+            blocking_file: None,
+        }
+    }
+
+    async fn write_all(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let block_size = match self.block_size {
+            Some(block_size) => block_size,
+            None => return self.temp_file.write_all(bytes).await,
+        };
+
+        self.block.extend_from_slice(bytes);
+        if self.block.len() >= block_size {
+            self.write_block().await?;
+        }
+        Ok(())
+    }
+
+    async fn write_block(&mut self) -> std::io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+
+        let block = std::mem::take(&mut self.block);
+        let file = self.blocking_file.take();
+        let file_path = self.temp_file.file_path().clone();
+
+        let result = tokio::task::spawn_blocking(move || -> std::io::Result<std::fs::File> {
+            let mut file = match file {
+                Some(file) => file,
+                None => std::fs::OpenOptions::new().write(true).open(file_path)?,
+            };
+
+            file.write_all(&block)?;
+            Ok(file)
+        })
+        .await;
+
+        match result {
+            Ok(file) => {
+                self.blocking_file = Some(file?);
+                Ok(())
+            }
+            Err(error) => Err(std::io::Error::other(error)),
+        }
+    }
+
+    ///
+    /// Writes the remaining bytes and returns the file.
+    ///
+    async fn finish(mut self) -> std::io::Result<TempFile> {
+        self.write_block().await?;
+        let _ = self.temp_file.flush().await;
+        Ok(self.temp_file)
+    }
+}
+
+enum DelimiterScan {
+    /// (position, is_last) Delimiter position and whether it is the close delimiter.
+    Found(usize, bool),
+    /// (position) Delimiter matched but bytes following it are not available yet.
+    Incomplete(usize),
+    NotFound,
+}
+
+///
+/// Scans boundary delimiter in the buffer.
+///
+/// Boundary text is only treated as delimiter if it is followed by `--` or CRLF. Otherwise it is
+/// the part of the field data and scanning continues from the next byte.
+///
+fn scan_delimiter(buffer: &[u8], delimiter: &[u8]) -> DelimiterScan {
+    let mut offset = 0;
+
+    while let Some(relative_position) = buffer[offset..]
+        .windows(delimiter.len())
+        .position(|window| window == delimiter)
+    {
+        let position = offset + relative_position;
+        let following = &buffer[position + delimiter.len()..];
+
+        if following.len() < 2 {
+            return DelimiterScan::Incomplete(position);
+        }
+
+        if following.starts_with(b"--") {
+            return DelimiterScan::Found(position, true);
+        } else if following.starts_with(b"\r\n") {
+            return DelimiterScan::Found(position, false);
+        }
+
+        offset = position + 1;
+    }
+
+    DelimiterScan::NotFound
 }
 
 pub fn parse_form_part_header(header_bytes: &[u8]) -> Result<FormPart, FormFieldError> {
@@ -491,12 +866,21 @@ fn parse_form_part_header_line(
     Ok(())
 }
 
+///
+/// Parses `name` and `filename` attributes from Content-Disposition value. Missing `name` is not
+/// an error here, such parts are skipped by the parser.
+///
 pub fn parse_content_disposition_value(
     value: &str,
     form_part: &mut FormPart,
 ) -> std::io::Result<()> {
     let value = value.trim();
 
+    if value == "form-data" {
+        // No attributes are present.
+        return Ok(());
+    }
+
     if !value.starts_with("form-data;") {
         // Not a valid Content-Deposition value for form part header
         return Err(std::io::Error::other(
@@ -519,12 +903,6 @@ pub fn parse_content_disposition_value(
         }
     }
 
-    if form_part.name.is_none() {
-        return Err(std::io::Error::other(
-            "Field name is missing in form part header.",
-        ));
-    }
-
     Ok(())
 }
 
@@ -532,7 +910,7 @@ pub fn parse_content_disposition_value(
 pub mod tests {
     use std::{collections::HashMap, sync::Arc};
 
-    use crate::core::forms::{FileFieldShortcut, FormConstraints};
+    use crate::core::forms::{FileFieldShortcut, FormConstraints, FormFieldError, PartValue};
     use crate::core::headers::{HeaderValue, Headers};
     use crate::core::shortcuts::SingleText;
     use crate::core::stream::{AbstractStream, TestStreamWrapper};
@@ -570,8 +948,450 @@ pub mod tests {
         let file = file_field.unwrap();
         let file_path = &file.temp_path;
         assert_eq!("example.txt".to_string(), file.name);
+        assert_eq!(Some("text/plain".to_string()), file.content_type);
 
         let file_content = tokio::fs::read_to_string(&file_path).await.unwrap();
         assert_eq!("Hello World".to_string(), file_content);
     }
+
+    #[tokio::test]
+    async fn test_multipart_boundary_inside_data() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let name_value = "John --boundary123 Doe\r\n--boundary123x";
+        let file_content = "Hello\r\n--boundary123 World\r\n--boundary12";
+
+        let test_data = format!("--boundary123\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\n{}\r\n--boundary123\r\nContent-Disposition: form-data; name=\"file\"; filename=\"example.txt\"\r\nContent-Type: text/plain\r\n\r\n{}\r\n--boundary123--\r\n", name_value, file_content).as_bytes().to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        // Small buffer sizes make delimiters split across multiple chunks.
+        for buffer_size in [7, 16, 1024] {
+            let stream: Box<dyn AbstractStream> =
+                Box::new(TestStreamWrapper::new(test_data.clone(), buffer_size));
+
+            let form_constraints = Arc::new(FormConstraints::new(
+                500 * 1024 * 1024,
+                2 * 1024 * 1024,
+                500 * 1024 * 1024,
+                2 * 1024 * 1024,
+                HashMap::new(),
+            ));
+
+            let result =
+                MultipartParser::parse(Arc::new(stream), form_constraints, &headers).await;
+            assert_eq!(true, result.is_ok());
+
+            let (form_data, files) = result.unwrap();
+            assert_eq!(Some(&name_value.to_string()), form_data.value("name"));
+
+            let file = files.value("file").unwrap();
+            let content = tokio::fs::read_to_string(&file.temp_path).await.unwrap();
+            assert_eq!(file_content.to_string(), content);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_next_part() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let file_content = "Hello\r\n--boundary12 World".repeat(10);
+        let test_data = format!("--boundary123\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nJohn\r\n--boundary123\r\nContent-Disposition: form-data; name=\"skipped\"; filename=\"skipped.txt\"\r\n\r\n{}\r\n--boundary123\r\nContent-Disposition: form-data; name=\"file\"; filename=\"example.txt\"\r\nContent-Type: text/plain\r\n\r\n{}\r\n--boundary123--\r\n", file_content, file_content).as_bytes().to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        for buffer_size in [7, 16, 1024] {
+            let stream: Box<dyn AbstractStream> =
+                Box::new(TestStreamWrapper::new(test_data.clone(), buffer_size));
+
+            let form_constraints = Arc::new(FormConstraints::new(
+                500 * 1024 * 1024,
+                2 * 1024 * 1024,
+                500 * 1024 * 1024,
+                2 * 1024 * 1024,
+                HashMap::new(),
+            ));
+
+            let mut parser =
+                MultipartParser::from(Arc::new(stream), &headers, form_constraints).unwrap();
+
+            let mut part = parser.next_part().await.unwrap().unwrap();
+            assert_eq!(Some("name".to_string()), part.name);
+            assert_eq!(false, parser.next_form_value(&mut part).await.unwrap());
+            assert_eq!(Some("John".to_string()), part.value);
+
+            // Body of the skipped part is not read.
+            let part = parser.next_part().await.unwrap().unwrap();
+            assert_eq!(Some("skipped.txt".to_string()), part.filename);
+
+            let part = parser.next_part().await.unwrap().unwrap();
+            assert_eq!(Some("example.txt".to_string()), part.filename);
+            assert_eq!(Some("text/plain".to_string()), part.content_type);
+
+            let mut content = vec![];
+            while let Some(chunk) = parser.read_part_chunk().await.unwrap() {
+                content.extend(chunk);
+            }
+            assert_eq!(file_content.as_bytes(), content.as_slice());
+
+            assert_eq!(true, parser.next_part().await.unwrap().is_none());
+            assert_eq!(true, parser.next_part().await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_nameless_part() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let test_data = "--boundary123\r\nContent-Disposition: form-data\r\n\r\nignored\r\n--boundary123\r\nContent-Disposition: form-data; filename=\"example.txt\"\r\nContent-Type: text/plain\r\n\r\nHello World\r\n--boundary123\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nJohn\r\n--boundary123--\r\n".as_bytes().to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+
+        let form_constraints = Arc::new(FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        ));
+
+        let result = MultipartParser::parse(Arc::new(stream), form_constraints, &headers).await;
+        assert_eq!(true, result.is_ok());
+
+        let (form_data, files) = result.unwrap();
+        assert_eq!(1, form_data.len());
+        assert_eq!(Some(&"John".to_string()), form_data.value("name"));
+        assert_eq!(true, files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_charset() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let mut test_data = b"--boundary123\r\nContent-Disposition: form-data; name=\"_charset_\"\r\n\r\niso-8859-1\r\n--boundary123\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nJos\xe9\r\n--boundary123\r\nContent-Disposition: form-data; name=\"city\"\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n".to_vec();
+        test_data.extend("Zürich\r\n--boundary123--\r\n".as_bytes());
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+
+        let form_constraints = Arc::new(FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        ));
+
+        let result = MultipartParser::parse(Arc::new(stream), form_constraints.clone(), &headers).await;
+        let (form_data, _) = result.unwrap();
+        assert_eq!(Some(&"José".to_string()), form_data.value("name"));
+        assert_eq!(Some(&"Zürich".to_string()), form_data.value("city"));
+
+        // Invalid UTF-8 without declared charset
+        let test_data = b"--boundary123\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nJos\xe9\r\n--boundary123--\r\n".to_vec();
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result = MultipartParser::parse(Arc::new(stream), form_constraints, &headers).await;
+        assert_eq!(true, result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_parse_ordered() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let test_data = b"--boundary123\r\nContent-Disposition: form-data; name=\"z\"\r\n\r\n1\r\n\
+            --boundary123\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            Content-Type: text/plain\r\n\r\nHello\r\n\
+            --boundary123\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n2\r\n\
+            --boundary123\r\nContent-Disposition: form-data; name=\"z\"\r\n\r\n3\r\n\
+            --boundary123--\r\n"
+            .to_vec();
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 16));
+
+        let form_constraints = Arc::new(FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        ));
+
+        let parts = MultipartParser::parse_ordered(Arc::new(stream), form_constraints, &headers)
+            .await
+            .unwrap();
+
+        let parts: Vec<(String, String)> = parts
+            .into_iter()
+            .map(|(name, value)| match value {
+                PartValue::Text(text) => (name, text),
+                PartValue::File(file) => (name, format!("file {}", file.name)),
+            })
+            .collect();
+
+        let expected = vec![
+            ("z".to_string(), "1".to_string()),
+            ("file".to_string(), "file a.txt".to_string()),
+            ("a".to_string(), "2".to_string()),
+            ("z".to_string(), "3".to_string()),
+        ];
+        assert_eq!(expected, parts);
+    }
+
+    #[tokio::test]
+    async fn test_multipart_max_fields() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let mut form_constraints = FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        );
+        form_constraints.set_max_fields(2);
+        let form_constraints = Arc::new(form_constraints);
+
+        let part = |name: &str| {
+            format!(
+                "--boundary123\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\nvalue\r\n",
+                name
+            )
+        };
+
+        let test_data = format!("{}{}--boundary123--\r\n", part("a"), part("b"));
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(test_data.into_bytes(), 1024));
+        let result =
+            MultipartParser::parse(Arc::new(stream), form_constraints.clone(), &headers).await;
+        assert_eq!(true, result.is_ok());
+
+        let test_data = format!("{}{}{}--boundary123--\r\n", part("a"), part("b"), part("c"));
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(test_data.into_bytes(), 1024));
+        let result = MultipartParser::parse(Arc::new(stream), form_constraints, &headers).await;
+        assert_eq!(true, matches!(result, Err(FormFieldError::MaxFieldsExceed)));
+    }
+
+    #[tokio::test]
+    async fn test_multipart_max_concurrent_uploads() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let test_data = "--boundary123\r\nContent-Disposition: form-data; name=\"file\"; filename=\"example.txt\"\r\nContent-Type: text/plain\r\n\r\nHello World\r\n--boundary123--\r\n".as_bytes().to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let mut form_constraints = FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        );
+        form_constraints.set_max_concurrent_uploads(1);
+        let form_constraints = Arc::new(form_constraints);
+
+        // Occupies the only upload permit.
+        let permit = form_constraints.acquire_upload_permit().await.unwrap();
+        assert_eq!(true, permit.is_some());
+
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(test_data.clone(), 1024));
+        let constraints = form_constraints.clone();
+        let headers_ref = headers.clone();
+        let mut upload = tokio::spawn(async move {
+            MultipartParser::parse(Arc::new(stream), constraints, &headers_ref)
+                .await
+                .is_ok()
+        });
+
+        let waiting =
+            tokio::time::timeout(std::time::Duration::from_millis(100), &mut upload).await;
+        assert_eq!(true, waiting.is_err());
+
+        drop(permit);
+        assert_eq!(true, upload.await.unwrap());
+
+        // Upload waiting longer than the timeout is rejected.
+        let mut form_constraints = FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        );
+        form_constraints.set_max_concurrent_uploads(1);
+        form_constraints.set_upload_wait_timeout(std::time::Duration::from_millis(100));
+        let form_constraints = Arc::new(form_constraints);
+        let _permit = form_constraints.acquire_upload_permit().await.unwrap();
+
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(test_data.clone(), 1024));
+        let result =
+            MultipartParser::parse(Arc::new(stream), form_constraints.clone(), &headers).await;
+        assert_eq!(true, matches!(result, Err(FormFieldError::UploadsBusy)));
+
+        // Zero does not limit the uploads.
+        let mut form_constraints = FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        );
+        form_constraints.set_max_concurrent_uploads(0);
+        let permit = form_constraints.acquire_upload_permit().await;
+        assert_eq!(true, matches!(permit, Ok(None)));
+        drop(permit);
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let form_constraints = Arc::new(form_constraints);
+        let result = MultipartParser::parse(Arc::new(stream), form_constraints, &headers).await;
+        assert_eq!(true, result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_blocking_file_writes() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let content = "Hello World ".repeat(100);
+        let test_data = format!(
+            "--boundary123\r\nContent-Disposition: form-data; name=\"file\"; \
+            filename=\"example.txt\"\r\n\r\n{}\r\n--boundary123--\r\n",
+            content
+        );
+
+        for block_size in [1, 100, 1024 * 1024] {
+            let mut form_constraints = FormConstraints::new(
+                500 * 1024 * 1024,
+                2 * 1024 * 1024,
+                500 * 1024 * 1024,
+                2 * 1024 * 1024,
+                HashMap::new(),
+            );
+            form_constraints.set_blocking_file_writes(block_size);
+            let form_constraints = Arc::new(form_constraints);
+
+            let stream: Box<dyn AbstractStream> =
+                Box::new(TestStreamWrapper::new(test_data.clone().into_bytes(), 64));
+            let result = MultipartParser::parse(Arc::new(stream), form_constraints, &headers).await;
+
+            let (_, files) = result.unwrap();
+            let file = files.value("file").unwrap();
+            let file_content = tokio::fs::read_to_string(&file.temp_path).await.unwrap();
+            assert_eq!(content, file_content);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload_progress() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        let file_content = "a".repeat(200);
+        let test_data = format!("--boundary123\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nJohn\r\n--boundary123\r\nContent-Disposition: form-data; name=\"file\"; filename=\"example.txt\"\r\nContent-Type: text/plain\r\n\r\n{}\r\n--boundary123--\r\n", file_content).as_bytes().to_vec();
+        let content_length = test_data.len();
+        headers.set("Content-Length", content_length.to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 32));
+
+        let form_constraints = Arc::new(FormConstraints::new(
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        ));
+
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(100);
+        let result = MultipartParser::parse_with_progress(
+            Arc::new(stream),
+            form_constraints,
+            &headers,
+            Some(sender),
+        )
+        .await;
+        assert_eq!(true, result.is_ok());
+
+        let mut reports = vec![];
+        while let Some(progress) = receiver.recv().await {
+            reports.push(progress);
+        }
+
+        assert_eq!(true, reports.len() > 1);
+        assert_eq!(true, reports.iter().all(|progress| progress.field == "file"));
+        assert_eq!(
+            true,
+            reports
+                .iter()
+                .all(|progress| progress.content_length == Some(content_length))
+        );
+        assert_eq!(
+            true,
+            reports
+                .windows(2)
+                .all(|pair| pair[0].bytes_so_far <= pair[1].bytes_so_far)
+        );
+        assert_eq!(content_length, reports.last().unwrap().bytes_so_far);
+    }
+
+    #[tokio::test]
+    async fn test_multipart_max_body_size() {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", "multipart/form-data; boundary=boundary123");
+
+        // Every part is within the value size limit, but the whole body is not.
+        let mut test_data = vec![];
+        for index in 0..20 {
+            let part = format!(
+                "--boundary123\r\nContent-Disposition: form-data; name=\"tag\"\r\n\r\n{}\r\n",
+                index
+            );
+            test_data.extend(part.as_bytes());
+        }
+        test_data.extend(b"--boundary123--\r\n");
+        let body_size = test_data.len();
+
+        let form_constraints = |max_body_size| {
+            Arc::new(FormConstraints::new(
+                max_body_size,
+                1024,
+                1024,
+                1024,
+                HashMap::new(),
+            ))
+        };
+
+        // Content-Length header is not present, so the body size is only known while reading.
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(test_data.clone(), 32));
+        let result =
+            MultipartParser::parse(Arc::new(stream), form_constraints(body_size - 20), &headers)
+                .await;
+        assert_eq!(
+            true,
+            matches!(result, Err(FormFieldError::MaxBodySizeExceed))
+        );
+
+        let stream: Box<dyn AbstractStream> =
+            Box::new(TestStreamWrapper::new(test_data.clone(), 32));
+        let result =
+            MultipartParser::parse(Arc::new(stream), form_constraints(body_size), &headers).await;
+        assert_eq!(20, result.unwrap().0.get("tag").unwrap().len());
+
+        // Declared oversized body is rejected before reading.
+        headers.set("Content-Length", body_size.to_string());
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 32));
+        let result =
+            MultipartParser::parse(Arc::new(stream), form_constraints(body_size / 2), &headers)
+                .await;
+        assert_eq!(
+            true,
+            matches!(result, Err(FormFieldError::MaxBodySizeExceed))
+        );
+    }
 }
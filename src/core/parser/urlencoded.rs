@@ -2,8 +2,9 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::core::forms::{FormConstraints, FormData, FormFieldError};
-use crate::core::headers::{HeaderValue, Headers};
-use crate::core::parser::params::parse_url_encoded;
+use crate::core::headers::{header_param, HeaderValue, Headers};
+use crate::core::parser::charset::Charset;
+use crate::core::parser::params::parse_url_encoded_bytes;
 
 use crate::core::stream::Stream;
 
@@ -13,6 +14,7 @@ pub struct UrlEncodedParser {
     stream: Arc<Stream>,
     form_constraints: Arc<FormConstraints>,
     content_length: usize,
+    charset: Option<Charset>,
 }
 
 impl UrlEncodedParser {
@@ -41,10 +43,29 @@ impl UrlEncodedParser {
             ));
         }
 
+        // Charset declared in the Content-Type header. If missing, `_charset_` field or UTF-8 is
+        // used while decoding.
+        let mut charset = None;
+        if let Some(content_type) = headers.value("Content-Type") {
+            if let Some(label) = header_param(&content_type, "charset") {
+                charset = match Charset::from_label(&label) {
+                    Some(charset) => Some(charset),
+                    None => {
+                        return Err(FormFieldError::Others(
+                            None,
+                            format!("Unsupported charset: {}", label),
+                            false,
+                        ));
+                    }
+                };
+            }
+        }
+
         Ok(UrlEncodedParser {
             stream,
             form_constraints,
             content_length,
+            charset,
         })
     }
 
@@ -65,8 +86,20 @@ impl UrlEncodedParser {
 
         loop {
             if buffer.len() >= self.content_length {
-                let value = String::from_utf8_lossy(&buffer);
-                return Ok(parse_url_encoded(value.to_string().as_str()));
+                // Pairs without "=" are ignored by the parser, so they are not counted.
+                let field_count = buffer
+                    .split(|byte| *byte == b'&')
+                    .filter(|pair| pair.contains(&b'='))
+                    .count();
+
+                if field_count > self.form_constraints.max_fields() {
+                    return Err(FormFieldError::MaxFieldsExceed);
+                }
+
+                return match parse_url_encoded_bytes(&buffer, self.charset) {
+                    Ok(params) => Ok(params),
+                    Err(error) => Err(FormFieldError::Others(None, error.to_string(), false)),
+                };
             }
 
             let chunk = match self.stream.read_chunk().await {
@@ -158,4 +191,81 @@ pub mod test {
             }
         }
     }
+
+    #[tokio::test()]
+    async fn test_url_encode_charset() {
+        let form_constraints = Arc::new(FormConstraints::new(
+            2 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        ));
+
+        // Charset from Content-Type header
+        let mut headers = Headers::new();
+        headers.set(
+            "Content-Type",
+            "application/x-www-form-urlencoded; charset=ISO-8859-1",
+        );
+        let test_data = b"name=Jos%E9+Garc%EDa".to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result =
+            UrlEncodedParser::parse(Arc::new(stream), &headers, form_constraints.clone()).await;
+        assert_eq!(Some(&"José García".to_string()), result.unwrap().value("name"));
+
+        // Charset from _charset_ field
+        let mut headers = Headers::new();
+        let test_data = b"_charset_=windows-1252&price=%805".to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result =
+            UrlEncodedParser::parse(Arc::new(stream), &headers, form_constraints.clone()).await;
+        assert_eq!(Some(&"€5".to_string()), result.unwrap().value("price"));
+
+        // Invalid UTF-8 is reported instead of being replaced.
+        let mut headers = Headers::new();
+        let test_data = b"name=Jos%E9".to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result = UrlEncodedParser::parse(Arc::new(stream), &headers, form_constraints).await;
+        match result {
+            Err(FormFieldError::Others(_, _, is_critical)) => assert_eq!(false, is_critical),
+            _ => panic!("Expected invalid UTF-8 error."),
+        }
+    }
+
+    #[tokio::test()]
+    async fn test_url_encode_max_fields() {
+        let mut form_constraints = FormConstraints::new(
+            2 * 1024 * 1024,
+            2 * 1024 * 1024,
+            500 * 1024 * 1024,
+            2 * 1024 * 1024,
+            HashMap::new(),
+        );
+        form_constraints.set_max_fields(2);
+        let form_constraints = Arc::new(form_constraints);
+
+        let mut headers = Headers::new();
+        let test_data = b"name=John&location=ktm&flag".to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result =
+            UrlEncodedParser::parse(Arc::new(stream), &headers, form_constraints.clone()).await;
+        assert_eq!(true, result.is_ok());
+
+        let mut headers = Headers::new();
+        let test_data = b"name=John&location=ktm&name=Doe".to_vec();
+        headers.set("Content-Length", test_data.len().to_string());
+
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(test_data, 1024));
+        let result = UrlEncodedParser::parse(Arc::new(stream), &headers, form_constraints).await;
+        assert_eq!(true, matches!(result, Err(FormFieldError::MaxFieldsExceed)));
+    }
 }
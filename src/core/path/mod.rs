@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 
+use crate::core::forms::FormConstraints;
+use crate::core::method::Method;
 use crate::core::request::Request;
 use crate::core::response::status::ResponseStatus;
 use crate::core::response::{AbstractResponse, HttpResponse, Response};
@@ -11,24 +14,210 @@ use super::headers::HeaderValue;
 
 pub type View = fn(Request) -> Pin<Box<dyn Future<Output = Box<dyn AbstractResponse>> + Send>>;
 
+/// Request methods implemented by the server. Requests with other methods receive
+/// `501 Not Implemented` unless the matched path registers a view for the method explicitly.
+pub const KNOWN_METHODS: [&str; 9] = [
+    "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
+];
+
 pub struct Path {
     pub name: String,
-    pub view: View,
+    /// View serving all the request methods. `None` if the path only serves specific methods.
+    pub view: Option<View>,
+    /// Views serving specific request methods. These take precedence over `view`.
+    pub method_views: HashMap<Method, View>,
+    /// Constraints replacing the size limits of the server form constraints for requests to this
+    /// path.
+    pub form_constraints: Option<Arc<FormConstraints>>,
+}
+
+/// Result of matching request method against the views registered for the path.
+pub enum MethodMatch {
+    View(View),
+    /// Method is known but not served by this path. Contains allowed methods.
+    NotAllowed(Vec<String>),
+    /// Method is not implemented by the server.
+    NotImplemented,
 }
 
 impl Path {
+    ///
+    /// Creates path which passes requests with any method to the view.
+    ///
+    /// Path segments written as `{name}` match a single segment. A catch-all `{*name}` at the
+    /// end of the path matches the remaining path including slashes. Matched values are
+    /// available in `request.path_params` under the declared names.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::path::Path;
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    /// use racoon::core::shortcuts::SingleText;
+    /// use racoon::view;
+    ///
+    /// async fn serve_file(request: Request) -> Response {
+    ///     // For "/files/docs/a/b/c.txt", bucket is "docs" and rest is "a/b/c.txt".
+    ///     let bucket = request.path_params.value("bucket").unwrap();
+    ///     let rest = request.path_params.value("rest").unwrap();
+    ///     HttpResponse::ok().body(format!("{}: {}", bucket, rest))
+    /// }
+    ///
+    /// let path = Path::new("/files/{bucket}/{*rest}", view!(serve_file));
+    /// ```
+    ///
     pub fn new<S: AsRef<str>>(name: S, view: View) -> Self {
         Self {
             name: name.as_ref().to_string(),
-            view,
+            view: Some(view),
+            method_views: HashMap::new(),
+            form_constraints: None,
         }
     }
 
-    pub async fn resolve(request: Request, view: Option<View>) -> Response {
+    ///
+    /// Creates path without any view. Register views for specific methods with `get`, `post`,
+    /// `method` etc. Requests with other methods receive `405 Method Not Allowed`.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::path::Path;
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    /// use racoon::view;
+    ///
+    /// async fn list_users(request: Request) -> Response {
+    ///     HttpResponse::ok().body("Users")
+    /// }
+    ///
+    /// async fn create_user(request: Request) -> Response {
+    ///     HttpResponse::created().body("Created")
+    /// }
+    ///
+    /// let path = Path::route("/users")
+    ///     .get(view!(list_users))
+    ///     .post(view!(create_user));
+    /// ```
+    ///
+    pub fn route<S: AsRef<str>>(name: S) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            view: None,
+            method_views: HashMap::new(),
+            form_constraints: None,
+        }
+    }
+
+    /// Registers view for the given request method. Method name is case-sensitive.
+    pub fn method<S: AsRef<str>>(mut self, method: S, view: View) -> Self {
+        self.method_views
+            .insert(Method::from(method.as_ref()), view);
+        self
+    }
+
+    ///
+    /// Sets body parsing limits for this path instead of the server form constraints, for
+    /// example to allow large uploads only on the upload route. Only the size limits are replaced.
+    /// Concurrent upload limit, upload wait timeout and blocking file writes of the server still
+    /// apply to the requests to this path.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use racoon::core::forms::FormConstraints;
+    /// use racoon::core::path::Path;
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    /// use racoon::view;
+    ///
+    /// async fn upload_avatar(request: Request) -> Response {
+    ///     HttpResponse::ok().body("Uploaded")
+    /// }
+    ///
+    /// let mb = 1024 * 1024;
+    /// let avatar_constraints = FormConstraints::new(10 * mb, 2 * mb, 10 * mb, mb, HashMap::new());
+    /// let path = Path::new("/avatar", view!(upload_avatar)).form_constraints(avatar_constraints);
+    /// ```
+    ///
+    pub fn form_constraints(mut self, form_constraints: FormConstraints) -> Self {
+        self.form_constraints = Some(Arc::new(form_constraints));
+        self
+    }
+
+    pub fn get(self, view: View) -> Self {
+        self.method("GET", view)
+    }
+
+    pub fn post(self, view: View) -> Self {
+        self.method("POST", view)
+    }
+
+    pub fn put(self, view: View) -> Self {
+        self.method("PUT", view)
+    }
+
+    pub fn patch(self, view: View) -> Self {
+        self.method("PATCH", view)
+    }
+
+    pub fn delete(self, view: View) -> Self {
+        self.method("DELETE", view)
+    }
+
+    ///
+    /// Returns view registered for the request method.
+    ///
+    /// Method specific views are checked first. `HEAD` requests are served by the `GET` view
+    /// unless a view is registered for `HEAD`. The server sends headers of the response without
+    /// the body. Methods not listed in `KNOWN_METHODS` are not implemented unless registered
+    /// explicitly, otherwise the view for all methods is used.
+    ///
+    pub fn view_for(&self, method: &Method) -> MethodMatch {
+        if let Some(view) = self.method_views.get(method) {
+            return MethodMatch::View(*view);
+        }
+
+        if *method == Method::Head {
+            if let Some(view) = self.method_views.get(&Method::Get) {
+                return MethodMatch::View(*view);
+            }
+        }
+
+        if !KNOWN_METHODS.contains(&method.as_str()) {
+            return MethodMatch::NotImplemented;
+        }
+
+        if let Some(view) = self.view {
+            return MethodMatch::View(view);
+        }
+
+        let mut allowed: Vec<String> = self.method_views.keys().map(Method::to_string).collect();
+        if self.method_views.contains_key(&Method::Get) && !allowed.contains(&"HEAD".to_string()) {
+            allowed.push("HEAD".to_string());
+        }
+        allowed.sort();
+        MethodMatch::NotAllowed(allowed)
+    }
+
+    ///
+    /// Passes request to the next registered middleware. After all the middleware are called,
+    /// passes request to the view.
+    ///
+    pub async fn resolve(mut request: Request, view: Option<View>) -> Response {
+        if let Some(middleware) = request.next_middleware() {
+            return middleware(request, view).await;
+        }
+
         let mut response;
         let response_headers_from_request_ref = request.response_headers.clone();
 
-        if let Some(view) = view {
+        if let Some(server_response) = request.take_server_response() {
+            response = server_response;
+        } else if let Some(view) = view {
             response = view(request).await;
         } else {
             response = HttpResponse::not_found().body("404 Page not found");
@@ -53,12 +242,17 @@ impl Clone for Path {
         Self {
             name: self.name.clone(),
             view: self.view.clone(),
+            method_views: self.method_views.clone(),
+            form_constraints: self.form_constraints.clone(),
         }
     }
 }
 
 pub type Paths = Vec<Path>;
 
+///
+/// Values of the named and catch-all segments of the matched path.
+///
 #[derive(Debug)]
 pub struct PathParams {
     params: HashMap<String, String>,
@@ -95,9 +289,196 @@ impl PathParams {
     }
 }
 
+///
+/// Builds URL from the path pattern by substituting `{name}` and `{*name}` segments with the
+/// values of `params`. Values are percent-encoded; slashes in catch-all values are kept. Params
+/// not used in the pattern are appended as query string in the given order.
+///
+/// Returns `None` if the value of a segment is missing or empty.
+///
+/// # Examples
+/// ```
+/// use racoon::core::path::reverse;
+///
+/// let url = reverse("/users/{id}/posts", &[("id", "25"), ("page", "2")]);
+/// assert_eq!(Some("/users/25/posts?page=2".to_string()), url);
+///
+/// let url = reverse("/files/{*rest}", &[("rest", "docs/read me.txt")]);
+/// assert_eq!(Some("/files/docs/read%20me.txt".to_string()), url);
+/// ```
+///
+pub fn reverse(pattern: &str, params: &[(&str, &str)]) -> Option<String> {
+    let mut url = String::with_capacity(pattern.len());
+    let mut used = vec![false; params.len()];
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        // Doubled braces are literal braces.
+        if (char == '{' || char == '}') && chars.peek() == Some(&char) {
+            chars.next();
+            url.push(char);
+            continue;
+        }
+
+        if char != '{' {
+            url.push(char);
+            continue;
+        }
+
+        let segment: String = chars.by_ref().take_while(|char| *char != '}').collect();
+        let (name, is_catch_all) = match segment.strip_prefix('*') {
+            Some(name) => (name, true),
+            None => (segment.as_str(), false),
+        };
+
+        let position = params.iter().position(|(param, _)| *param == name)?;
+        let value = params[position].1;
+        if value.is_empty() {
+            return None;
+        }
+        used[position] = true;
+
+        if is_catch_all {
+            let encoded: Vec<String> = value
+                .split('/')
+                .map(|part| urlencoding::encode(part).into_owned())
+                .collect();
+            url.push_str(&encoded.join("/"));
+        } else {
+            url.push_str(&urlencoding::encode(value));
+        }
+    }
+
+    let query: Vec<String> = params
+        .iter()
+        .zip(used)
+        .filter(|(_, used)| !used)
+        .map(|((name, value), _)| {
+            format!(
+                "{}={}",
+                urlencoding::encode(name),
+                urlencoding::encode(value)
+            )
+        })
+        .collect();
+
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+    Some(url)
+}
+
 #[macro_export]
 macro_rules! view {
     ($view_name: ident) => {
         |request: racoon::core::request::Request| Box::pin($view_name(request))
     };
 }
+
+#[cfg(test)]
+pub mod tests {
+    use crate::core::method::Method;
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::{HttpResponse, Response};
+
+    use super::{reverse, MethodMatch, Path, View};
+
+    async fn home(_: Request) -> Response {
+        HttpResponse::ok().body("Home")
+    }
+
+    fn home_view() -> View {
+        |request| Box::pin(home(request))
+    }
+
+    #[test]
+    fn test_view_for_any_method() {
+        let path = Path::new("/", home_view());
+        assert_eq!(
+            true,
+            matches!(path.view_for(&Method::from("GET")), MethodMatch::View(_))
+        );
+        assert_eq!(
+            true,
+            matches!(path.view_for(&Method::from("DELETE")), MethodMatch::View(_))
+        );
+        assert_eq!(
+            true,
+            matches!(
+                path.view_for(&Method::from("PROPFIND")),
+                MethodMatch::NotImplemented
+            )
+        );
+        assert_eq!(
+            true,
+            matches!(
+                path.view_for(&Method::from("GETT")),
+                MethodMatch::NotImplemented
+            )
+        );
+    }
+
+    #[test]
+    fn test_view_for_specific_methods() {
+        let path = Path::route("/users")
+            .get(home_view())
+            .post(home_view())
+            .method("PROPFIND", home_view());
+
+        assert_eq!(
+            true,
+            matches!(path.view_for(&Method::from("GET")), MethodMatch::View(_))
+        );
+        assert_eq!(
+            true,
+            matches!(
+                path.view_for(&Method::from("PROPFIND")),
+                MethodMatch::View(_)
+            )
+        );
+        assert_eq!(
+            true,
+            matches!(path.view_for(&Method::from("HEAD")), MethodMatch::View(_))
+        );
+        assert_eq!(
+            true,
+            matches!(
+                path.view_for(&Method::from("MKCOL")),
+                MethodMatch::NotImplemented
+            )
+        );
+
+        match path.view_for(&Method::from("DELETE")) {
+            MethodMatch::NotAllowed(allowed) => {
+                assert_eq!(vec!["GET", "HEAD", "POST", "PROPFIND"], allowed);
+            }
+            _ => panic!("Expected method not allowed."),
+        }
+    }
+
+    #[test]
+    fn test_reverse() {
+        assert_eq!(Some("/".to_string()), reverse("/", &[]));
+        assert_eq!(
+            Some("/users/25/posts/a%20b".to_string()),
+            reverse("/users/{id}/posts/{slug}", &[("slug", "a b"), ("id", "25")])
+        );
+        assert_eq!(
+            Some("/users/25?page=2&q=a%26b".to_string()),
+            reverse("/users/{id}", &[("id", "25"), ("page", "2"), ("q", "a&b")])
+        );
+        assert_eq!(
+            Some("/files/docs/a%3Fb.txt".to_string()),
+            reverse("/files/{*rest}", &[("rest", "docs/a?b.txt")])
+        );
+        assert_eq!(
+            Some("/literal/{id}/1".to_string()),
+            reverse("/literal/{{id}}/{id}", &[("id", "1")])
+        );
+
+        assert_eq!(None, reverse("/users/{id}", &[]));
+        assert_eq!(None, reverse("/users/{id}", &[("id", "")]));
+    }
+}
@@ -0,0 +1,152 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+///
+/// Values attached to the request, keyed by the type of the value. Middleware can insert values
+/// such as the authenticated user for the views to read. Extensions are empty for every new
+/// request.
+///
+/// # Examples
+/// ```
+/// use racoon::core::path::View;
+/// use racoon::core::middleware::next;
+/// use racoon::core::request::Request;
+/// use racoon::core::response::{HttpResponse, Response};
+/// use racoon::core::response::status::ResponseStatus;
+///
+/// #[derive(Clone)]
+/// struct User {
+///     username: String,
+/// }
+///
+/// async fn authenticate(request: Request, view: Option<View>) -> Response {
+///     let user = User {
+///         username: "John".to_string(),
+///     };
+///     request.extensions().lock().await.insert(user);
+///     next(request, view).await
+/// }
+///
+/// async fn profile(request: Request) -> Response {
+///     let user = request.extensions().lock().await.get::<User>().cloned();
+///     match user {
+///         Some(user) => HttpResponse::ok().body(format!("Hello {}", user.username)),
+///         None => HttpResponse::unauthorized().body("Unauthorized"),
+///     }
+/// }
+/// ```
+///
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Inserts the value and returns the previous value of the same type.
+    ///
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast::<T>().ok())
+            .map(|previous| *previous)
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut::<T>())
+    }
+
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast::<T>().ok())
+            .map(|value| *value)
+    }
+
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::core::middleware::next;
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::{HttpResponse, Response};
+    use crate::core::server::Server;
+    use crate::core::testing::TestClient;
+
+    use super::Extensions;
+
+    #[derive(Debug, PartialEq)]
+    struct User(String);
+
+    #[test]
+    fn test_extensions() {
+        let mut extensions = Extensions::new();
+        assert_eq!(None, extensions.get::<User>());
+
+        assert_eq!(None, extensions.insert(User("John".to_string())));
+        assert_eq!(
+            Some(User("John".to_string())),
+            extensions.insert(User("Jane".to_string()))
+        );
+        assert_eq!(Some(&User("Jane".to_string())), extensions.get::<User>());
+        assert_eq!(None, extensions.get::<String>());
+
+        extensions.get_mut::<User>().unwrap().0.push_str(" Doe");
+        assert_eq!(Some(User("Jane Doe".to_string())), extensions.remove());
+        assert_eq!(false, extensions.contains::<User>());
+    }
+
+    async fn authenticate(request: Request, view: Option<View>) -> Response {
+        if let Some(username) = request
+            .query_params
+            .get("user")
+            .and_then(|values| values.first())
+        {
+            let user = User(username.to_string());
+            request.extensions().lock().await.insert(user);
+        }
+        next(request, view).await
+    }
+
+    async fn profile(request: Request) -> Response {
+        let extensions = request.extensions().lock().await;
+        match extensions.get::<User>() {
+            Some(user) => HttpResponse::ok().body(format!("Hello {}", user.0)),
+            None => HttpResponse::unauthorized().body("Unauthorized"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_extensions() {
+        let view: View = |request| Box::pin(profile(request));
+        let mut server = Server::bind("127.0.0.1:0");
+        server
+            .urls(vec![Path::new("/", view)])
+            .wrap(|request, view| Box::pin(authenticate(request, view)));
+        let client = TestClient::new(&server);
+
+        let response = client.get("/?user=John").send().await;
+        assert_eq!("Hello John", response.text());
+
+        // Values are not shared between the requests.
+        let response = client.get("/").send().await;
+        assert_eq!(401, response.status_code);
+    }
+}
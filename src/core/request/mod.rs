@@ -1,36 +1,64 @@
-use std::collections::HashMap;
+pub mod extensions;
+pub mod query;
+
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
 
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use tokio::sync::mpsc::Sender;
 use tokio::sync::Mutex;
 
-use crate::core::forms::{Files, FormConstraints, FormData};
+use crate::core::forms::{Files, FormConstraints, FormData, UploadProgress};
+use crate::core::middleware::Middleware;
 
-use crate::core::headers::{HeaderValue, Headers};
+use crate::core::headers::{self, ContentRange, HeaderValue, Headers, RawHeaders};
+use crate::core::method::Method;
+use crate::core::parser::chunked;
 use crate::core::parser::multipart::MultipartParser;
 use crate::core::parser::urlencoded::UrlEncodedParser;
 use crate::core::server::Context;
-use crate::core::stream::Stream;
+use crate::core::stream::{BodyTee, PeerAddr, Stream, TeeStreamWrapper};
+use crate::core::websocket::WebSocketConnections;
 
 use crate::core::path::PathParams;
+use crate::core::response::Response;
 use crate::{racoon_debug, racoon_error};
 
 use crate::core::cookie::{parse_cookies_from_header, Cookies};
 use crate::core::session::{Session, SessionManager};
 use crate::core::shortcuts::SingleText;
 
+use self::extensions::Extensions;
+use self::query::QueryParseError;
 use super::forms::FormFieldError;
 
 pub type QueryParams = HashMap<String, Vec<String>>;
 
+///
+/// Request line and headers of the request, available before the request is routed and its body
+/// is read. Used by the server guards.
+///
+pub struct RequestHead<'a> {
+    pub method: &'a Method,
+    /// Path including the query string, same as `Request::path`.
+    pub path: &'a str,
+    pub http_version: u8,
+    pub headers: &'a Headers,
+    pub remote_addr: Option<PeerAddr>,
+}
+
 pub struct Request {
     pub stream: Arc<Stream>,
     context: Arc<Context>,
     pub scheme: String,
-    pub method: String,
+    pub method: Method,
     pub path: String,
     pub http_version: u8,
     pub headers: Headers,
+    /// Headers in the order received, for example to compute signatures over canonical headers.
+    pub raw_headers: RawHeaders,
     pub path_params: PathParams,
     pub query_params: QueryParams,
     pub cookies: Cookies,
@@ -38,6 +66,15 @@ pub struct Request {
     pub body_read: Arc<AtomicBool>,
     pub form_constraints: Arc<FormConstraints>,
     pub response_headers: Arc<Mutex<Headers>>,
+    /// Middleware not yet called for this request, outermost first.
+    middleware_chain: VecDeque<Middleware>,
+    body_tee: Option<BodyTee>,
+    websocket_connections: Option<Arc<WebSocketConnections>>,
+    request_id: Option<String>,
+    extensions: Arc<Mutex<Extensions>>,
+    /// Response prepared by the server, e.g. `405 Method Not Allowed`, returned instead of calling
+    /// the view once all the middleware are called.
+    server_response: Arc<StdMutex<Option<Response>>>,
 }
 
 impl Request {
@@ -45,10 +82,11 @@ impl Request {
         stream: Arc<Stream>,
         context: Arc<Context>,
         scheme: String,
-        method: String,
+        method: Method,
         path: String,
         http_version: u8,
         headers: Headers,
+        raw_headers: RawHeaders,
         path_params: PathParams,
         query_params: QueryParams,
         session_manager: Arc<SessionManager>,
@@ -59,7 +97,8 @@ impl Request {
         let cookies = parse_cookies_from_header(&headers);
         let session_id = cookies.value("sessionid");
 
-        let session = Session::from(session_manager, session_id, response_headers.clone());
+        let mut session = Session::from(session_manager, session_id, response_headers.clone());
+        session.set_secure(scheme.eq_ignore_ascii_case("https"));
 
         Self {
             stream,
@@ -69,6 +108,7 @@ impl Request {
             path,
             http_version,
             headers,
+            raw_headers,
             path_params,
             query_params,
             cookies,
@@ -76,17 +116,314 @@ impl Request {
             body_read,
             form_constraints,
             response_headers,
+            middleware_chain: VecDeque::new(),
+            body_tee: None,
+            websocket_connections: None,
+            request_id: None,
+            extensions: Arc::new(Mutex::new(Extensions::new())),
+            server_response: Arc::new(StdMutex::new(None)),
         }
     }
 
-    pub async fn remote_addr(&self) -> Option<String> {
+    pub(crate) fn set_middleware_chain(&mut self, middlewares: &[Middleware]) {
+        self.middleware_chain = middlewares.iter().cloned().collect();
+    }
+
+    pub(crate) fn set_websocket_connections(
+        &mut self,
+        websocket_connections: Option<Arc<WebSocketConnections>>,
+    ) {
+        self.websocket_connections = websocket_connections;
+    }
+
+    pub(crate) fn set_request_id(&mut self, request_id: Option<String>) {
+        self.request_id = request_id;
+    }
+
+    ///
+    /// Returns id of the request if the server is configured to assign request ids with
+    /// `Server::request_id`.
+    ///
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Open WebSocket connections of the server, if the server limits them.
+    pub(crate) fn websocket_connections(&self) -> Option<&Arc<WebSocketConnections>> {
+        self.websocket_connections.as_ref()
+    }
+
+    pub(crate) fn set_server_response(&mut self, response: Response) {
+        if let Ok(mut server_response) = self.server_response.lock() {
+            *server_response = Some(response);
+        }
+    }
+
+    pub(crate) fn take_server_response(&self) -> Option<Response> {
+        match self.server_response.lock() {
+            Ok(mut server_response) => server_response.take(),
+            Err(_) => None,
+        }
+    }
+
+    /// Removes and returns the next middleware to call.
+    pub(crate) fn next_middleware(&mut self) -> Option<Middleware> {
+        self.middleware_chain.pop_front()
+    }
+
+    ///
+    /// Records raw request body bytes up to `limit` while parsers such as `parse` or `json` read
+    /// them, for example for audit logging. The record is available from `raw_body` and is shared
+    /// with the clones of this request, so it must be enabled before the body is read. Body sent
+    /// with chunked transfer coding is recorded decoded.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::path::View;
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::Response;
+    /// use racoon::core::middleware::next;
+    ///
+    /// async fn audit(mut request: Request, view: Option<View>) -> Response {
+    ///     request.tee_body(64 * 1024);
+    ///     let audited_request = request.clone();
+    ///
+    ///     let response = next(request, view).await;
+    ///     if let Some(raw_body) = audited_request.raw_body() {
+    ///         println!("{} {}: {:?}", audited_request.method, audited_request.path, raw_body);
+    ///     }
+    ///     response
+    /// }
+    /// ```
+    ///
+    pub fn tee_body(&mut self, limit: usize) {
+        if self.body_tee.is_some() {
+            return;
+        }
+
+        let body_tee = BodyTee::new(limit);
+        let stream: Stream = Box::new(TeeStreamWrapper::new(self.stream.clone(), body_tee.clone()));
+        self.stream = Arc::new(stream);
+        self.body_tee = Some(body_tee);
+    }
+
+    ///
+    /// Returns raw body bytes read so far if `tee_body` is enabled.
+    ///
+    pub fn raw_body(&self) -> Option<Vec<u8>> {
+        self.body_tee.as_ref().map(|body_tee| body_tee.bytes())
+    }
+
+    ///
+    /// Returns request method name as received in the request line, e.g. `GET` or `PROPFIND`.
+    ///
+    pub fn method_str(&self) -> &str {
+        self.method.as_str()
+    }
+
+    ///
+    /// Returns address of the connected client, either TCP socket address or Unix socket path.
+    /// Behind a reverse proxy, this is the address of the proxy.
+    ///
+    pub async fn remote_addr(&self) -> Option<PeerAddr> {
         self.stream.peer_addr().await
     }
 
+    ///
+    /// Deserializes query params into `T`. Repeated keys are deserialized as sequences and single
+    /// keys as scalars. Empty values like `?page=` are deserialized as `None` for optional fields.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Pagination {
+    ///     page: u32,
+    ///     size: Option<u32>,
+    /// }
+    ///
+    /// async fn users(request: Request) -> Response {
+    ///     // For `/users?page=2&size=10`
+    ///     let pagination: Pagination = match request.query() {
+    ///         Ok(pagination) => pagination,
+    ///         Err(error) => return HttpResponse::bad_request().body(error.to_string()),
+    ///     };
+    ///
+    ///     let size = pagination.size.unwrap_or(20);
+    ///     HttpResponse::ok().body(format!("Page {} of size {}", pagination.page, size))
+    /// }
+    /// ```
+    ///
+    pub fn query<T: DeserializeOwned>(&self) -> Result<T, QueryParseError> {
+        query::from_query_params(&self.query_params)
+    }
+
+    ///
+    /// Returns absolute URL of the given path on the host requested by the client, e.g. for
+    /// redirect URIs, pagination links or canonical URLs. Default ports are omitted, and paths
+    /// which are already absolute URLs are returned as they are.
+    ///
+    /// The `Host` header is sent by the client, so validate it against the known hosts when the
+    /// URL is sent to other users.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn login(request: Request) -> Response {
+    ///     // For `Host: example.com`, returns `http://example.com/oauth/callback`.
+    ///     let redirect_uri = request.absolute_url("/oauth/callback");
+    ///     HttpResponse::ok().body(redirect_uri)
+    /// }
+    /// ```
+    ///
+    pub fn absolute_url(&self, path: &str) -> String {
+        let host = self.headers.value("Host").unwrap_or_default();
+        build_absolute_url(&self.scheme, &host, path)
+    }
+
+    ///
+    /// Returns URL decoded value of the cookie received in `Cookie` header. Cookie name is
+    /// case-insensitive.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn home(request: Request) -> Response {
+    ///     let theme = request.cookie("theme").unwrap_or("light".to_string());
+    ///     HttpResponse::ok().body(format!("Theme: {}", theme))
+    /// }
+    /// ```
+    ///
+    pub fn cookie<S: AsRef<str>>(&self, name: S) -> Option<String> {
+        self.cookies.value(name).cloned()
+    }
+
     pub fn context<T: 'static>(&self) -> Option<&T> {
         self.context.downcast_ref::<T>()
     }
 
+    ///
+    /// Returns values attached to this request. Unlike `context`, extensions are not shared with
+    /// other requests, so middleware can pass per-request data such as the authenticated user to
+    /// the view. See `Extensions` for an example.
+    ///
+    pub fn extensions(&self) -> &Mutex<Extensions> {
+        &self.extensions
+    }
+
+    ///
+    /// Returns media type of the request body from `Content-Type` header without parameters such as
+    /// `charset` or `boundary`. The value is lowercased and trimmed, e.g. `multipart/form-data`.
+    ///
+    pub fn media_type(&self) -> Option<String> {
+        let content_type = self.headers.value("Content-Type")?;
+        Some(headers::media_type(content_type))
+    }
+
+    ///
+    /// Returns byte range of the partial body sent in `Content-Range` header, used by clients
+    /// resuming uploads. Returns `None` if the header is missing or invalid.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::SeekFrom;
+    ///
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    /// use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+    ///
+    /// async fn upload(request: Request) -> Response {
+    ///     let range = match request.content_range() {
+    ///         Some(range) => range,
+    ///         None => return HttpResponse::bad_request().body("Invalid Content-Range."),
+    ///     };
+    ///
+    ///     // Size of the chunk must be limited before reading it to memory.
+    ///     if range.length() > 1024 * 1024 {
+    ///         return HttpResponse::payload_too_large().body("Chunk is too large.");
+    ///     }
+    ///
+    ///     let mut chunk = vec![];
+    ///     while (chunk.len() as u64) < range.length() {
+    ///         match request.stream.read_chunk().await {
+    ///             Ok(bytes) if !bytes.is_empty() => chunk.extend(bytes),
+    ///             _ => return HttpResponse::bad_request().body("Incomplete chunk."),
+    ///         }
+    ///     }
+    ///     chunk.truncate(range.length() as usize);
+    ///     request.body_read.store(true, std::sync::atomic::Ordering::Relaxed);
+    ///
+    ///     // Writes chunk at its offset, so chunks can arrive in any order or be retried.
+    ///     let mut file = tokio::fs::OpenOptions::new()
+    ///         .create(true)
+    ///         .write(true)
+    ///         .open("/tmp/upload.bin")
+    ///         .await
+    ///         .unwrap();
+    ///     file.seek(SeekFrom::Start(range.start)).await.unwrap();
+    ///     file.write_all(&chunk).await.unwrap();
+    ///
+    ///     if range.is_last() {
+    ///         return HttpResponse::created().body("Upload complete.");
+    ///     }
+    ///     HttpResponse::ok().body(format!("Received bytes up to {}.", range.end))
+    /// }
+    /// ```
+    ///
+    pub fn content_range(&self) -> Option<ContentRange> {
+        let value = self.headers.value("Content-Range")?;
+        ContentRange::parse(value)
+    }
+
+    ///
+    /// Returns date of `If-Modified-Since` header. Returns `None` if the header is missing or the
+    /// date is malformed, in which case the header must be ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use chrono::{DateTime, Utc};
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn report(request: Request) -> Response {
+    ///     let last_modified = DateTime::<Utc>::from_timestamp(784111777, 0).unwrap();
+    ///
+    ///     if let Some(date) = request.if_modified_since() {
+    ///         if last_modified <= date {
+    ///             return HttpResponse::not_modified().empty();
+    ///         }
+    ///     }
+    ///     HttpResponse::ok().body("Report")
+    /// }
+    /// ```
+    ///
+    pub fn if_modified_since(&self) -> Option<DateTime<Utc>> {
+        let value = self.headers.value("If-Modified-Since")?;
+        headers::parse_http_date(&value)
+    }
+
+    ///
+    /// Returns date of `If-Unmodified-Since` header. Returns `None` if the header is missing or the
+    /// date is malformed, in which case the header must be ignored.
+    ///
+    pub fn if_unmodified_since(&self) -> Option<DateTime<Utc>> {
+        let value = self.headers.value("If-Unmodified-Since")?;
+        headers::parse_http_date(&value)
+    }
+
     pub async fn parse(&self) -> (FormData, Files) {
         return match self.parse_body(self.form_constraints.clone()).await {
             Ok((form_data, files)) => (form_data, files),
@@ -94,16 +431,60 @@ impl Request {
         };
     }
 
+    ///
+    /// Same as `parse` but reports upload progress of multipart file fields to the channel.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::forms::UploadProgress;
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn upload(request: Request) -> Response {
+    ///     let (sender, mut receiver) = tokio::sync::mpsc::channel::<UploadProgress>(16);
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Some(progress) = receiver.recv().await {
+    ///             println!("{}: {} bytes received", progress.field, progress.bytes_so_far);
+    ///         }
+    ///     });
+    ///
+    ///     let (form_data, files) = request.parse_with_progress(sender).await;
+    ///     HttpResponse::ok().body("Uploaded")
+    /// }
+    /// ```
+    ///
+    pub async fn parse_with_progress(&self, progress: Sender<UploadProgress>) -> (FormData, Files) {
+        let form_constraints = self.form_constraints.clone();
+
+        return match self
+            .parse_body_with_progress(form_constraints, Some(progress))
+            .await
+        {
+            Ok((form_data, files)) => (form_data, files),
+            Err(_) => (FormData::new(), Files::new()),
+        };
+    }
+
     pub async fn parse_body(
         &self,
         form_constraints: Arc<FormConstraints>,
+    ) -> Result<(FormData, Files), FormFieldError> {
+        self.parse_body_with_progress(form_constraints, None).await
+    }
+
+    pub async fn parse_body_with_progress(
+        &self,
+        form_constraints: Arc<FormConstraints>,
+        progress: Option<Sender<UploadProgress>>,
     ) -> Result<(FormData, Files), FormFieldError> {
         let form_data = FormData::new();
         let files = Files::new();
 
-        let content_type;
-        if let Some(value) = self.headers.value("Content-Type") {
-            content_type = value;
+        let media_type;
+        if let Some(value) = self.media_type() {
+            media_type = value;
         } else {
             racoon_debug!("Content type is missing.");
             return Ok((form_data, files));
@@ -112,16 +493,16 @@ impl Request {
         let body_read = self.body_read.clone();
         body_read.store(false, Ordering::Relaxed);
 
-        if content_type
-            .to_lowercase()
-            .starts_with("multipart/form-data")
-        {
+        let headers = self.decode_chunked_body(&form_constraints).await?;
+
+        if media_type == "multipart/form-data" {
             racoon_debug!("Parsing with MultipartParser");
 
-            return match MultipartParser::parse(
+            return match MultipartParser::parse_with_progress(
                 self.stream.clone(),
                 form_constraints,
-                &self.headers,
+                &headers,
+                progress,
             )
             .await
             {
@@ -134,17 +515,10 @@ impl Request {
                     Err(error)
                 }
             };
-        } else if content_type
-            .to_lowercase()
-            .starts_with("application/x-www-form-urlencoded")
-        {
+        } else if media_type == "application/x-www-form-urlencoded" {
             racoon_debug!("Parsing with UrlEncoded parser.");
 
-            return match UrlEncodedParser::parse(
-                self.stream.clone(),
-                &self.headers,
-                form_constraints,
-            )
+            return match UrlEncodedParser::parse(self.stream.clone(), &headers, form_constraints)
             .await
             {
                 Ok(form_data) => {
@@ -158,9 +532,234 @@ impl Request {
             };
         }
 
-        racoon_debug!("Unhandled enctype: {}", content_type);
+        racoon_debug!("Unhandled enctype: {}", media_type);
         Ok((form_data, files))
     }
+
+    ///
+    /// Decodes body sent with chunked transfer coding and restores it back to the stream, so the
+    /// body parsers read it like a body with `Content-Length`. Returns headers to pass to the
+    /// parsers.
+    ///
+    async fn decode_chunked_body(
+        &self,
+        form_constraints: &FormConstraints,
+    ) -> Result<Headers, FormFieldError> {
+        let headers = self.headers.clone();
+        if !chunked::is_chunked(&headers) {
+            return Ok(headers);
+        }
+
+        let max_body_size = form_constraints.max_body_size(self.stream.buffer_size().await);
+
+        // Raw chunked body is not recorded by the tee. The decoded body restored below is
+        // recorded when the parsers read it.
+        if let Some(body_tee) = &self.body_tee {
+            body_tee.set_paused(true);
+        }
+        let result = self.restore_decoded_body(headers, max_body_size).await;
+        if let Some(body_tee) = &self.body_tee {
+            body_tee.set_paused(false);
+        }
+        result
+    }
+
+    async fn restore_decoded_body(
+        &self,
+        mut headers: Headers,
+        max_body_size: usize,
+    ) -> Result<Headers, FormFieldError> {
+        let (mut body, remaining) = chunked::read_chunked_body(&self.stream, max_body_size).await?;
+
+        headers.retain(|name, _| {
+            !name.eq_ignore_ascii_case("Transfer-Encoding")
+                && !name.eq_ignore_ascii_case("Content-Length")
+        });
+        headers.set("Content-Length", body.len().to_string());
+
+        body.extend(remaining);
+        if !body.is_empty() {
+            if let Err(error) = self.stream.restore_payload(&body).await {
+                return Err(FormFieldError::Others(None, error.to_string(), true));
+            }
+        }
+        Ok(headers)
+    }
+
+    ///
+    /// Reads the raw request body, for example to verify webhook signatures or decode custom
+    /// content types. Body sent with `Transfer-Encoding: chunked` is decoded, otherwise exactly
+    /// `Content-Length` bytes are read. Without `Content-Length`, body sent with other transfer
+    /// codings is read until the client closes the connection, and requests without either
+    /// header have empty body. Body larger than `max_body_size` of the form constraints is rejected.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn webhook(request: Request) -> Response {
+    ///     let body = match request.body_bytes().await {
+    ///         Ok(body) => body,
+    ///         Err(error) => return HttpResponse::bad_request().body(error.to_string()),
+    ///     };
+    ///
+    ///     HttpResponse::ok().body(format!("Received {} bytes", body.len()))
+    /// }
+    /// ```
+    ///
+    pub async fn body_bytes(&self) -> std::io::Result<Vec<u8>> {
+        let max_body_size = self
+            .form_constraints
+            .max_body_size(self.stream.buffer_size().await);
+        let max_body_size_error =
+            || std::io::Error::new(std::io::ErrorKind::InvalidData, "Max body size exceeded.");
+
+        if chunked::is_chunked(&self.headers) {
+            self.body_read.store(false, Ordering::Relaxed);
+            let result = chunked::read_chunked_body(&self.stream, max_body_size).await;
+            let (body, remaining) = match result {
+                Ok(result) => result,
+                Err(FormFieldError::MaxBodySizeExceed) => return Err(max_body_size_error()),
+                Err(error) => return Err(std::io::Error::other(format!("{:?}", error))),
+            };
+
+            // Bytes after the body belong to the next request.
+            if !remaining.is_empty() {
+                self.stream.restore_payload(&remaining).await?;
+            }
+            self.body_read.store(true, Ordering::Relaxed);
+            return Ok(body);
+        }
+
+        let content_length = match self.headers.value("Content-Length") {
+            Some(value) => match value.trim().parse::<usize>() {
+                Ok(content_length) => Some(content_length),
+                Err(_) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "Invalid Content-Length header.",
+                    ));
+                }
+            },
+            None => None,
+        };
+
+        if content_length.is_none() && self.headers.value("Transfer-Encoding").is_none() {
+            return Ok(vec![]);
+        }
+
+        if content_length.unwrap_or_default() > max_body_size {
+            return Err(max_body_size_error());
+        }
+
+        self.body_read.store(false, Ordering::Relaxed);
+        let mut buffer = Vec::with_capacity(content_length.unwrap_or_default());
+
+        loop {
+            if let Some(content_length) = content_length {
+                if buffer.len() >= content_length {
+                    break;
+                }
+            }
+
+            let chunk = match self.stream.read_chunk().await {
+                Ok(chunk) => chunk,
+                // Without Content-Length, body ends when the client closes the connection.
+                Err(error)
+                    if content_length.is_none()
+                        && error.kind() == std::io::ErrorKind::BrokenPipe =>
+                {
+                    break;
+                }
+                Err(error) => return Err(error),
+            };
+
+            buffer.extend(chunk);
+            if buffer.len() > max_body_size && content_length.is_none() {
+                return Err(max_body_size_error());
+            }
+        }
+
+        // Bytes after the body belong to the next request.
+        if let Some(content_length) = content_length {
+            if buffer.len() > content_length {
+                let remaining = buffer.split_off(content_length);
+                self.stream.restore_payload(&remaining).await?;
+            }
+        }
+        self.body_read.store(true, Ordering::Relaxed);
+        Ok(buffer)
+    }
+
+    ///
+    /// Reads `application/json` request body and deserializes it to `T`. Body is read with
+    /// `body_bytes`, so chunked body is supported. Body larger than `max_body_size` of the form
+    /// constraints is rejected.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn create_user(request: Request) -> Response {
+    ///     let user: serde_json::Value = match request.json().await {
+    ///         Ok(user) => user,
+    ///         Err(error) => {
+    ///             return HttpResponse::bad_request().body(format!("{:?}", error));
+    ///         }
+    ///     };
+    ///
+    ///     HttpResponse::created().body(format!("Created user: {}", user["name"]))
+    /// }
+    /// ```
+    ///
+    pub async fn json<T: DeserializeOwned>(&self) -> Result<T, JsonBodyError> {
+        let media_type = match self.media_type() {
+            Some(media_type) => media_type,
+            None => return Err(JsonBodyError::MissingContentType),
+        };
+
+        if media_type != "application/json" && !media_type.ends_with("+json") {
+            return Err(JsonBodyError::InvalidContentType(media_type));
+        }
+
+        let is_chunked = chunked::is_chunked(&self.headers);
+        if !is_chunked {
+            let content_length = match self.headers.value("Content-Length") {
+                Some(value) => match value.trim().parse::<usize>() {
+                    Ok(content_length) => content_length,
+                    Err(_) => return Err(JsonBodyError::InvalidContentLength),
+                },
+                None => return Err(JsonBodyError::InvalidContentLength),
+            };
+
+            let max_body_size = self
+                .form_constraints
+                .max_body_size(self.stream.buffer_size().await);
+
+            if content_length > max_body_size {
+                return Err(JsonBodyError::MaxBodySizeExceed);
+            }
+        }
+
+        let buffer = match self.body_bytes().await {
+            Ok(buffer) => buffer,
+            // Chunked body is checked against the limit while it is decoded.
+            Err(error) if is_chunked && error.kind() == std::io::ErrorKind::InvalidData => {
+                return Err(JsonBodyError::MaxBodySizeExceed);
+            }
+            Err(error) => return Err(JsonBodyError::Io(error)),
+        };
+
+        serde_json::from_slice(&buffer).map_err(|error| JsonBodyError::Deserialize {
+            message: error.to_string(),
+            line: error.line(),
+            column: error.column(),
+        })
+    }
 }
 
 impl Clone for Request {
@@ -173,6 +772,7 @@ impl Clone for Request {
             path: self.path.clone(),
             http_version: self.http_version.clone(),
             headers: self.headers.clone(),
+            raw_headers: self.raw_headers.clone(),
             path_params: self.path_params.clone(),
             query_params: self.query_params.clone(),
             cookies: self.cookies.clone(),
@@ -180,6 +780,12 @@ impl Clone for Request {
             body_read: self.body_read.clone(),
             form_constraints: self.form_constraints.clone(),
             response_headers: self.response_headers.clone(),
+            middleware_chain: self.middleware_chain.clone(),
+            body_tee: self.body_tee.clone(),
+            websocket_connections: self.websocket_connections.clone(),
+            request_id: self.request_id.clone(),
+            extensions: self.extensions.clone(),
+            server_response: self.server_response.clone(),
         }
     }
 }
@@ -187,5 +793,305 @@ impl Clone for Request {
 #[derive(Debug)]
 pub enum RequestError {
     HeaderSizeExceed,
+    /// Request header is not received within the read timeout or minimum read rate.
+    TimedOut,
     Others(String),
 }
+
+fn build_absolute_url(scheme: &str, host: &str, path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return path.to_string();
+    }
+
+    let scheme = scheme.to_lowercase();
+    let host = host.trim();
+    let default_port = match scheme.as_str() {
+        "https" => ":443",
+        _ => ":80",
+    };
+    let host = host.strip_suffix(default_port).unwrap_or(host);
+
+    if path.starts_with('/') {
+        format!("{}://{}{}", scheme, host, path)
+    } else {
+        format!("{}://{}/{}", scheme, host, path)
+    }
+}
+
+#[derive(Debug)]
+pub enum JsonBodyError {
+    /// Content-Type header is missing.
+    MissingContentType,
+    /// Media type is not `application/json` or `+json`.
+    InvalidContentType(String),
+    /// Content-Length header is invalid, or missing for body without chunked transfer coding.
+    InvalidContentLength,
+    /// Body is larger than maximum allowed body size.
+    MaxBodySizeExceed,
+    /// Failed to read body from the stream.
+    Io(std::io::Error),
+    /// Body is not valid JSON for the requested type.
+    Deserialize {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use crate::core::forms::{FileFieldShortcut, FormConstraints};
+    use crate::core::path::{Path, View};
+    use crate::core::request::{build_absolute_url, JsonBodyError, Request};
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::{HttpResponse, Response};
+    use crate::core::server::tests::{serve_raw, test_state};
+    use crate::core::shortcuts::SingleText;
+
+    async fn theme(request: Request) -> Response {
+        let theme = request.cookie("Theme").unwrap_or("none".to_string());
+        HttpResponse::ok().body(theme)
+    }
+
+    #[test]
+    fn test_build_absolute_url() {
+        assert_eq!(
+            "http://example.com/callback",
+            build_absolute_url("http", "example.com:80", "/callback")
+        );
+        assert_eq!(
+            "https://example.com/page?n=2",
+            build_absolute_url("https", "example.com:443", "page?n=2")
+        );
+        assert_eq!(
+            "http://localhost:8080/",
+            build_absolute_url("http", "localhost:8080", "/")
+        );
+        assert_eq!(
+            "https://[::1]:8443/a",
+            build_absolute_url("https", "[::1]:8443", "/a")
+        );
+        assert_eq!(
+            "https://cdn.example.com/a.js",
+            build_absolute_url("http", "example.com", "https://cdn.example.com/a.js")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cookie() {
+        let view: View = |request| Box::pin(theme(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"GET / HTTP/1.1\r\nCookie: sessionid=1; theme=dark%20blue\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("dark blue"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let response = serve_raw(state, b"GET / HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.ends_with("none"));
+    }
+
+    async fn create_user(request: Request) -> Response {
+        match request.json::<HashMap<String, String>>().await {
+            Ok(user) => HttpResponse::ok().body(format!("name={}", user["name"])),
+            Err(JsonBodyError::Deserialize { line, column, .. }) => {
+                HttpResponse::bad_request().body(format!("error at {}:{}", line, column))
+            }
+            Err(error) => HttpResponse::bad_request().body(format!("{:?}", error)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json() {
+        let view: View = |request| Box::pin(create_user(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/json; charset=utf-8\r\n\
+            Content-Length: 16\r\n\r\n{\"name\": \"John\"}";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("name=John"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/json\r\n\
+            Content-Length: 11\r\n\r\n{\n\"name\": }";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("error at 2:9"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: text/plain\r\n\
+            Content-Length: 2\r\n\r\n{}";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("InvalidContentType(\"text/plain\")"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let response = serve_raw(state, b"POST / HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.ends_with("MissingContentType"));
+
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.form_constraints = Arc::new(FormConstraints::new(2, 2, 2, 2, HashMap::new()));
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/json\r\n\
+            Content-Length: 100000\r\n\r\n{}";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("MaxBodySizeExceed"));
+
+        // Chunked body
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/json\r\n\
+            Transfer-Encoding: chunked\r\n\r\n9\r\n{\"name\": \r\n7\r\n\"John\"}\r\n0\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("name=John"));
+
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.form_constraints = Arc::new(FormConstraints::new(2, 2, 2, 2, HashMap::new()));
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/json\r\n\
+            Transfer-Encoding: chunked\r\n\r\nFFFFF\r\n{}\r\n0\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("MaxBodySizeExceed"));
+    }
+
+    async fn echo_body(request: Request) -> Response {
+        match request.body_bytes().await {
+            Ok(body) => HttpResponse::ok().body(format!("body={}", String::from_utf8_lossy(&body))),
+            Err(error) => HttpResponse::bad_request().body(error.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_body_bytes() {
+        let view: View = |request| Box::pin(echo_body(request));
+
+        // Bytes after the body are read as the next request.
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/octet-stream\r\n\
+            Content-Length: 5\r\n\r\nHelloPOST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nHi";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.contains("body=Hello"));
+        assert_eq!(true, response.ends_with("body=Hi"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nTransfer-Encoding: identity\r\n\r\nHello World";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("body=Hello World"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let response = serve_raw(state, b"POST / HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.ends_with("body="));
+
+        let mut state = test_state(vec![Path::new("/", view)]).await;
+        state.form_constraints = Arc::new(FormConstraints::new(2, 2, 2, 2, HashMap::new()));
+        let request = b"POST / HTTP/1.1\r\nContent-Length: 100000\r\n\r\nHello";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 400 Bad Request"));
+        assert_eq!(true, response.ends_with("Max body size exceeded."));
+    }
+
+    async fn form_fields(request: Request) -> Response {
+        let (form_data, files) = request.parse().await;
+        let name = form_data.value("name").cloned().unwrap_or_default();
+        let file = files.value("file").map(|file| file.name.clone());
+        HttpResponse::ok().body(format!("name={} file={}", name, file.unwrap_or_default()))
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body() {
+        let view: View = |request| Box::pin(echo_body(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nHello\r\n6\r\n World\r\n0\r\n\r\n\
+            POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nHi";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.contains("body=Hello World"));
+        assert_eq!(true, response.ends_with("body=Hi"));
+
+        let view: View = |request| Box::pin(form_fields(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+            Transfer-Encoding: chunked\r\n\r\n4\r\nname\r\n5\r\n=John\r\n0\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("name=John file="));
+
+        let body = "--boundary123\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nJohn\r\n\
+            --boundary123\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            \r\nHello\r\n--boundary123--\r\n";
+        let (first, second) = body.split_at(50);
+        let request = format!(
+            "POST / HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=boundary123\r\n\
+            Transfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            first.len(),
+            first,
+            second.len(),
+            second
+        );
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.ends_with("name=John file=a.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_route_form_constraints() {
+        let view: View = |request| Box::pin(create_user(request));
+        let paths = || {
+            let config_constraints = FormConstraints::new(8, 8, 8, 8, HashMap::new());
+            vec![
+                Path::new("/users", view),
+                Path::new("/config", view).form_constraints(config_constraints),
+            ]
+        };
+        let body = b"{\"name\": \"John\"}";
+
+        let state = test_state(paths()).await;
+        let mut request = b"POST /users HTTP/1.1\r\nContent-Type: application/json\r\n\
+            Content-Length: 16\r\n\r\n"
+            .to_vec();
+        request.extend(body);
+        let response = serve_raw(state, &request).await;
+        assert_eq!(true, response.ends_with("name=John"));
+
+        // Limits smaller than the stream buffer size are raised to the buffer size.
+        let state = test_state(paths()).await;
+        let request = b"POST /config HTTP/1.1\r\nContent-Type: application/json\r\n\
+            Content-Length: 100000\r\n\r\n{}";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("MaxBodySizeExceed"));
+
+        // Server constraints allow the body, so it is read until the stream ends.
+        let state = test_state(paths()).await;
+        let request = b"POST /users HTTP/1.1\r\nContent-Type: application/json\r\n\
+            Content-Length: 100000\r\n\r\n{}";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.contains("No bytes left to read."));
+    }
+
+    async fn audited_login(mut request: Request) -> Response {
+        request.tee_body(10);
+        let (form_data, _) = request.parse().await;
+
+        let username = form_data.value("username").cloned().unwrap_or_default();
+        let raw_body = String::from_utf8(request.raw_body().unwrap()).unwrap();
+        HttpResponse::ok().body(format!("{} {}", username, raw_body))
+    }
+
+    #[tokio::test]
+    async fn test_tee_body() {
+        let view: View = |request| Box::pin(audited_login(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+            Content-Length: 12\r\n\r\nusername=jon";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("jon username=j"));
+
+        // Chunked body is recorded decoded.
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"POST / HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+            Transfer-Encoding: chunked\r\n\r\n5\r\nusern\r\n6\r\name=jo\r\n0\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.ends_with("jo username=j"));
+    }
+}
@@ -0,0 +1,372 @@
+use std::fmt::Display;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::core::request::QueryParams;
+
+#[derive(Debug, PartialEq)]
+pub enum QueryParseError {
+    /// (field_name)
+    MissingField(String),
+    InvalidValue {
+        field: String,
+        message: String,
+    },
+    Other(String),
+}
+
+impl Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingField(field) => write!(f, "Missing query parameter `{}`.", field),
+            Self::InvalidValue { field, message } => {
+                write!(
+                    f,
+                    "Invalid value of query parameter `{}`: {}",
+                    field, message
+                )
+            }
+            Self::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for QueryParseError {}
+
+impl de::Error for QueryParseError {
+    fn custom<T: Display>(message: T) -> Self {
+        Self::Other(message.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::MissingField(field.to_string())
+    }
+}
+
+///
+/// Deserializes query params into `T`. Keys with single value are deserialized as scalars and
+/// repeated keys as sequences. Scalars are parsed from the first value.
+///
+pub fn from_query_params<T: DeserializeOwned>(params: &QueryParams) -> Result<T, QueryParseError> {
+    T::deserialize(QueryDeserializer { params })
+}
+
+struct QueryDeserializer<'a> {
+    params: &'a QueryParams,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for QueryDeserializer<'a> {
+    type Error = QueryParseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(QueryMapAccess {
+            iter: self.params.iter(),
+            field: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+struct QueryMapAccess<'a> {
+    iter: std::collections::hash_map::Iter<'a, String, Vec<String>>,
+    /// Field whose value is deserialized next.
+    field: Option<(&'a String, &'a Vec<String>)>,
+}
+
+impl<'de, 'a> MapAccess<'de> for QueryMapAccess<'a> {
+    type Error = QueryParseError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((name, values)) => {
+                self.field = Some((name, values));
+                seed.deserialize(name.as_str().into_deserializer())
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (name, values) = match self.field.take() {
+            Some(field) => field,
+            None => return Err(de::Error::custom("Value is requested before key.")),
+        };
+
+        seed.deserialize(ValueDeserializer { values })
+            .map_err(|error| match error {
+                QueryParseError::Other(message) => QueryParseError::InvalidValue {
+                    field: name.to_string(),
+                    message,
+                },
+                error => error,
+            })
+    }
+}
+
+struct ValueDeserializer<'a> {
+    values: &'a [String],
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn first(&self) -> Result<&'a str, QueryParseError> {
+        match self.values.first() {
+            Some(value) => Ok(value),
+            None => Err(de::Error::custom("Value is empty.")),
+        }
+    }
+
+    fn parse<T>(&self) -> Result<T, QueryParseError>
+    where
+        T: std::str::FromStr,
+        T::Err: Display,
+    {
+        let value = self.first()?;
+        value
+            .trim()
+            .parse::<T>()
+            .map_err(|error| de::Error::custom(format!("{} ({:?})", error, value)))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method: ident => $visit: ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = QueryParseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.values.len() == 1 {
+            visitor.visit_str(&self.values[0])
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.first()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    ///
+    /// Missing and empty values like `?page=` are deserialized as `None`.
+    ///
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.values {
+            [] => visitor.visit_none(),
+            [value] if value.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(ValueSeqAccess {
+            iter: self.values.iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.first()?.into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct tuple_struct map struct
+    }
+}
+
+struct ValueSeqAccess<'a> {
+    iter: std::slice::Iter<'a, String>,
+}
+
+impl<'de, 'a> SeqAccess<'de> for ValueSeqAccess<'a> {
+    type Error = QueryParseError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some(value) => seed
+                .deserialize(ValueDeserializer {
+                    values: std::slice::from_ref(value),
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use serde::Deserialize;
+
+    use crate::core::request::QueryParams;
+
+    use super::{from_query_params, QueryParseError};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Pagination {
+        page: u32,
+        size: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+        Asc,
+        Desc,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        q: String,
+        tags: Vec<String>,
+        order: Option<Order>,
+        limit: Option<u8>,
+        exact: Option<bool>,
+    }
+
+    fn params(query: &[(&str, &[&str])]) -> QueryParams {
+        let mut params = QueryParams::new();
+        for (name, values) in query {
+            params.insert(
+                name.to_string(),
+                values.iter().map(|value| value.to_string()).collect(),
+            );
+        }
+        params
+    }
+
+    #[test]
+    fn test_from_query_params() {
+        let query = params(&[("page", &["2"]), ("size", &["10"]), ("unknown", &["1"])]);
+        let pagination: Pagination = from_query_params(&query).unwrap();
+        assert_eq!(Pagination { page: 2, size: 10 }, pagination);
+
+        let query = params(&[
+            ("q", &["racoon"]),
+            ("tags", &["web", "rust"]),
+            ("order", &["desc"]),
+            ("limit", &[""]),
+        ]);
+        let search: Search = from_query_params(&query).unwrap();
+        assert_eq!(
+            Search {
+                q: "racoon".to_string(),
+                tags: vec!["web".to_string(), "rust".to_string()],
+                order: Some(Order::Desc),
+                limit: None,
+                exact: None,
+            },
+            search
+        );
+
+        // Single value is a sequence with one item.
+        let query = params(&[("q", &["racoon"]), ("tags", &["web"]), ("exact", &["true"])]);
+        let search: Search = from_query_params(&query).unwrap();
+        assert_eq!(vec!["web".to_string()], search.tags);
+        assert_eq!(Some(true), search.exact);
+    }
+
+    #[test]
+    fn test_from_query_params_errors() {
+        let query = params(&[("page", &["2"])]);
+        let result: Result<Pagination, QueryParseError> = from_query_params(&query);
+        assert_eq!(
+            Err(QueryParseError::MissingField("size".to_string())),
+            result
+        );
+        assert_eq!(
+            "Missing query parameter `size`.",
+            result.unwrap_err().to_string()
+        );
+
+        let query = params(&[("page", &["two"]), ("size", &["10"])]);
+        let result: Result<Pagination, QueryParseError> = from_query_params(&query);
+        match result {
+            Err(QueryParseError::InvalidValue { field, .. }) => assert_eq!("page", field),
+            _ => panic!("Expected invalid value error."),
+        }
+
+        let query = params(&[
+            ("q", &["racoon"]),
+            ("tags", &["web"]),
+            ("order", &["random"]),
+        ]);
+        let result: Result<Search, QueryParseError> = from_query_params(&query);
+        match result {
+            Err(QueryParseError::InvalidValue { field, .. }) => assert_eq!("order", field),
+            _ => panic!("Expected invalid value error."),
+        }
+    }
+}
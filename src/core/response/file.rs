@@ -0,0 +1,376 @@
+use std::io::SeekFrom;
+use std::path::{Component, Path};
+use std::time::SystemTime;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::core::headers::{self, HeaderValue};
+use crate::core::method::Method;
+use crate::core::mime;
+use crate::core::request::Request;
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::streaming::StreamingResponse;
+use crate::core::response::{AbstractResponse, HttpResponse, Response};
+use crate::racoon_debug;
+
+/// Size of the chunks read from the file and written to the client.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    /// Range header is absent or not supported, so the whole file is served.
+    Full,
+    /// Inclusive start and end offsets.
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+///
+/// Serves a file from the disk. The body is streamed in chunks, so memory use does not depend on
+/// the file size.
+///
+/// Responds with `404 Not Found` if the file does not exist and `403 Forbidden` if the path
+/// contains `..` components. To serve files of a directory requested by the client, use
+/// `StaticFiles` which makes sure the path stays inside the directory.
+///
+/// # Examples
+/// ```
+/// use racoon::core::request::Request;
+/// use racoon::core::response::Response;
+/// use racoon::core::response::file::FileResponse;
+///
+/// async fn download(request: Request) -> Response {
+///     // Supports Range header for resuming downloads.
+///     FileResponse::from_request(&request, "./reports/latest.pdf").await
+/// }
+/// ```
+///
+pub struct FileResponse;
+
+impl FileResponse {
+    ///
+    /// Returns response with the whole file. `Content-Type` is guessed from the file extension and
+    /// `Last-Modified` is set from the file modification time.
+    ///
+    pub async fn from_path<P: AsRef<Path>>(path: P) -> Response {
+        Self::serve(path.as_ref(), None).await
+    }
+
+    ///
+    /// Same as `from_path` but honors single byte range requested in `Range` header with
+    /// `206 Partial Content` response. Unsatisfiable ranges receive `416 Range Not Satisfiable`.
+    ///
+    /// Conditional requests are answered with `304 Not Modified` if the file is not modified
+    /// after `If-Modified-Since` date and with `412 Precondition Failed` if it is modified after
+    /// `If-Unmodified-Since` date.
+    ///
+    pub async fn from_request<P: AsRef<Path>>(request: &Request, path: P) -> Response {
+        Self::serve(path.as_ref(), Some(request)).await
+    }
+
+    async fn serve(path: &Path, request: Option<&Request>) -> Response {
+        if path
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            racoon_debug!("Denied access to path: {:?}", path);
+            return HttpResponse::forbidden().body("Forbidden");
+        }
+
+        let mut file = match tokio::fs::File::open(path).await {
+            Ok(file) => file,
+            Err(error) => {
+                racoon_debug!("Failed to open {:?}. Error: {}", path, error);
+                return HttpResponse::not_found().body("404 Page not found");
+            }
+        };
+
+        let (file_size, modified) = match file.metadata().await {
+            Ok(metadata) if metadata.is_file() => (metadata.len(), metadata.modified().ok()),
+            _ => return HttpResponse::not_found().body("404 Page not found"),
+        };
+
+        if let (Some(request), Some(modified)) = (request, modified) {
+            if let Some(response) = check_preconditions(request, modified) {
+                return response;
+            }
+        }
+
+        let range = request.and_then(|request| request.headers.value("Range"));
+        let byte_range = match range {
+            Some(range) => parse_byte_range(&range, file_size),
+            None => ByteRange::Full,
+        };
+
+        let (start, end) = match byte_range {
+            ByteRange::Full => (0, file_size.saturating_sub(1)),
+            ByteRange::Partial(start, end) => (start, end),
+            ByteRange::Unsatisfiable => {
+                let mut response = HttpResponse::range_not_satisfiable();
+                response
+                    .get_headers()
+                    .set("Content-Range", format!("bytes */{}", file_size));
+                return response.body("Range Not Satisfiable");
+            }
+        };
+
+        let mut response;
+        if byte_range == ByteRange::Full {
+            response = StreamingResponse::ok();
+        } else {
+            response = StreamingResponse::partial_content();
+            response.get_headers().set(
+                "Content-Range",
+                format!("bytes {}-{}/{}", start, end, file_size),
+            );
+        }
+
+        let content_length = if file_size == 0 { 0 } else { end - start + 1 };
+        response.get_headers().set("Accept-Ranges", "bytes");
+        if let Some(modified) = modified {
+            response
+                .get_headers()
+                .set("Last-Modified", headers::http_date(modified));
+        }
+        response = response
+            .content_type(mime::from_path(path))
+            .content_length(content_length);
+
+        if start > 0 {
+            if let Err(error) = file.seek(SeekFrom::Start(start)).await {
+                racoon_debug!("Failed to seek {:?}. Error: {}", path, error);
+                return HttpResponse::internal_server_error().body("Internal Server Error");
+            }
+        }
+
+        response.body(move |mut writer| async move {
+            let mut remaining = content_length;
+            let mut buffer = vec![0; CHUNK_SIZE.min(content_length) as usize];
+
+            while remaining > 0 {
+                let read_size = CHUNK_SIZE.min(remaining) as usize;
+                let bytes_read = file.read(&mut buffer[..read_size]).await?;
+
+                if bytes_read == 0 {
+                    // File is truncated after the response headers were sent.
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "File ended before writing whole body.",
+                    ));
+                }
+
+                writer.write(&buffer[..bytes_read]).await?;
+                remaining -= bytes_read as u64;
+            }
+            Ok(())
+        })
+    }
+}
+
+///
+/// Evaluates `If-Unmodified-Since` and `If-Modified-Since` headers against the modification time
+/// of the file. Returns response to send instead of the file if a precondition is not met. Date
+/// headers are ignored if the entity tag headers are present.
+///
+fn check_preconditions(request: &Request, modified: SystemTime) -> Option<Response> {
+    // Dates in the headers have one second resolution.
+    let last_modified = DateTime::<Utc>::from(modified).trunc_subsecs(0);
+
+    if request.headers.value("If-Match").is_none() {
+        if let Some(date) = request.if_unmodified_since() {
+            if last_modified > date {
+                return Some(HttpResponse::precondition_failed().body("Precondition Failed"));
+            }
+        }
+    }
+
+    let is_get = request.method == Method::Get || request.method == Method::Head;
+    if is_get && request.headers.value("If-None-Match").is_none() {
+        if let Some(date) = request.if_modified_since() {
+            if last_modified <= date {
+                let mut response = HttpResponse::not_modified();
+                response
+                    .get_headers()
+                    .set("Last-Modified", headers::http_date(modified));
+                return Some(response.empty());
+            }
+        }
+    }
+
+    None
+}
+
+///
+/// Parses single range from the `Range` header value, e.g. `bytes=0-499`, `bytes=500-` or
+/// `bytes=-500`. Multiple ranges and other units are not supported and the whole file is served
+/// instead.
+///
+fn parse_byte_range(value: &str, file_size: u64) -> ByteRange {
+    let ranges = match value.trim().strip_prefix("bytes=") {
+        Some(ranges) => ranges.trim(),
+        None => return ByteRange::Full,
+    };
+
+    if ranges.contains(',') {
+        return ByteRange::Full;
+    }
+
+    let (start, end) = match ranges.split_once('-') {
+        Some((start, end)) => (start.trim(), end.trim()),
+        None => return ByteRange::Full,
+    };
+
+    if start.is_empty() {
+        // Suffix range containing the last bytes of the file.
+        let suffix_length = match end.parse::<u64>() {
+            Ok(length) => length,
+            Err(_) => return ByteRange::Full,
+        };
+
+        if suffix_length == 0 || file_size == 0 {
+            return ByteRange::Unsatisfiable;
+        }
+        return ByteRange::Partial(file_size.saturating_sub(suffix_length), file_size - 1);
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(start) => start,
+        Err(_) => return ByteRange::Full,
+    };
+
+    let end = if end.is_empty() {
+        file_size.saturating_sub(1)
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => end.min(file_size.saturating_sub(1)),
+            _ => return ByteRange::Full,
+        }
+    };
+
+    if start >= file_size {
+        return ByteRange::Unsatisfiable;
+    }
+
+    ByteRange::Partial(start, end)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::PathBuf;
+
+    use uuid::Uuid;
+
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::Response;
+    use crate::core::server::tests::{serve_raw, test_state};
+    use crate::core::shortcuts::SingleText;
+
+    use super::{parse_byte_range, ByteRange, FileResponse};
+
+    fn file_path(request: &Request) -> PathBuf {
+        let name = request.query_params.value("name").unwrap();
+        std::env::temp_dir().join(name)
+    }
+
+    async fn download(request: Request) -> Response {
+        FileResponse::from_request(&request, file_path(&request)).await
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(ByteRange::Partial(0, 4), parse_byte_range("bytes=0-4", 10));
+        assert_eq!(ByteRange::Partial(5, 9), parse_byte_range("bytes=5-", 10));
+        assert_eq!(ByteRange::Partial(7, 9), parse_byte_range("bytes=-3", 10));
+        assert_eq!(ByteRange::Partial(0, 9), parse_byte_range("bytes=-30", 10));
+        assert_eq!(
+            ByteRange::Partial(8, 9),
+            parse_byte_range("bytes=8-100", 10)
+        );
+        assert_eq!(ByteRange::Unsatisfiable, parse_byte_range("bytes=10-", 10));
+        assert_eq!(ByteRange::Unsatisfiable, parse_byte_range("bytes=-0", 10));
+        assert_eq!(ByteRange::Full, parse_byte_range("bytes=0-1,4-5", 10));
+        assert_eq!(ByteRange::Full, parse_byte_range("items=0-4", 10));
+        assert_eq!(ByteRange::Full, parse_byte_range("bytes=5-2", 10));
+    }
+
+    #[tokio::test]
+    async fn test_file_response() {
+        let name = format!("racoon-file-{}.txt", Uuid::new_v4());
+        std::fs::write(std::env::temp_dir().join(&name), "0123456789").unwrap();
+
+        let view: View = |request| Box::pin(download(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!("GET /?name={} HTTP/1.1\r\n\r\n", name);
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(true, response.contains("Content-Type: text/plain"));
+        assert_eq!(true, response.contains("Content-Length: 10\r\n"));
+        assert_eq!(false, response.contains("Transfer-Encoding"));
+        assert_eq!(true, response.ends_with("\r\n\r\n0123456789"));
+        assert_eq!(true, response.contains("Last-Modified: "));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!(
+            "GET /?name={} HTTP/1.1\r\nIf-Modified-Since: Fri, 01 Jan 2100 00:00:00 GMT\r\n\r\n",
+            name
+        );
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 304"));
+        assert_eq!(true, response.contains("Last-Modified: "));
+        assert_eq!(false, response.contains("0123456789"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!(
+            "GET /?name={} HTTP/1.1\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n",
+            name
+        );
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 200 OK"));
+
+        // Malformed dates are ignored.
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!(
+            "GET /?name={} HTTP/1.1\r\nIf-Modified-Since: tomorrow\r\n\r\n",
+            name
+        );
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 200 OK"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!(
+            "GET /?name={} HTTP/1.1\r\nIf-Unmodified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n",
+            name
+        );
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 412"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!("GET /?name={} HTTP/1.1\r\nRange: bytes=2-5\r\n\r\n", name);
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 206"));
+        assert_eq!(true, response.contains("Content-Range: bytes 2-5/10\r\n"));
+        assert_eq!(true, response.contains("Content-Length: 4\r\n"));
+        assert_eq!(true, response.ends_with("\r\n\r\n2345"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!("GET /?name={} HTTP/1.1\r\nRange: bytes=20-\r\n\r\n", name);
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 416"));
+        assert_eq!(true, response.contains("Content-Range: bytes */10\r\n"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"GET /?name=racoon-missing-file.txt HTTP/1.1\r\n\r\n";
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 404"));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = format!("GET /?name=../tmp/{} HTTP/1.1\r\n\r\n", name);
+        let response = serve_raw(state, request.as_bytes()).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 403"));
+
+        let _ = std::fs::remove_file(std::env::temp_dir().join(name));
+    }
+}
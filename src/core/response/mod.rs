@@ -1,20 +1,75 @@
+pub mod file;
+pub mod sse;
 pub mod status;
+pub mod streaming;
 
 use std::collections::HashMap;
 use std::time::Duration;
 
 use serde_json::json;
 
-use crate::core::cookie;
-use crate::core::headers::{HeaderValue, Headers};
+use crate::core::cookie::{self, CookieBuilder, CookieOptions};
+use crate::core::headers::{self, HeaderValue, Headers};
 use crate::core::response::status::ResponseStatus;
+use crate::core::response::streaming::StreamingBody;
 
 pub trait AbstractResponse: Send {
     fn status(&self) -> (u32, String);
+
+    ///
+    /// Returns true if the server writes status, headers and body of this response. Otherwise the
+    /// handler has already written to the stream by itself and nothing is written by the server,
+    /// so `should_close` is the only way to control what happens to the connection afterwards.
+    ///
     fn serve_default(&mut self) -> bool;
     fn get_headers(&mut self) -> &mut Headers;
     fn get_body(&mut self) -> &mut Vec<u8>;
+
+    ///
+    /// Returns true if the connection must be closed after this response instead of reading the
+    /// next request from it, for example after WebSocket session or Server-Sent Events. It is
+    /// checked before the response is written, so the response carries `Connection: close`, and
+    /// requests pipelined after this one are not served. Returning false keeps the connection only
+    /// if the client and the server keep-alive policy allow it.
+    ///
     fn should_close(&mut self) -> bool;
+
+    ///
+    /// Returns true if the body is not served from `get_body`, because the handler writes the
+    /// response to the stream by itself like WebSocket, or the body is written in chunks like
+    /// `StreamingResponse` and `SseResponse`. Useful for middleware to log such responses
+    /// differently.
+    ///
+    fn was_custom_served(&self) -> bool {
+        false
+    }
+
+    ///
+    /// Replaces response body and updates `Content-Length` header to the new body length.
+    ///
+    fn set_body(&mut self, body: Vec<u8>) {
+        let content_length = body.len();
+        *self.get_body() = body;
+        self.get_headers()
+            .set("Content-Length", content_length.to_string());
+    }
+
+    ///
+    /// Returns body written in chunks after the headers are sent. Implemented by
+    /// `StreamingResponse`. The body is written only when `serve_default` returns true. If writing
+    /// the body fails, the connection is closed regardless of `should_close` because the client
+    /// cannot find the end of the response.
+    ///
+    fn take_streaming_body(&mut self) -> Option<StreamingBody> {
+        None
+    }
+
+    ///
+    /// Replaces status code and reason phrase, for example to turn a view's `500` into `503` from
+    /// middleware. Does nothing for responses written to the stream by the handler itself, like
+    /// WebSocket.
+    ///
+    fn set_status(&mut self, _status_code: u32, _status_text: &str) {}
 }
 
 pub type Response = Box<dyn AbstractResponse>;
@@ -48,6 +103,16 @@ impl AbstractResponse for HttpResponse {
     fn should_close(&mut self) -> bool {
         !self.keep_alive
     }
+
+    fn was_custom_served(&self) -> bool {
+        !self.serve_default
+    }
+
+    fn set_status(&mut self, status_code: u32, status_text: &str) {
+        assert_valid_status(status_code);
+        self.status_code = status_code;
+        self.status_text = status_text.to_owned();
+    }
 }
 
 impl HttpResponse {
@@ -56,8 +121,12 @@ impl HttpResponse {
         self
     }
 
+    ///
+    /// Passing false closes the connection after this response. Passing true keeps the connection
+    /// only if the client and the server keep-alive policy allow it.
+    ///
     pub fn keep_alive(mut self, is_alive: bool) -> Self {
-        self.keep_alive = !is_alive;
+        self.keep_alive = is_alive;
         self
     }
 
@@ -66,6 +135,34 @@ impl HttpResponse {
         self
     }
 
+    ///
+    /// Appends request header name to the `Vary` header, for example when the body depends on
+    /// `Accept` or `Accept-Language`, so caches do not serve this response to requests with other
+    /// values of the header. Names already listed are not repeated.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::headers::HeaderValue;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn greeting(request: Request) -> Response {
+    ///     let language = request.headers.value("Accept-Language").unwrap_or_default();
+    ///
+    ///     let response = HttpResponse::ok().vary("Accept-Language");
+    ///     if language.starts_with("ne") {
+    ///         return response.body("Namaste");
+    ///     }
+    ///     response.body("Hello")
+    /// }
+    /// ```
+    ///
+    pub fn vary(mut self, header_name: &str) -> Self {
+        headers::add_vary(&mut self.headers, header_name);
+        self
+    }
+
     pub fn location(mut self, url: &str) -> Box<Self> {
         self.get_headers().set("Location", url);
         Box::new(self)
@@ -76,20 +173,21 @@ impl HttpResponse {
 
         self.headers
             .set("Content-Length", data.len().to_string());
-
-        if self.headers.value("Connection").is_none() {
-            if self.keep_alive {
-                self.headers.set("Connection", "keep-alive");
-            } else {
-                self.headers.set("Connection", "close");
-            }
-        }
-
         self.body = data.as_bytes().to_vec();
 
         Box::new(self)
     }
 
+    ///
+    /// Serializes JSON value as response body and sets `application/json; charset=utf-8` content
+    /// type.
+    ///
+    pub fn json_body(mut self, json: serde_json::Value) -> Box<Self> {
+        self.headers
+            .set("Content-Type", "application/json; charset=utf-8");
+        self.body(json.to_string())
+    }
+
     pub fn empty(self) -> Box<Self> {
         self.body("")
     }
@@ -99,6 +197,62 @@ impl HttpResponse {
         cookie::set_cookie(headers, name, value, max_age);
     }
 
+    ///
+    /// Adds cookie to the response. Can be called multiple times, each call adds separate
+    /// `Set-Cookie` header.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use racoon::core::cookie::CookieOptions;
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn home(request: Request) -> Response {
+    ///     let options = CookieOptions {
+    ///         max_age: Some(Duration::from_secs(3600)),
+    ///         ..CookieOptions::default()
+    ///     };
+    ///
+    ///     HttpResponse::ok()
+    ///         .with_cookie("theme", "dark", options.clone())
+    ///         .with_cookie("language", "en", options)
+    ///         .body("Home")
+    /// }
+    /// ```
+    ///
+    pub fn with_cookie<S: AsRef<str>>(mut self, name: S, value: S, options: CookieOptions) -> Self {
+        cookie::set_cookie_with_options(&mut self.headers, name, value, &options);
+        self
+    }
+
+    ///
+    /// Adds cookie built with `CookieBuilder` to the response.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::cookie::{CookieBuilder, SameSite};
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// async fn home(request: Request) -> Response {
+    ///     let cookie = CookieBuilder::new("theme", "dark")
+    ///         .secure(request.scheme == "https")
+    ///         .same_site(SameSite::Lax);
+    ///
+    ///     let mut response = HttpResponse::ok();
+    ///     response.set_cookie_builder(cookie);
+    ///     response.body("Home")
+    /// }
+    /// ```
+    ///
+    pub fn set_cookie_builder(&mut self, cookie: CookieBuilder) {
+        cookie::set_cookie_builder(&mut self.headers, &cookie);
+    }
+
     pub fn remove_cookie<S: AsRef<str>>(&mut self, name: S) {
         let headers = &mut self.headers;
         let expire_header_value = format!(
@@ -111,6 +265,8 @@ impl HttpResponse {
 
 impl ResponseStatus for HttpResponse {
     fn with_status(status_code: u32, status_text: &str) -> Self {
+        assert_valid_status(status_code);
+
         Self {
             status_code,
             status_text: status_text.to_owned(),
@@ -122,9 +278,32 @@ impl ResponseStatus for HttpResponse {
     }
 }
 
+///
+/// Serializes response to HTTP/1.1 response bytes.
+///
+/// `Content-Length` header is recomputed from the actual body length, so body changes made after
+/// the response is constructed are always reflected. Responses with `Transfer-Encoding` header
+/// are left untouched. Responses with 1xx and 204 status codes never contain `Content-Length`.
+///
 pub fn response_to_bytes(response: &mut Box<dyn AbstractResponse>) -> Vec<u8> {
+    let (status_code, _) = response.status();
+    sync_content_length(status_code, response);
+
+    let mut response_bytes = headers_to_bytes(response);
+
+    // Body start
+    response_bytes.extend(response.get_body().as_slice());
+    response_bytes
+}
+
+///
+/// Serializes status line and headers of the response without the body.
+///
+pub(crate) fn headers_to_bytes(response: &mut Box<dyn AbstractResponse>) -> Vec<u8> {
     let mut response_bytes: Vec<u8> = Vec::with_capacity(response.get_body().len());
     let (status_code, status_text) = response.status();
+    // Line breaks would end the status line early and inject headers.
+    let status_text = status_text.replace(['\r', '\n'], "");
 
     // Append header response start line
     let response_header_begin = format!("HTTP/1.1 {} {}\r\n", status_code, status_text);
@@ -141,40 +320,193 @@ pub fn response_to_bytes(response: &mut Box<dyn AbstractResponse>) -> Vec<u8> {
     });
 
     response_bytes.extend(b"\r\n");
-
-    // Body start
-    response_bytes.extend(response.get_body().as_slice());
     response_bytes
 }
 
+///
+/// Sets `Connection` header for the connection persistence decided by the server. This is the
+/// only place setting the header for served responses. Responses upgrading the protocol keep
+/// their `Connection: upgrade` header.
+///
+pub(crate) fn set_connection_header(response: &mut Box<dyn AbstractResponse>, keep_alive: bool) {
+    let headers = response.get_headers();
+
+    if let Some(connection) = headers.value("Connection") {
+        let is_upgrade = connection
+            .split(',')
+            .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+
+        if is_upgrade {
+            return;
+        }
+    }
+
+    headers.retain(|name, _| !name.eq_ignore_ascii_case("Connection"));
+    if keep_alive {
+        headers.set("Connection", "keep-alive");
+    } else {
+        headers.set("Connection", "close");
+    }
+}
+
+fn sync_content_length(status_code: u32, response: &mut Box<dyn AbstractResponse>) {
+    if response.get_headers().value("Transfer-Encoding").is_some() {
+        // Body length is framed by the transfer coding.
+        return;
+    }
+
+    // Content-Length of 304 response refers to the selected representation, not the body.
+    if status_code == 304 {
+        return;
+    }
+
+    let content_length = response.get_body().len();
+    let headers = response.get_headers();
+    headers.retain(|name, _| !name.eq_ignore_ascii_case("Content-Length"));
+
+    if (100..200).contains(&status_code) || status_code == 204 {
+        return;
+    }
+
+    headers.set("Content-Length", content_length.to_string());
+}
+
+fn assert_valid_status(status_code: u32) {
+    assert!(
+        (100..=599).contains(&status_code),
+        "Invalid HTTP status code: {}. Status code must be between 100 and 599.",
+        status_code
+    );
+}
+
+///
+/// Function converting JSON value to response body text.
+///
+pub type JsonSerializer = fn(&serde_json::Value) -> String;
+
+fn compact_json(json: &serde_json::Value) -> String {
+    json.to_string()
+}
+
+fn pretty_json(json: &serde_json::Value) -> String {
+    serde_json::to_string_pretty(json).unwrap_or_else(|_| json.to_string())
+}
+
 pub struct JsonResponse {
     http_response: HttpResponse,
+    serializer: JsonSerializer,
 }
 
 impl JsonResponse {
+    ///
+    /// Serializes JSON body with indentation and line breaks. Useful for debugging endpoints.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{JsonResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    /// use serde_json::json;
+    ///
+    /// async fn debug_info(request: Request) -> Response {
+    ///     JsonResponse::ok().pretty().body(json!({"version": "1.0"}))
+    /// }
+    /// ```
+    ///
+    pub fn pretty(self) -> Self {
+        self.serializer(pretty_json)
+    }
+
+    ///
+    /// Overrides the function used to serialize JSON body. Default serializer writes compact JSON.
+    ///
+    pub fn serializer(mut self, serializer: JsonSerializer) -> Self {
+        self.serializer = serializer;
+        self
+    }
+
+    ///
+    /// Overrides the default `application/json; charset=utf-8` content type.
+    ///
+    pub fn content_type(mut self, value: &str) -> Self {
+        self.http_response = self.http_response.content_type(value);
+        self
+    }
+
+    ///
+    /// Appends request header name to the `Vary` header. See `HttpResponse::vary`.
+    ///
+    pub fn vary(mut self, header_name: &str) -> Self {
+        self.http_response = self.http_response.vary(header_name);
+        self
+    }
+
     pub fn body(mut self, json: serde_json::Value) -> Box<Self> {
-        let json_text = json.to_string();
+        let json_text = (self.serializer)(&json);
+        self.set_body_text(json_text);
+        Box::new(self)
+    }
 
-        self.http_response
-            .headers
-            .set("Content-Length", json_text.len().to_string().as_bytes());
-
-        if self.http_response.headers.value("Connection").is_none() {
-            if self.http_response.keep_alive {
-                self.http_response
-                    .headers
-                    .set("Connection", "keep-alive");
-            } else {
-                self.http_response
-                    .headers
-                    .set("Connection", "close");
-            }
+    ///
+    /// Creates JSONP response which wraps the JSON value in the given callback function and serves
+    /// it as `application/javascript`. Characters other than letters, digits, `_`, `$` and `.` are
+    /// removed from the callback name. If the remaining name is not a valid function name, such as
+    /// empty or starting with a digit, `callback` is used instead. The function is called only if
+    /// it is defined, including the objects of the dotted name such as `app.handle`.
+    ///
+    pub fn jsonp<S: AsRef<str>>(mut self, callback: S, json: serde_json::Value) -> Box<Self> {
+        let mut callback: String = callback
+            .as_ref()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '$' || *c == '.')
+            .collect();
+
+        // Each part of the dotted name must be an identifier.
+        let is_valid = callback.split('.').all(|part| {
+            part.chars()
+                .next()
+                .is_some_and(|first| !first.is_ascii_digit())
+        });
+        if !is_valid {
+            callback = "callback".to_string();
         }
 
-        self.http_response.body = json_text.as_bytes().to_vec();
+        let headers = self.http_response.get_headers();
+        headers.set("Content-Type", "application/javascript; charset=utf-8");
+        headers.set("X-Content-Type-Options", "nosniff");
+
+        // Objects of the dotted name are checked first, so missing object does not throw.
+        let parts: Vec<&str> = callback.split('.').collect();
+        let mut guard = String::new();
+        for index in 1..parts.len() {
+            guard.push_str(&format!(
+                "typeof {} !== 'undefined' && ",
+                parts[..index].join(".")
+            ));
+        }
+
+        // Line and paragraph separators are valid in JSON strings but end the string literals in
+        // older JavaScript engines.
+        let json_text = (self.serializer)(&json)
+            .replace('\u{2028}', "\\u2028")
+            .replace('\u{2029}', "\\u2029");
+
+        // Leading comment prevents the response from being sniffed as other content types.
+        let script = format!(
+            "/**/ {0}typeof {1} === 'function' && {1}({2});",
+            guard, callback, json_text
+        );
+        self.set_body_text(script);
         Box::new(self)
     }
 
+    fn set_body_text(&mut self, text: String) {
+        self.http_response
+            .headers
+            .set("Content-Length", text.len().to_string().as_bytes());
+        self.http_response.body = text.into_bytes();
+    }
+
     ///
     /// Creates empty JSON object response.
     ///
@@ -189,12 +521,27 @@ impl JsonResponse {
         self.http_response.set_cookie(name, value, max_age);
     }
 
+    ///
+    /// Adds cookie built with `CookieBuilder`. See `HttpResponse::set_cookie_builder`.
+    ///
+    pub fn set_cookie_builder(&mut self, cookie: CookieBuilder) {
+        self.http_response.set_cookie_builder(cookie);
+    }
+
     ///
     /// Removes cookie from "/" path.
     ///
     pub fn remove_cookie<S: AsRef<str>>(&mut self, name: S) {
         self.http_response.remove_cookie(name)
     }
+
+    ///
+    /// Adds cookie to the response. See `HttpResponse::with_cookie`.
+    ///
+    pub fn with_cookie<S: AsRef<str>>(mut self, name: S, value: S, options: CookieOptions) -> Self {
+        self.http_response = self.http_response.with_cookie(name, value, options);
+        self
+    }
 }
 
 impl AbstractResponse for JsonResponse {
@@ -217,14 +564,319 @@ impl AbstractResponse for JsonResponse {
     fn should_close(&mut self) -> bool {
         self.http_response.should_close()
     }
+
+    fn was_custom_served(&self) -> bool {
+        self.http_response.was_custom_served()
+    }
+
+    fn set_status(&mut self, status_code: u32, status_text: &str) {
+        self.http_response.set_status(status_code, status_text);
+    }
 }
 
 impl ResponseStatus for JsonResponse {
     fn with_status(status_code: u32, status_text: &str) -> Self {
         let mut http_response = HttpResponse::with_status(status_code, status_text);
         let headers = http_response.get_headers();
-        headers.set("Content-Type", "application/json");
+        headers.set("Content-Type", "application/json; charset=utf-8");
+
+        Self {
+            http_response,
+            serializer: compact_json,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::core::cookie::{CookieBuilder, CookieOptions, SameSite};
+    use crate::core::headers::HeaderValue;
+    use crate::core::response::sse::SseResponse;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::streaming::StreamingResponse;
+    use crate::core::response::{
+        response_to_bytes, set_connection_header, AbstractResponse, HttpResponse, JsonResponse,
+    };
+
+    #[test]
+    fn test_response_to_bytes_content_length() {
+        let mut response: Box<dyn AbstractResponse> = HttpResponse::ok().body("Hello World");
+        // Body rewritten directly without updating headers.
+        *response.get_body() = b"Hi".to_vec();
+        response.get_headers().set("content-length", "11");
+
+        let response_text = String::from_utf8(response_to_bytes(&mut response)).unwrap();
+        assert_eq!(true, response_text.contains("Content-Length: 2\r\n"));
+        assert_eq!(false, response_text.contains("11"));
+
+        let mut response: Box<dyn AbstractResponse> = HttpResponse::no_content().empty();
+        let response_text = String::from_utf8(response_to_bytes(&mut response)).unwrap();
+        assert_eq!(false, response_text.contains("Content-Length"));
+
+        let mut response: Box<dyn AbstractResponse> = HttpResponse::ok().empty();
+        response.get_headers().retain(|name, _| name != "Content-Length");
+        response.get_headers().set("Transfer-Encoding", "chunked");
+        let response_text = String::from_utf8(response_to_bytes(&mut response)).unwrap();
+        assert_eq!(false, response_text.contains("Content-Length"));
+    }
+
+    #[test]
+    fn test_custom_status() {
+        let mut response: Box<dyn AbstractResponse> =
+            HttpResponse::with_status(299, "Custom Success").body("Done");
+        let response_text = String::from_utf8(response_to_bytes(&mut response)).unwrap();
+        assert_eq!(true, response_text.starts_with("HTTP/1.1 299 Custom Success\r\n"));
+
+        let mut response: Box<dyn AbstractResponse> =
+            HttpResponse::with_status(299, "Custom\r\nX-Injected: 1").empty();
+        let response_text = String::from_utf8(response_to_bytes(&mut response)).unwrap();
+        assert_eq!(true, response_text.starts_with("HTTP/1.1 299 CustomX-Injected: 1\r\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid HTTP status code: 1000")]
+    fn test_invalid_status() {
+        let _ = HttpResponse::with_status(1000, "Invalid");
+    }
+
+    #[test]
+    fn test_set_status() {
+        let mut response: Box<dyn AbstractResponse> = JsonResponse::not_found().body(json!({}));
+        response.set_status(410, "Gone");
+        assert_eq!((410, "Gone".to_string()), response.status());
+
+        let response_text = String::from_utf8(response_to_bytes(&mut response)).unwrap();
+        assert_eq!(true, response_text.starts_with("HTTP/1.1 410 Gone\r\n"));
+    }
+
+    #[test]
+    fn test_set_connection_header() {
+        // Responses express intent with keep-alive flag. Header is set while serving.
+        let mut response: Box<dyn AbstractResponse> = HttpResponse::ok().body("Hello");
+        assert_eq!(None, response.get_headers().value("Connection"));
+
+        set_connection_header(&mut response, true);
+        assert_eq!(Some("keep-alive".to_string()), response.get_headers().value("Connection"));
+
+        response.get_headers().set("connection", "keep-alive");
+        set_connection_header(&mut response, false);
+        assert_eq!(Some("close".to_string()), response.get_headers().value("Connection"));
+        assert_eq!(false, response.get_headers().contains_key("connection"));
+
+        let mut response: Box<dyn AbstractResponse> = HttpResponse::switching_protocols().empty();
+        response.get_headers().set("Connection", "Upgrade");
+        set_connection_header(&mut response, false);
+        assert_eq!(Some("Upgrade".to_string()), response.get_headers().value("Connection"));
+    }
+
+    #[test]
+    fn test_set_body() {
+        let mut response: Box<dyn AbstractResponse> = HttpResponse::ok().body("Hello World");
+        response.set_body(b"Hi".to_vec());
+        assert_eq!(b"Hi".to_vec(), *response.get_body());
+        assert_eq!(Some("2".to_string()), response.get_headers().value("Content-Length"));
+    }
+
+    #[test]
+    fn test_http_response_json_body() {
+        let mut response = HttpResponse::ok().json_body(json!({"name": "John"}));
+        assert_eq!(
+            Some("application/json; charset=utf-8".to_string()),
+            response.get_headers().value("Content-Type")
+        );
+        assert_eq!(Some("15".to_string()), response.get_headers().value("Content-Length"));
+        assert_eq!(b"{\"name\":\"John\"}".to_vec(), *response.get_body());
+    }
+
+    #[test]
+    fn test_json_response_content_type() {
+        let mut response = JsonResponse::ok().body(json!({"name": "John"}));
+        assert_eq!(
+            Some("application/json; charset=utf-8".to_string()),
+            response.get_headers().value("Content-Type")
+        );
+
+        let mut response = JsonResponse::ok()
+            .content_type("application/problem+json")
+            .body(json!({}));
+        assert_eq!(
+            Some("application/problem+json".to_string()),
+            response.get_headers().value("Content-Type")
+        );
+    }
+
+    #[test]
+    fn test_vary() {
+        let mut response = HttpResponse::ok()
+            .vary("Accept")
+            .vary("Accept-Language")
+            .vary("accept")
+            .body("Hello");
+        assert_eq!(
+            Some("Accept, Accept-Language".to_string()),
+            response.get_headers().value("Vary")
+        );
+
+        let mut response = JsonResponse::ok().vary("Accept").body(json!({}));
+        assert_eq!(
+            Some("Accept".to_string()),
+            response.get_headers().value("Vary")
+        );
+    }
+
+    #[test]
+    fn test_json_response_serializer() {
+        let mut response = JsonResponse::ok().pretty().body(json!({"name": "John"}));
+        assert_eq!(b"{\n  \"name\": \"John\"\n}".to_vec(), *response.get_body());
+        assert_eq!(Some("20".to_string()), response.get_headers().value("Content-Length"));
+
+        let mut response = JsonResponse::ok()
+            .serializer(|json| format!("{}\n", json))
+            .body(json!([1.5, 2]));
+        assert_eq!(b"[1.5,2]\n".to_vec(), *response.get_body());
+    }
+
+    #[test]
+    fn test_jsonp_response() {
+        let mut response = JsonResponse::ok().jsonp("handle<script>", json!({"id": 1}));
+        assert_eq!(
+            Some("application/javascript; charset=utf-8".to_string()),
+            response.get_headers().value("Content-Type")
+        );
+
+        let body = String::from_utf8(response.get_body().clone()).unwrap();
+        assert_eq!(
+            "/**/ typeof handlescript === 'function' && handlescript({\"id\":1});",
+            body
+        );
+        assert_eq!(
+            Some(body.len().to_string()),
+            response.get_headers().value("Content-Length")
+        );
+
+        let response = JsonResponse::ok().jsonp("app.handle", json!(1));
+        let body = String::from_utf8(response.http_response.body.clone()).unwrap();
+        assert_eq!(
+            "/**/ typeof app !== 'undefined' && typeof app.handle === 'function' && app.handle(1);",
+            body
+        );
+
+        let response = JsonResponse::ok().jsonp("a.b.c", json!(1));
+        let body = String::from_utf8(response.http_response.body.clone()).unwrap();
+        assert_eq!(
+            "/**/ typeof a !== 'undefined' && typeof a.b !== 'undefined' && \
+            typeof a.b.c === 'function' && a.b.c(1);",
+            body
+        );
+
+        let response = JsonResponse::ok().jsonp("handle", json!("a\u{2028}b\u{2029}"));
+        let body = String::from_utf8(response.http_response.body.clone()).unwrap();
+        assert_eq!(
+            "/**/ typeof handle === 'function' && handle(\"a\\u2028b\\u2029\");",
+            body
+        );
+
+        // Invalid callback names are replaced with the default name.
+        for callback in ["<>", "1handle", "app..handle", "app.", "app.2"] {
+            let response = JsonResponse::ok().jsonp(callback, json!(1));
+            let body = String::from_utf8(response.http_response.body.clone()).unwrap();
+            assert_eq!("/**/ typeof callback === 'function' && callback(1);", body);
+        }
+    }
+
+    #[test]
+    fn test_with_cookie() {
+        let options = CookieOptions {
+            max_age: None,
+            path: "/admin".to_string(),
+            http_only: false,
+        };
+
+        let mut response = HttpResponse::ok()
+            .with_cookie("theme", "dark", CookieOptions::default())
+            .with_cookie("token", "a b", options)
+            .body("Hello");
+
+        let cookies = response.get_headers().get("Set-Cookie").unwrap();
+        assert_eq!(
+            vec![
+                b"theme=dark; Path=/; HttpOnly".to_vec(),
+                b"token=a%20b; Path=/admin".to_vec()
+            ],
+            *cookies
+        );
+
+        let options = CookieOptions {
+            max_age: Some(Duration::from_secs(60)),
+            ..CookieOptions::default()
+        };
+        let mut response = JsonResponse::ok()
+            .with_cookie("theme", "dark", options)
+            .empty();
+
+        let cookies = response.get_headers().value("Set-Cookie");
+        assert_eq!(true, cookies.is_some());
+        let cookie = cookies.unwrap();
+        assert_eq!(true, cookie.starts_with("theme=dark; Expires="));
+        assert_eq!(true, cookie.ends_with("; Path=/; HttpOnly"));
+    }
+
+    #[test]
+    fn test_set_cookie_builder() {
+        let mut response = HttpResponse::ok();
+        response.set_cookie_builder(
+            CookieBuilder::new("theme", "dark")
+                .path("/admin")
+                .domain("example.com")
+                .http_only(false)
+                .secure(true)
+                .same_site(SameSite::None),
+        );
+        response.set_cookie_builder(CookieBuilder::new("token", "a b").same_site(SameSite::Lax));
+        let mut response = response.body("Hello");
+
+        let cookies = response.get_headers().get("Set-Cookie").unwrap();
+        assert_eq!(
+            vec![
+                b"theme=dark; Domain=example.com; Path=/admin; Secure; SameSite=None".to_vec(),
+                b"token=a%20b; Path=/; HttpOnly; SameSite=Lax".to_vec()
+            ],
+            *cookies
+        );
+
+        let mut response = JsonResponse::ok();
+        response.set_cookie_builder(
+            CookieBuilder::new("theme", "dark").max_age(Duration::from_secs(60)),
+        );
+        let mut response = response.empty();
+
+        let cookie = response.get_headers().value("Set-Cookie").unwrap();
+        assert_eq!(true, cookie.starts_with("theme=dark; Expires="));
+        assert_eq!(true, cookie.ends_with("; Max-Age=60; Path=/; HttpOnly"));
+    }
+
+    #[test]
+    fn test_was_custom_served() {
+        let response: Box<dyn AbstractResponse> = HttpResponse::ok().body("Hello");
+        assert_eq!(false, response.was_custom_served());
+
+        let response: Box<dyn AbstractResponse> =
+            HttpResponse::ok().disable_serve_default().empty();
+        assert_eq!(true, response.was_custom_served());
+
+        let response: Box<dyn AbstractResponse> = JsonResponse::ok().body(json!({}));
+        assert_eq!(false, response.was_custom_served());
+
+        let response: Box<dyn AbstractResponse> =
+            StreamingResponse::ok().body(|mut writer| async move { writer.write("Hello").await });
+        assert_eq!(true, response.was_custom_served());
 
-        Self { http_response }
+        let (_sender, receiver) = tokio::sync::mpsc::channel(1);
+        let response: Box<dyn AbstractResponse> = SseResponse::from_channel(receiver);
+        assert_eq!(true, response.was_custom_served());
     }
 }
@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc::Receiver;
+
+use crate::core::headers::HeaderValue;
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::streaming::StreamingResponse;
+use crate::core::response::AbstractResponse;
+
+/// Default interval of heartbeat comments sent while no events are received.
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+///
+/// Single Server-Sent Event. Multiline data is sent as multiple `data` lines and joined back by
+/// the browser.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use racoon::core::response::sse::SseEvent;
+///
+/// let event = SseEvent::new("{\"price\": 20}")
+///     .event("price")
+///     .id("42")
+///     .retry(Duration::from_secs(5));
+///
+/// assert_eq!(
+///     b"event: price\nid: 42\nretry: 5000\ndata: {\"price\": 20}\n\n".to_vec(),
+///     event.to_bytes()
+/// );
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct SseEvent {
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseEvent {
+    pub fn new<S: AsRef<str>>(data: S) -> Self {
+        Self {
+            data: data.as_ref().to_string(),
+            event: None,
+            id: None,
+            retry: None,
+        }
+    }
+
+    ///
+    /// Sets event type. Browsers dispatch the event to listeners of this type instead of
+    /// `message`.
+    ///
+    pub fn event<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.event = Some(single_line(name.as_ref()));
+        self
+    }
+
+    ///
+    /// Sets event id. Browsers send the last received id in `Last-Event-ID` header on reconnect.
+    ///
+    pub fn id<S: AsRef<str>>(mut self, id: S) -> Self {
+        self.id = Some(single_line(id.as_ref()));
+        self
+    }
+
+    ///
+    /// Sets reconnection time used by the browser when the connection is lost.
+    ///
+    pub fn retry(mut self, duration: Duration) -> Self {
+        self.retry = Some(duration);
+        self
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        if let Some(event) = &self.event {
+            bytes.extend(format!("event: {}\n", event).as_bytes());
+        }
+
+        if let Some(id) = &self.id {
+            bytes.extend(format!("id: {}\n", id).as_bytes());
+        }
+
+        if let Some(retry) = &self.retry {
+            bytes.extend(format!("retry: {}\n", retry.as_millis()).as_bytes());
+        }
+
+        // Event stream lines end with CRLF, LF or a bare CR.
+        for line in self.data.replace("\r\n", "\n").split(['\r', '\n']) {
+            bytes.extend(format!("data: {}\n", line).as_bytes());
+        }
+
+        bytes.push(b'\n');
+        bytes
+    }
+}
+
+/// Line breaks in field values would end the field early.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], "")
+}
+
+///
+/// Builds `text/event-stream` response sending events received from the channel until all the
+/// senders are dropped. A heartbeat comment is sent when no event is received within the heartbeat
+/// interval, which keeps proxies from closing idle connections.
+///
+/// When the client disconnects, the receiver is dropped on the next write, so `send` fails in the
+/// background task and it can stop producing events. The connection is closed once the stream
+/// ends, so it is never reused for another request.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use racoon::core::request::Request;
+/// use racoon::core::response::Response;
+/// use racoon::core::response::sse::{SseEvent, SseResponse};
+///
+/// async fn clock(request: Request) -> Response {
+///     let (sender, receiver) = tokio::sync::mpsc::channel(16);
+///
+///     tokio::spawn(async move {
+///         for tick in 0.. {
+///             let event = SseEvent::new(tick.to_string()).event("tick");
+///             if sender.send(event).await.is_err() {
+///                 // Client disconnected
+///                 break;
+///             }
+///             tokio::time::sleep(Duration::from_secs(1)).await;
+///         }
+///     });
+///
+///     SseResponse::new()
+///         .heartbeat(Duration::from_secs(30))
+///         .channel(receiver)
+/// }
+/// ```
+///
+pub struct SseResponse {
+    heartbeat: Option<Duration>,
+}
+
+impl SseResponse {
+    pub fn new() -> Self {
+        Self {
+            heartbeat: Some(DEFAULT_HEARTBEAT_INTERVAL),
+        }
+    }
+
+    ///
+    /// Shorthand for `SseResponse::new().channel(receiver)`.
+    ///
+    pub fn from_channel(receiver: Receiver<SseEvent>) -> Box<StreamingResponse> {
+        Self::new().channel(receiver)
+    }
+
+    ///
+    /// Sets interval of `: keep-alive` comments. Default is 15 seconds.
+    ///
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat = Some(interval);
+        self
+    }
+
+    pub fn disable_heartbeat(mut self) -> Self {
+        self.heartbeat = None;
+        self
+    }
+
+    pub fn channel(self, mut receiver: Receiver<SseEvent>) -> Box<StreamingResponse> {
+        let heartbeat = self.heartbeat;
+
+        let mut response = StreamingResponse::ok()
+            .content_type("text/event-stream")
+            .keep_alive(false);
+        response.get_headers().set("Cache-Control", "no-cache");
+
+        response.body(move |mut writer| async move {
+            loop {
+                let event = match heartbeat {
+                    Some(interval) => {
+                        match tokio::time::timeout(interval, receiver.recv()).await {
+                            Ok(event) => event,
+                            Err(_) => {
+                                writer.write(": keep-alive\n\n").await?;
+                                continue;
+                            }
+                        }
+                    }
+                    None => receiver.recv().await,
+                };
+
+                match event {
+                    Some(event) => writer.write(event.to_bytes()).await?,
+                    // All the senders are dropped.
+                    None => break,
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Default for SseResponse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::Response;
+    use crate::core::server::tests::{serve_raw, serve_raw_chunked, test_state};
+
+    use super::{SseEvent, SseResponse};
+
+    async fn events(_: Request) -> Response {
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+
+        tokio::spawn(async move {
+            let _ = sender.send(SseEvent::new("hello").event("greeting")).await;
+            tokio::time::sleep(Duration::from_millis(120)).await;
+            let _ = sender.send(SseEvent::new("line1\nline2").id("2")).await;
+        });
+
+        SseResponse::new()
+            .heartbeat(Duration::from_millis(50))
+            .channel(receiver)
+    }
+
+    #[test]
+    fn test_sse_event() {
+        let event = SseEvent::new("a\r\nb").event("up\ndate");
+        assert_eq!(
+            b"event: update\ndata: a\ndata: b\n\n".to_vec(),
+            event.to_bytes()
+        );
+
+        let event = SseEvent::new("a\rb\n\nc\r");
+        assert_eq!(
+            b"data: a\ndata: b\ndata: \ndata: c\ndata: \n\n".to_vec(),
+            event.to_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sse_response() {
+        let view: View = |request| Box::pin(events(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let response = serve_raw(state, b"GET / HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.contains("Content-Type: text/event-stream\r\n"));
+        assert_eq!(true, response.contains("Cache-Control: no-cache\r\n"));
+        assert_eq!(true, response.contains("event: greeting\ndata: hello\n\n"));
+        assert_eq!(true, response.contains(": keep-alive\n\n"));
+        assert_eq!(true, response.contains("id: 2\ndata: line1\ndata: line2\n\n"));
+        assert_eq!(true, response.ends_with("0\r\n\r\n"));
+
+        // Requests pipelined after the event stream are not served.
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let request = b"GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        let response = serve_raw_chunked(state, request, 18).await;
+        assert_eq!(true, response.contains("Connection: close\r\n"));
+        assert_eq!(1, response.matches("HTTP/1.1 200 OK").count());
+    }
+}
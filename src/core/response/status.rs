@@ -2,6 +2,19 @@
 /// More information: <https://developer.mozilla.org/en-US/docs/Web/HTTP/Status>
 ///
 pub trait ResponseStatus: Sized {
+    ///
+    /// Creates response with any status code, including non-standard ones like `299` or vendor
+    /// codes. Status code must be a three digit number from 100 to 599, otherwise it panics. Line
+    /// breaks in the status text are removed when the response is sent.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::response::HttpResponse;
+    /// use racoon::core::response::status::ResponseStatus;
+    ///
+    /// let response = HttpResponse::with_status(299, "Custom Success").body("Done");
+    /// ```
+    ///
     fn with_status(status_code: u32, status_text: &str) -> Self;
 
     fn r#continue() -> Self {
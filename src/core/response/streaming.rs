@@ -0,0 +1,255 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::core::headers::{HeaderValue, Headers};
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::{AbstractResponse, HttpResponse};
+use crate::core::stream::Stream;
+
+///
+/// Function producing the response body by writing chunks to `BodyWriter`.
+///
+pub type StreamingBody =
+    Box<dyn FnOnce(BodyWriter) -> Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>> + Send>;
+
+///
+/// Writes response body chunks directly to the client stream. With `Transfer-Encoding: chunked`,
+/// each chunk is framed with its hex length.
+///
+pub struct BodyWriter {
+    stream: Arc<Stream>,
+    chunked: bool,
+}
+
+impl BodyWriter {
+    pub(crate) fn new(stream: Arc<Stream>, chunked: bool) -> Self {
+        Self { stream, chunked }
+    }
+
+    ///
+    /// Writes chunk to the client. Empty chunks are skipped because zero length chunk marks end of
+    /// the chunked body.
+    ///
+    pub async fn write<B: AsRef<[u8]>>(&mut self, chunk: B) -> std::io::Result<()> {
+        let chunk = chunk.as_ref();
+        if chunk.is_empty() {
+            return Ok(());
+        }
+
+        if !self.chunked {
+            return self.stream.write_chunk(chunk).await;
+        }
+
+        let mut framed = Vec::with_capacity(chunk.len() + 12);
+        framed.extend(format!("{:X}\r\n", chunk.len()).as_bytes());
+        framed.extend(chunk);
+        framed.extend(b"\r\n");
+        self.stream.write_chunk(&framed).await
+    }
+}
+
+///
+/// Response whose body is written in chunks while it is produced instead of being buffered in
+/// memory, for example large downloads or event streams. Body is sent with
+/// `Transfer-Encoding: chunked` framing. HTTP/1.0 clients receive raw body and the connection is
+/// closed after it.
+///
+/// # Examples
+/// ```
+/// use racoon::core::request::Request;
+/// use racoon::core::response::Response;
+/// use racoon::core::response::status::ResponseStatus;
+/// use racoon::core::response::streaming::StreamingResponse;
+///
+/// async fn export(request: Request) -> Response {
+///     StreamingResponse::ok()
+///         .content_type("text/csv")
+///         .body(|mut writer| async move {
+///             writer.write("id,name\n").await?;
+///
+///             for id in 0..100_000 {
+///                 writer.write(format!("{},user{}\n", id, id)).await?;
+///             }
+///             Ok(())
+///         })
+/// }
+/// ```
+///
+pub struct StreamingResponse {
+    http_response: HttpResponse,
+    body: Option<StreamingBody>,
+}
+
+impl StreamingResponse {
+    pub fn content_type(mut self, value: &str) -> Self {
+        self.http_response = self.http_response.content_type(value);
+        self
+    }
+
+    ///
+    /// Sends body with `Content-Length` header instead of chunked transfer coding when the body
+    /// size is known in advance. The body must write exactly `length` bytes.
+    ///
+    pub fn content_length(mut self, length: u64) -> Self {
+        let headers = self.http_response.get_headers();
+        headers.retain(|name, _| !name.eq_ignore_ascii_case("Transfer-Encoding"));
+        headers.set("Content-Length", length.to_string());
+        self
+    }
+
+    ///
+    /// Passing false closes the connection after the body is written, for example for long-polling
+    /// responses. Finite bodies framed with chunked transfer coding or `Content-Length` can be
+    /// followed by another request on the same connection.
+    ///
+    pub fn keep_alive(mut self, is_alive: bool) -> Self {
+        self.http_response = self.http_response.keep_alive(is_alive);
+        self
+    }
+
+    pub fn body<F, Fut>(mut self, body: F) -> Box<Self>
+    where
+        F: FnOnce(BodyWriter) -> Fut + Send + 'static,
+        Fut: Future<Output = std::io::Result<()>> + Send + 'static,
+    {
+        self.body = Some(Box::new(|writer| Box::pin(body(writer))));
+        Box::new(self)
+    }
+}
+
+impl AbstractResponse for StreamingResponse {
+    fn status(&self) -> (u32, String) {
+        self.http_response.status()
+    }
+
+    fn serve_default(&mut self) -> bool {
+        self.http_response.serve_default()
+    }
+
+    fn get_headers(&mut self) -> &mut Headers {
+        self.http_response.get_headers()
+    }
+
+    fn get_body(&mut self) -> &mut Vec<u8> {
+        self.http_response.get_body()
+    }
+
+    fn should_close(&mut self) -> bool {
+        self.http_response.should_close()
+    }
+
+    fn was_custom_served(&self) -> bool {
+        true
+    }
+
+    fn take_streaming_body(&mut self) -> Option<StreamingBody> {
+        self.body.take()
+    }
+
+    fn set_status(&mut self, status_code: u32, status_text: &str) {
+        self.http_response.set_status(status_code, status_text);
+    }
+}
+
+impl ResponseStatus for StreamingResponse {
+    fn with_status(status_code: u32, status_text: &str) -> Self {
+        let mut http_response = HttpResponse::with_status(status_code, status_text);
+        http_response
+            .get_headers()
+            .set("Transfer-Encoding", "chunked");
+
+        Self {
+            http_response,
+            body: None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::Response;
+    use crate::core::server::tests::{serve_raw, serve_raw_chunked, test_state};
+
+    use super::StreamingResponse;
+
+    async fn numbers(_: Request) -> Response {
+        StreamingResponse::ok().body(|mut writer| async move {
+            writer.write("hello").await?;
+            writer.write("").await?;
+            writer.write(" world, this is streamed").await?;
+            Ok(())
+        })
+    }
+
+    async fn closing(_: Request) -> Response {
+        StreamingResponse::ok()
+            .keep_alive(false)
+            .body(|mut writer| async move { writer.write("bye").await })
+    }
+
+    async fn failing(_: Request) -> Response {
+        StreamingResponse::ok().body(|mut writer| async move {
+            writer.write("partial").await?;
+            Err(std::io::Error::other("Failed to produce body."))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_streaming_response_keep_alive() {
+        let view: View = |request| Box::pin(numbers(request));
+        let closing_view: View = |request| Box::pin(closing(request));
+        let failing_view: View = |request| Box::pin(failing(request));
+        let paths = || {
+            vec![
+                Path::new("/", view),
+                Path::new("/closing", closing_view),
+                Path::new("/failing", failing_view),
+            ]
+        };
+
+        let state = test_state(paths()).await;
+        let request = b"GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        let response = serve_raw_chunked(state, request, 18).await;
+        assert_eq!(2, response.matches("Connection: keep-alive\r\n").count());
+
+        let state = test_state(paths()).await;
+        let request = b"GET /closing HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        let response = serve_raw_chunked(state, request, 25).await;
+        assert_eq!(true, response.contains("Connection: close\r\n"));
+        assert_eq!(1, response.matches("HTTP/1.1 200 OK").count());
+        assert_eq!(true, response.ends_with("3\r\nbye\r\n0\r\n\r\n"));
+
+        // Incomplete body closes the connection without the last chunk.
+        let state = test_state(paths()).await;
+        let request = b"GET /failing HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        let response = serve_raw_chunked(state, request, 25).await;
+        assert_eq!(1, response.matches("HTTP/1.1 200 OK").count());
+        assert_eq!(true, response.ends_with("7\r\npartial\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_response() {
+        let view: View = |request| Box::pin(numbers(request));
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let response = serve_raw(state, b"GET / HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.contains("Transfer-Encoding: chunked\r\n"));
+        assert_eq!(false, response.contains("Content-Length"));
+        assert_eq!(
+            true,
+            response.ends_with("\r\n\r\n5\r\nhello\r\n18\r\n world, this is streamed\r\n0\r\n\r\n")
+        );
+
+        let state = test_state(vec![Path::new("/", view)]).await;
+        let response = serve_raw(state, b"GET / HTTP/1.0\r\n\r\n").await;
+        assert_eq!(false, response.contains("Transfer-Encoding"));
+        assert_eq!(
+            true,
+            response.ends_with("\r\n\r\nhello world, this is streamed")
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::headers::HeaderValue;
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::{AbstractResponse, HttpResponse, Response};
+
+///
+/// Runtime toggle which makes the server respond with `503 Service Unavailable` to all the
+/// requests except the allowed paths, for example health checks.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use racoon::core::server::Server;
+///
+/// let mut server = Server::bind("127.0.0.1:8080");
+/// server
+///     .maintenance_allowed_path("/health")
+///     .maintenance_retry_after(Duration::from_secs(300));
+///
+/// let maintenance = server.maintenance();
+///
+/// // Later, for example from an admin endpoint or signal handler.
+/// maintenance.enable();
+/// assert_eq!(true, maintenance.is_enabled());
+/// maintenance.disable();
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct MaintenanceMode {
+    enabled: Arc<AtomicBool>,
+    retry_after: Duration,
+    allowed_paths: Vec<String>,
+}
+
+impl Default for MaintenanceMode {
+    fn default() -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(false)),
+            retry_after: Duration::from_secs(60),
+            allowed_paths: vec![],
+        }
+    }
+}
+
+impl MaintenanceMode {
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_retry_after(&mut self, retry_after: Duration) {
+        self.retry_after = retry_after;
+    }
+
+    pub(crate) fn allow_path<S: AsRef<str>>(&mut self, path: S) {
+        self.allowed_paths.push(path.as_ref().to_string());
+    }
+
+    ///
+    /// Returns maintenance response if maintenance mode is enabled and the path is not allowed.
+    ///
+    pub(crate) fn response_for(&self, path: &str) -> Option<Response> {
+        if !self.is_enabled() || self.allowed_paths.iter().any(|allowed| allowed == path) {
+            return None;
+        }
+
+        let mut response = HttpResponse::service_unavailable();
+        response
+            .get_headers()
+            .set("Retry-After", self.retry_after.as_secs().to_string());
+        Some(response.body("Service Unavailable"))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use crate::core::headers::HeaderValue;
+
+    use super::MaintenanceMode;
+
+    #[test]
+    fn test_maintenance_response() {
+        let mut maintenance = MaintenanceMode::default();
+        maintenance.allow_path("/health");
+        maintenance.set_retry_after(Duration::from_secs(120));
+        assert_eq!(true, maintenance.response_for("/").is_none());
+
+        // Handles share the same toggle.
+        maintenance.clone().enable();
+
+        let mut response = maintenance.response_for("/").unwrap();
+        assert_eq!(503, response.status().0);
+        assert_eq!(
+            Some("120".to_string()),
+            response.get_headers().value("Retry-After")
+        );
+        assert_eq!(true, maintenance.response_for("/health").is_none());
+
+        maintenance.disable();
+        assert_eq!(true, maintenance.response_for("/").is_none());
+    }
+}
@@ -1,13 +1,18 @@
+use std::collections::{HashMap, VecDeque};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "redis")]
+use redis::AsyncCommands;
 use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::ConnectOptions;
 use sqlx::Executor;
 use sqlx::Pool;
 use sqlx::Sqlite;
+use tokio::sync::OnceCell;
 
 use crate::core::session::AbstractSessionManager;
 use crate::core::session::SessionResult;
@@ -21,49 +26,101 @@ use crate::racoon_error;
 /// # Examples
 ///
 /// ```
-/// use std::env;
-///
 /// use racoon::core::session::managers::FileSessionManager;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///   // Optional
-///   env::set_var("SESSION_FILE_PATH", "../mydb/session");
-///   let session_manager = FileSessionManager::new().await;
+///   let session_manager = FileSessionManager::with_path("../mydb/session").await;
 /// }
 /// ```
 ///
-/// The file path can be specified by specifying `SESSION_FILE_PATH` in environment variable.
+/// Without explicit path, the file path is read from `SESSION_FILE_PATH` environment variable.
 ///
 pub struct FileSessionManager {
-    db_connection: Arc<Option<Pool<Sqlite>>>,
+    db_connection: Arc<OnceCell<Pool<Sqlite>>>,
+    db_path: PathBuf,
+    ttl: Duration,
+    /// Time when expired sessions were last removed.
+    last_purge: Arc<StdMutex<Instant>>,
 }
 
 impl FileSessionManager {
     ///
-    /// Creates new instance of FileSessonManager.
+    /// Creates new instance of FileSessonManager storing sessions in the file returned from
+    /// `get_db_path`. Sessions expire after 7 days of the last update, same as the `sessionid`
+    /// cookie.
     ///
     pub async fn new() -> std::io::Result<Self> {
+        Self::with_ttl(DEFAULT_SESSION_TTL).await
+    }
+
+    ///
+    /// Creates new instance of FileSessionManager storing sessions in the given Sqlite database
+    /// file. The file and its parent directories are created when the session is first used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use racoon::core::session::managers::FileSessionManager;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let session_manager = FileSessionManager::with_path("/var/lib/myapp/session")
+    ///       .await
+    ///       .unwrap()
+    ///       .ttl(Duration::from_secs(3600));
+    /// }
+    /// ```
+    ///
+    pub async fn with_path<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
         let instance = Self {
-            db_connection: Arc::new(None),
+            db_connection: Arc::new(OnceCell::new()),
+            db_path: path.as_ref().to_path_buf(),
+            ttl: DEFAULT_SESSION_TTL,
+            last_purge: Arc::new(StdMutex::new(Instant::now())),
         };
         Ok(instance)
     }
 
     ///
-    /// Returns stored session file path.
+    /// Creates new instance of FileSessionManager with sessions expiring after the given duration
+    /// since the last update. Expired sessions are not returned and are removed periodically from
+    /// the database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use racoon::core::session::managers::FileSessionManager;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let session_manager = FileSessionManager::with_ttl(Duration::from_secs(3600)).await;
+    /// }
+    /// ```
+    ///
+    pub async fn with_ttl(ttl: Duration) -> std::io::Result<Self> {
+        Ok(Self::with_path(Self::get_db_path()).await?.ttl(ttl))
+    }
+
+    ///
+    /// Sessions expire after the given duration since the last update.
+    ///
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    ///
+    /// Returns default session file path used by `new` and `with_ttl`.
     ///
     /// If environment variable `SESSION_FILE_PATH` is specified, it will return the specified path
     /// else default relative file path `.cache/session`.
     ///
     pub fn get_db_path() -> String {
-        let is_test = env::var("TEST_SESSION").unwrap_or("false".to_string());
-        if is_test.to_lowercase() == "true" {
-            // Returns Sqlite path for testing
-            racoon_debug!("Using test session database.");
-            return ".cache/test_session".to_string();
-        }
-
         env::var("SESSION_FILE_PATH").unwrap_or(".cache/session".to_string())
     }
 
@@ -72,14 +129,18 @@ impl FileSessionManager {
     /// new Sqlite database, creates table and returns the new initialized connection pool.
     ///
     async fn lazy_connection_pool(
-        mut db_connection: Arc<Option<Pool<Sqlite>>>,
+        db_connection: Arc<OnceCell<Pool<Sqlite>>>,
+        db_path: &Path,
+        ttl: Duration,
     ) -> std::io::Result<Pool<Sqlite>> {
-        if let Some(db_pool) = db_connection.as_ref() {
-            return Ok(db_pool.clone());
-        }
+        let db_pool = db_connection
+            .get_or_try_init(|| Self::connect(db_path, ttl))
+            .await?;
+        Ok(db_pool.clone())
+    }
 
-        let db_path = PathBuf::from(FileSessionManager::get_db_path());
-        let db_exists;
+    async fn connect(db_path: &Path, ttl: Duration) -> std::io::Result<Pool<Sqlite>> {
+        let db_path = db_path.to_path_buf();
 
         if !db_path.exists() {
             racoon_debug!("Session database does not exist. Creating new one.");
@@ -88,11 +149,8 @@ impl FileSessionManager {
             let mut db_dir = db_path.clone();
             db_dir.pop();
 
-            db_exists = false;
             std::fs::create_dir_all(db_dir)?;
             std::fs::File::create_new(&db_path)?;
-        } else {
-            db_exists = true;
         }
 
         // Disables sqlx logging
@@ -107,48 +165,128 @@ impl FileSessionManager {
                 }
             };
 
-        match sqlx::SqlitePool::connect_with(connect_options).await {
-            Ok(pool) => {
-                if !db_exists {
-                    const CREATE_SESSION_TABLE_QUERY: &str = r#"
-                        CREATE TABLE session(
-                            id BIGINT AUTO_INCREMENT PRIMARY KEY, 
-                            session_id VARCHAR(1025) NOT NULL,
-                            key TEXT NOT NULL UNIQUE,
-                            value TEXT NOT NULL
-                        )
-                    "#;
-
-                    match pool.execute(CREATE_SESSION_TABLE_QUERY).await {
-                        Ok(_) => {
-                            racoon_debug!("Created session table.");
-                        }
-                        Err(error) => {
-                            return Err(std::io::Error::other(format!(
-                                "Failed to create session table. Error: {}",
-                                error
-                            )));
-                        }
-                    };
-                }
-                db_connection = Arc::from(Some(pool.clone()));
-
-                if let Some(db_connection) = db_connection.as_ref() {
-                    return Ok(db_connection.clone());
-                }
-
-                return Err(std::io::Error::other("Error reading connection pool."));
-            }
+        let pool = match sqlx::SqlitePool::connect_with(connect_options).await {
+            Ok(pool) => pool,
             Err(error) => {
                 return Err(std::io::Error::other(format!(
                     "Failed to connect sqlite db for managing session. Error: {:?}",
                     error
                 )));
             }
+        };
+
+        if let Err(error) = Self::create_session_table(&pool, ttl).await {
+            return Err(std::io::Error::other(format!(
+                "Failed to create session table. Error: {}",
+                error
+            )));
+        }
+
+        Ok(pool)
+    }
+
+    ///
+    /// Creates session table if it does not exist. Tables created by older versions without
+    /// expiry time are migrated and their sessions expire after the ttl.
+    ///
+    async fn create_session_table(pool: &Pool<Sqlite>, ttl: Duration) -> sqlx::Result<()> {
+        const CREATE_SESSION_TABLE_QUERY: &str = r#"
+            CREATE TABLE IF NOT EXISTS session(
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id VARCHAR(1025) NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                expires_at BIGINT NOT NULL,
+                UNIQUE(session_id, key)
+            )
+        "#;
+
+        let columns: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info('session')")
+            .fetch_all(pool)
+            .await?;
+
+        let is_legacy_table = !columns.is_empty()
+            && !columns.iter().any(|(column,)| column == "expires_at");
+
+        if !is_legacy_table {
+            pool.execute(CREATE_SESSION_TABLE_QUERY).await?;
+            racoon_debug!("Created session table.");
+        } else {
+            racoon_debug!("Migrating session table to store expiry time.");
+            let mut transaction = pool.begin().await?;
+            transaction
+                .execute("ALTER TABLE session RENAME TO session_legacy")
+                .await?;
+            transaction.execute(CREATE_SESSION_TABLE_QUERY).await?;
+
+            const COPY_QUERY: &str = r#"
+                INSERT OR REPLACE INTO session(session_id, key, value, expires_at)
+                SELECT session_id, key, value, $1 FROM session_legacy
+            "#;
+            sqlx::query(COPY_QUERY)
+                .bind(expires_at(ttl))
+                .execute(&mut *transaction)
+                .await?;
+
+            transaction.execute("DROP TABLE session_legacy").await?;
+            transaction.commit().await?;
+        }
+
+        pool.execute("CREATE INDEX IF NOT EXISTS session_expires_at ON session(expires_at)")
+            .await?;
+        Ok(())
+    }
+
+    ///
+    /// Removes expired sessions if they were not removed within the purge interval.
+    ///
+    async fn purge_expired(db_pool: &Pool<Sqlite>, last_purge: Arc<StdMutex<Instant>>) {
+        {
+            let mut last_purge = last_purge.lock().unwrap();
+            if last_purge.elapsed() < PURGE_INTERVAL {
+                return;
+            }
+            *last_purge = Instant::now();
+        }
+
+        const PURGE_QUERY: &str = r#"
+            DELETE FROM session WHERE expires_at <= $1
+        "#;
+
+        let result = sqlx::query(PURGE_QUERY)
+            .bind(unix_time(SystemTime::now()))
+            .execute(db_pool)
+            .await;
+
+        match result {
+            Ok(result) => {
+                racoon_debug!("Removed {} expired session values.", result.rows_affected());
+            }
+            Err(error) => {
+                racoon_error!("Failed to remove expired sessions. Error: {}", error);
+            }
         }
     }
 }
 
+/// Sessions expire after 7 days by default, same as the `sessionid` cookie.
+const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(7 * 86400);
+
+/// Minimum interval between removing expired sessions from the database.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+fn unix_time(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(_) => 0,
+    }
+}
+
+/// Returns expiry time as unix timestamp for session updated now.
+fn expires_at(ttl: Duration) -> i64 {
+    unix_time(SystemTime::now() + ttl)
+}
+
 impl AbstractSessionManager for FileSessionManager {
     fn set(
         &self,
@@ -157,12 +295,15 @@ impl AbstractSessionManager for FileSessionManager {
         value: &str,
     ) -> SessionResult<std::io::Result<()>> {
         let db_connection = self.db_connection.clone();
+        let db_path = self.db_path.clone();
+        let ttl = self.ttl;
+        let last_purge = self.last_purge.clone();
         let session_id = session_id.to_owned();
         let key = name.to_string();
         let value = value.to_string();
 
         Box::new(Box::pin(async move {
-            let db_pool = match Self::lazy_connection_pool(db_connection.clone()).await {
+            let db_pool = match Self::lazy_connection_pool(db_connection, &db_path, ttl).await {
                 Ok(pool) => pool,
                 Err(error) => {
                     return Err(error);
@@ -170,22 +311,39 @@ impl AbstractSessionManager for FileSessionManager {
             };
 
             const UPSERT_QUERY: &str = r#"
-                INSERT INTO session(session_id, key, value) 
-                VALUES ($1, $2, $3)
-                ON CONFLICT(key) DO UPDATE 
+                INSERT INTO session(session_id, key, value, expires_at) 
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT(session_id, key) DO UPDATE 
                 SET 
-                    session_id=excluded.session_id, 
-                    key=excluded.key,
-                    value=excluded.value
+                    value=excluded.value,
+                    expires_at=excluded.expires_at
+            "#;
+
+            // Other values of the session are refreshed too, so the session expires as a whole.
+            const REFRESH_QUERY: &str = r#"
+                UPDATE session SET expires_at=$1 WHERE session_id=$2
             "#;
 
+            let expires_at = expires_at(ttl);
             let result = sqlx::query(UPSERT_QUERY)
-                .bind(session_id)
+                .bind(&session_id)
                 .bind(key)
                 .bind(value)
+                .bind(expires_at)
                 .execute(&db_pool)
                 .await;
 
+            let result = match result {
+                Ok(_) => {
+                    sqlx::query(REFRESH_QUERY)
+                        .bind(expires_at)
+                        .bind(&session_id)
+                        .execute(&db_pool)
+                        .await
+                }
+                Err(error) => Err(error),
+            };
+
             match result {
                 Ok(_) => {}
                 Err(error) => {
@@ -196,17 +354,20 @@ impl AbstractSessionManager for FileSessionManager {
                 }
             };
 
+            Self::purge_expired(&db_pool, last_purge).await;
             Ok(())
         }))
     }
 
     fn get(&self, session_id: &String, name: &str) -> SessionResult<Option<String>> {
         let db_connection = self.db_connection.clone();
+        let db_path = self.db_path.clone();
+        let ttl = self.ttl;
         let session_id = session_id.to_owned();
         let key = name.to_string();
 
         Box::new(Box::pin(async move {
-            let db_pool = match Self::lazy_connection_pool(db_connection.clone()).await {
+            let db_pool = match Self::lazy_connection_pool(db_connection, &db_path, ttl).await {
                 Ok(pool) => pool,
                 Err(error) => {
                     racoon_error!(
@@ -220,13 +381,14 @@ impl AbstractSessionManager for FileSessionManager {
             const FETCH_QUERY: &str = r#"
                 SELECT value FROM session 
                 WHERE 
-                    session_id=$1 AND key=$2 
+                    session_id=$1 AND key=$2 AND expires_at > $3
                 LIMIT 1
             "#;
 
             let result: Result<(String,), sqlx::Error> = sqlx::query_as(FETCH_QUERY)
                 .bind(session_id)
                 .bind(key)
+                .bind(unix_time(SystemTime::now()))
                 .fetch_one(&db_pool)
                 .await;
 
@@ -240,13 +402,143 @@ impl AbstractSessionManager for FileSessionManager {
         }))
     }
 
+    fn set_many(
+        &self,
+        session_id: &String,
+        values: &[(&str, &str)],
+    ) -> SessionResult<std::io::Result<()>> {
+        let db_connection = self.db_connection.clone();
+        let db_path = self.db_path.clone();
+        let ttl = self.ttl;
+        let last_purge = self.last_purge.clone();
+        let session_id = session_id.to_owned();
+        let values: Vec<(String, String)> = values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Box::new(Box::pin(async move {
+            let db_pool = Self::lazy_connection_pool(db_connection, &db_path, ttl).await?;
+
+            const UPSERT_QUERY: &str = r#"
+                INSERT INTO session(session_id, key, value, expires_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT(session_id, key) DO UPDATE
+                SET
+                    value=excluded.value,
+                    expires_at=excluded.expires_at
+            "#;
+
+            const REFRESH_QUERY: &str = r#"
+                UPDATE session SET expires_at=$1 WHERE session_id=$2
+            "#;
+
+            // All the values are written in single transaction.
+            let expires_at = expires_at(ttl);
+            let result: sqlx::Result<()> = async {
+                let mut transaction = db_pool.begin().await?;
+                for (key, value) in values {
+                    sqlx::query(UPSERT_QUERY)
+                        .bind(&session_id)
+                        .bind(key)
+                        .bind(value)
+                        .bind(expires_at)
+                        .execute(&mut *transaction)
+                        .await?;
+                }
+
+                sqlx::query(REFRESH_QUERY)
+                    .bind(expires_at)
+                    .bind(&session_id)
+                    .execute(&mut *transaction)
+                    .await?;
+                transaction.commit().await
+            }
+            .await;
+
+            if let Err(error) = result {
+                return Err(std::io::Error::other(format!(
+                    "Failed to set session values. Error: {}",
+                    error
+                )));
+            }
+
+            Self::purge_expired(&db_pool, last_purge).await;
+            Ok(())
+        }))
+    }
+
+    fn get_many(
+        &self,
+        session_id: &String,
+        names: &[&str],
+    ) -> SessionResult<HashMap<String, String>> {
+        let db_connection = self.db_connection.clone();
+        let db_path = self.db_path.clone();
+        let ttl = self.ttl;
+        let session_id = session_id.to_owned();
+        let keys: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+
+        Box::new(Box::pin(async move {
+            if keys.is_empty() {
+                return HashMap::new();
+            }
+
+            let db_pool = match Self::lazy_connection_pool(db_connection, &db_path, ttl).await {
+                Ok(pool) => pool,
+                Err(error) => {
+                    racoon_error!(
+                        "Failed to create session database connection pool. Error: {}",
+                        error
+                    );
+                    return HashMap::new();
+                }
+            };
+
+            // Placeholders for the keys start after session id and current time.
+            let placeholders: Vec<String> = (0..keys.len())
+                .map(|index| format!("${}", index + 3))
+                .collect();
+
+            let fetch_query = format!(
+                r#"
+                SELECT key, value FROM session
+                WHERE
+                    session_id=$1 AND expires_at > $2 AND key IN ({})
+                "#,
+                placeholders.join(", ")
+            );
+
+            let mut query = sqlx::query_as(&fetch_query)
+                .bind(session_id)
+                .bind(unix_time(SystemTime::now()));
+
+            for key in keys {
+                query = query.bind(key);
+            }
+
+            let result: Result<Vec<(String, String)>, sqlx::Error> =
+                query.fetch_all(&db_pool).await;
+
+            match result {
+                Ok(rows) => rows.into_iter().collect(),
+                Err(error) => {
+                    racoon_debug!("Failed to fetch session values. Error: {}", error);
+                    HashMap::new()
+                }
+            }
+        }))
+    }
+
     fn remove(&self, session_id: &String, name: &str) -> SessionResult<std::io::Result<()>> {
         let db_connection = self.db_connection.clone();
+        let db_path = self.db_path.clone();
+        let ttl = self.ttl;
         let session_id = session_id.to_owned();
         let key = name.to_string();
 
         Box::new(Box::pin(async move {
-            let db_pool = match Self::lazy_connection_pool(db_connection.clone()).await {
+            let db_pool = match Self::lazy_connection_pool(db_connection, &db_path, ttl).await {
                 Ok(pool) => pool,
                 Err(error) => {
                     return Err(std::io::Error::other(format!(
@@ -278,10 +570,12 @@ impl AbstractSessionManager for FileSessionManager {
 
     fn destroy(&self, session_id: &String) -> SessionResult<std::io::Result<()>> {
         let db_connection = self.db_connection.clone();
+        let db_path = self.db_path.clone();
+        let ttl = self.ttl;
         let session_id = session_id.to_owned();
 
         Box::new(Box::pin(async move {
-            let db_pool = match Self::lazy_connection_pool(db_connection.clone()).await {
+            let db_pool = match Self::lazy_connection_pool(db_connection, &db_path, ttl).await {
                 Ok(pool) => pool,
                 Err(error) => {
                     return Err(std::io::Error::other(format!(
@@ -309,24 +603,410 @@ impl AbstractSessionManager for FileSessionManager {
             };
         }))
     }
+
+    fn rename(
+        &self,
+        session_id: &String,
+        new_session_id: &String,
+    ) -> SessionResult<std::io::Result<()>> {
+        let db_connection = self.db_connection.clone();
+        let db_path = self.db_path.clone();
+        let ttl = self.ttl;
+        let session_id = session_id.to_owned();
+        let new_session_id = new_session_id.to_owned();
+
+        Box::new(Box::pin(async move {
+            let db_pool = match Self::lazy_connection_pool(db_connection, &db_path, ttl).await {
+                Ok(pool) => pool,
+                Err(error) => {
+                    return Err(std::io::Error::other(format!(
+                        "Failed to create session database connection pool. Error: {}",
+                        error
+                    )));
+                }
+            };
+
+            const RENAME_QUERY: &str = r#"
+                UPDATE session SET session_id=$1 WHERE session_id=$2
+            "#;
+
+            let result = sqlx::query(RENAME_QUERY)
+                .bind(new_session_id)
+                .bind(session_id)
+                .execute(&db_pool)
+                .await;
+
+            return match result {
+                Ok(_) => Ok(()),
+                Err(error) => Err(std::io::Error::other(format!(
+                    "Failed to rename session. Error: {}",
+                    error
+                ))),
+            };
+        }))
+    }
+}
+
+#[derive(Default)]
+struct MemorySessions {
+    values: HashMap<String, HashMap<String, String>>,
+    /// Session ids in the order they were created, oldest first.
+    order: VecDeque<String>,
+}
+
+impl MemorySessions {
+    fn remove_session(&mut self, session_id: &String) -> Option<HashMap<String, String>> {
+        self.order.retain(|id| id != session_id);
+        self.values.remove(session_id)
+    }
+}
+
+///
+/// Session manager storing sessions in memory. Sessions are lost when the server restarts, so it
+/// is suitable for tests and short-lived deployments.
+///
+/// # Examples
+///
+/// ```
+/// use racoon::core::server::Server;
+/// use racoon::core::session::managers::MemorySessionManager;
+///
+/// let mut server = Server::bind("127.0.0.1:8080");
+/// // Oldest session is removed when more than 10000 sessions are stored.
+/// server.set_session_manager(MemorySessionManager::new().max_sessions(10000));
+/// ```
+///
+#[derive(Clone, Default)]
+pub struct MemorySessionManager {
+    sessions: Arc<StdMutex<MemorySessions>>,
+    max_sessions: Option<usize>,
+}
+
+impl MemorySessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Maximum number of sessions stored. When exceeded, the oldest session is removed. Not
+    /// limited by default.
+    ///
+    pub fn max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = Some(max_sessions);
+        self
+    }
+
+    ///
+    /// Returns number of stored sessions.
+    ///
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl AbstractSessionManager for MemorySessionManager {
+    fn set(
+        &self,
+        session_id: &String,
+        name: &str,
+        value: &str,
+    ) -> SessionResult<std::io::Result<()>> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if !sessions.values.contains_key(session_id) {
+            sessions.order.push_back(session_id.to_owned());
+        }
+
+        sessions
+            .values
+            .entry(session_id.to_owned())
+            .or_default()
+            .insert(name.to_string(), value.to_string());
+
+        if let Some(max_sessions) = self.max_sessions {
+            while sessions.values.len() > max_sessions {
+                let oldest = match sessions.order.pop_front() {
+                    Some(session_id) => session_id,
+                    None => break,
+                };
+
+                racoon_debug!("Maximum sessions exceeded. Removing oldest session.");
+                sessions.values.remove(&oldest);
+            }
+        }
+
+        Box::new(Box::pin(async move { Ok(()) }))
+    }
+
+    fn get(&self, session_id: &String, name: &str) -> SessionResult<Option<String>> {
+        let sessions = self.sessions.lock().unwrap();
+        let value = sessions
+            .values
+            .get(session_id)
+            .and_then(|values| values.get(name))
+            .cloned();
+
+        Box::new(Box::pin(async move { value }))
+    }
+
+    fn remove(&self, session_id: &String, name: &str) -> SessionResult<std::io::Result<()>> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if let Some(values) = sessions.values.get_mut(session_id) {
+            values.remove(name);
+
+            if values.is_empty() {
+                sessions.remove_session(session_id);
+            }
+        }
+
+        Box::new(Box::pin(async move { Ok(()) }))
+    }
+
+    fn destroy(&self, session_id: &String) -> SessionResult<std::io::Result<()>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.remove_session(session_id);
+
+        Box::new(Box::pin(async move { Ok(()) }))
+    }
+
+    fn rename(
+        &self,
+        session_id: &String,
+        new_session_id: &String,
+    ) -> SessionResult<std::io::Result<()>> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if let Some(values) = sessions.remove_session(session_id) {
+            sessions.remove_session(new_session_id);
+            sessions.order.push_back(new_session_id.to_owned());
+            sessions.values.insert(new_session_id.to_owned(), values);
+        }
+
+        Box::new(Box::pin(async move { Ok(()) }))
+    }
+}
+
+///
+/// Session manager storing sessions in Redis, so sessions are shared by multiple server instances
+/// behind a load balancer. Each session is stored as a hash under `session:<session_id>` key and
+/// expires after the ttl since the last update. Requires `redis` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use racoon::core::server::Server;
+/// use racoon::core::session::managers::RedisSessionManager;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let session_manager = RedisSessionManager::new("redis://127.0.0.1:6379/0")
+///       .await
+///       .expect("Failed to connect Redis.");
+///
+///   let mut server = Server::bind("127.0.0.1:8080");
+///   server.set_session_manager(session_manager);
+/// }
+/// ```
+///
+#[cfg(feature = "redis")]
+#[derive(Clone)]
+pub struct RedisSessionManager {
+    connection: redis::aio::MultiplexedConnection,
+    ttl: Duration,
+}
+
+#[cfg(feature = "redis")]
+impl RedisSessionManager {
+    ///
+    /// Connects to Redis with connection string such as `redis://:password@127.0.0.1:6379/0`.
+    /// Sessions expire after 7 days of the last update, same as the `sessionid` cookie.
+    ///
+    pub async fn new<S: AsRef<str>>(url: S) -> std::io::Result<Self> {
+        Self::with_ttl(url, DEFAULT_SESSION_TTL).await
+    }
+
+    ///
+    /// Connects to Redis with sessions expiring after the given duration since the last update.
+    ///
+    pub async fn with_ttl<S: AsRef<str>>(url: S, ttl: Duration) -> std::io::Result<Self> {
+        let client = match redis::Client::open(url.as_ref()) {
+            Ok(client) => client,
+            Err(error) => {
+                return Err(std::io::Error::other(format!(
+                    "Invalid Redis connection string for session manager. Error: {}",
+                    error
+                )));
+            }
+        };
+
+        let connection = match client.get_multiplexed_tokio_connection().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                return Err(std::io::Error::other(format!(
+                    "Failed to connect Redis for managing session. Error: {}",
+                    error
+                )));
+            }
+        };
+
+        Ok(Self { connection, ttl })
+    }
+
+    fn session_key(session_id: &str) -> String {
+        format!("session:{}", session_id)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl AbstractSessionManager for RedisSessionManager {
+    fn set(
+        &self,
+        session_id: &String,
+        name: &str,
+        value: &str,
+    ) -> SessionResult<std::io::Result<()>> {
+        let mut connection = self.connection.clone();
+        let session_key = Self::session_key(session_id);
+        let ttl = self.ttl.as_secs().max(1) as i64;
+        let key = name.to_string();
+        let value = value.to_string();
+
+        Box::new(Box::pin(async move {
+            // Whole session expires together, so the ttl is refreshed with every update.
+            let result: redis::RedisResult<()> = redis::pipe()
+                .atomic()
+                .hset(&session_key, key, value)
+                .ignore()
+                .expire(&session_key, ttl)
+                .ignore()
+                .query_async(&mut connection)
+                .await;
+
+            match result {
+                Ok(()) => Ok(()),
+                Err(error) => Err(std::io::Error::other(format!(
+                    "Failed to set session value. Error: {}",
+                    error
+                ))),
+            }
+        }))
+    }
+
+    fn get(&self, session_id: &String, name: &str) -> SessionResult<Option<String>> {
+        let mut connection = self.connection.clone();
+        let session_key = Self::session_key(session_id);
+        let key = name.to_string();
+
+        Box::new(Box::pin(async move {
+            let result: redis::RedisResult<Option<String>> =
+                connection.hget(session_key, key).await;
+
+            match result {
+                Ok(value) => value,
+                Err(error) => {
+                    racoon_debug!("Failed to fetch session value. Error: {}", error);
+                    None
+                }
+            }
+        }))
+    }
+
+    fn remove(&self, session_id: &String, name: &str) -> SessionResult<std::io::Result<()>> {
+        let mut connection = self.connection.clone();
+        let session_key = Self::session_key(session_id);
+        let key = name.to_string();
+
+        Box::new(Box::pin(async move {
+            let result: redis::RedisResult<()> = connection.hdel(session_key, key).await;
+
+            match result {
+                Ok(()) => Ok(()),
+                Err(error) => Err(std::io::Error::other(format!(
+                    "Failed to delete session values. Error: {}",
+                    error
+                ))),
+            }
+        }))
+    }
+
+    fn destroy(&self, session_id: &String) -> SessionResult<std::io::Result<()>> {
+        let mut connection = self.connection.clone();
+        let session_key = Self::session_key(session_id);
+
+        Box::new(Box::pin(async move {
+            let result: redis::RedisResult<()> = connection.del(session_key).await;
+
+            match result {
+                Ok(()) => Ok(()),
+                Err(error) => Err(std::io::Error::other(format!(
+                    "Failed to delete all session values. Error: {}",
+                    error
+                ))),
+            }
+        }))
+    }
+
+    fn rename(
+        &self,
+        session_id: &String,
+        new_session_id: &String,
+    ) -> SessionResult<std::io::Result<()>> {
+        let mut connection = self.connection.clone();
+        let session_key = Self::session_key(session_id);
+        let new_session_key = Self::session_key(new_session_id);
+        let ttl = self.ttl.as_secs().max(1) as i64;
+
+        Box::new(Box::pin(async move {
+            // RENAME fails for missing keys, while sessions without values have nothing to move.
+            let result: redis::RedisResult<bool> = connection.exists(&session_key).await;
+            let result = match result {
+                Ok(true) => {
+                    // Renamed session gets the full ttl like any other update.
+                    redis::pipe()
+                        .atomic()
+                        .rename(&session_key, &new_session_key)
+                        .ignore()
+                        .expire(&new_session_key, ttl)
+                        .ignore()
+                        .query_async(&mut connection)
+                        .await
+                }
+                Ok(false) => Ok(()),
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Ok(()) => Ok(()),
+                Err(error) => Err(std::io::Error::other(format!(
+                    "Failed to rename session. Error: {}",
+                    error
+                ))),
+            }
+        }))
+    }
 }
 
 #[cfg(test)]
 pub mod test {
-    use std::{env, path::PathBuf, str::FromStr};
+    use std::sync::{Arc, Mutex as StdMutex};
+    use std::time::{Duration, Instant};
+    use std::{path::PathBuf, str::FromStr};
 
     use uuid::Uuid;
 
     use crate::core::session::AbstractSessionManager;
 
-    use super::FileSessionManager;
+    use super::{FileSessionManager, MemorySessionManager};
 
     #[tokio::test]
     async fn test_file_session() {
-        // Specifies to use seperate testing database for session
-        env::set_var("TEST_SESSION", "true");
-        let db_path = FileSessionManager::get_db_path();
-        assert_eq!(db_path, ".cache/test_session");
+        // Uses seperate testing database for session
+        let db_path = ".cache/test_session";
 
         // Removes existing database file if any
         if PathBuf::from_str(&db_path).unwrap().exists() {
@@ -334,7 +1014,7 @@ pub mod test {
             assert_eq!(true, result.is_ok());
         }
 
-        let session_manager_result = FileSessionManager::new().await;
+        let session_manager_result = FileSessionManager::with_path(db_path).await;
         assert_eq!(true, session_manager_result.is_ok());
 
         let session_manager = session_manager_result.unwrap();
@@ -362,6 +1042,18 @@ pub mod test {
         let name = session_manager.get(&session_id, "name").await;
         assert_eq!(None, name);
 
+        // tests rename
+        let new_session_id = Uuid::new_v4().to_string();
+        let rename_result = session_manager.rename(&session_id, &new_session_id).await;
+        assert_eq!(true, rename_result.is_ok());
+
+        let location = session_manager.get(&session_id, "location").await;
+        assert_eq!(None, location);
+
+        let location = session_manager.get(&new_session_id, "location").await;
+        assert_eq!(Some("ktm".to_string()), location);
+        let session_id = new_session_id;
+
         // tests destory
         let destroy_result = session_manager.destroy(&session_id).await;
         assert_eq!(true, destroy_result.is_ok());
@@ -369,7 +1061,140 @@ pub mod test {
         let location = session_manager.get(&session_id, "location").await;
         assert_eq!(None, location);
 
+        // Same key in different sessions
+        let other_session_id = Uuid::new_v4().to_string();
+        let _ = session_manager.set(&session_id, "name", "John").await;
+        let _ = session_manager.set(&other_session_id, "name", "Jane").await;
+        let name = session_manager.get(&session_id, "name").await;
+        assert_eq!(Some("John".to_string()), name);
+        let _ = session_manager.destroy(&session_id).await;
+        let _ = session_manager.destroy(&other_session_id).await;
+
+        // tests batch set and get
+        let values = [("name", "John"), ("location", "ktm"), ("theme", "dark")];
+        let result = session_manager.set_many(&session_id, &values).await;
+        assert_eq!(true, result.is_ok());
+        let values = [("theme", "light")];
+        let _ = session_manager.set_many(&session_id, &values).await;
+
+        let values = session_manager
+            .get_many(&session_id, &["name", "theme", "unknown"])
+            .await;
+        assert_eq!(2, values.len());
+        assert_eq!(Some(&"John".to_string()), values.get("name"));
+        assert_eq!(Some(&"light".to_string()), values.get("theme"));
+        let values = session_manager.get_many(&session_id, &[]).await;
+        assert_eq!(true, values.is_empty());
+        let _ = session_manager.destroy(&session_id).await;
+
+        // tests expiry
+        let session_manager = FileSessionManager::with_path(db_path)
+            .await
+            .unwrap()
+            .ttl(Duration::from_secs(1));
+        let result = session_manager.set(&session_id, "name", "John").await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(
+            Some("John".to_string()),
+            session_manager.get(&session_id, "name").await
+        );
+
+        tokio::time::sleep(Duration::from_millis(2100)).await;
+        assert_eq!(None, session_manager.get(&session_id, "name").await);
+
+        // Expired rows are purged from the database
+        let db_pool = FileSessionManager::lazy_connection_pool(
+            session_manager.db_connection.clone(),
+            &session_manager.db_path,
+            session_manager.ttl,
+        )
+        .await
+        .unwrap();
+        let last_purge = Instant::now().checked_sub(Duration::from_secs(120)).unwrap();
+        FileSessionManager::purge_expired(&db_pool, Arc::new(StdMutex::new(last_purge))).await;
+
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM session")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(0, count);
+
         let delete_db_result = tokio::fs::remove_file(db_path).await;
         assert_eq!(true, delete_db_result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_memory_session() {
+        let session_manager = MemorySessionManager::new();
+        let session_id = Uuid::new_v4().to_string();
+
+        let result = session_manager.set(&session_id, "name", "John").await;
+        assert_eq!(true, result.is_ok());
+        let _ = session_manager.set(&session_id, "location", "ktm").await;
+        let _ = session_manager.set(&session_id, "name", "Jane").await;
+
+        let name = session_manager.get(&session_id, "name").await;
+        assert_eq!(Some("Jane".to_string()), name);
+        assert_eq!(None, session_manager.get(&session_id, "unknown").await);
+
+        let result = session_manager
+            .set_many(&session_id, &[("theme", "dark"), ("language", "en")])
+            .await;
+        assert_eq!(true, result.is_ok());
+        let values = session_manager
+            .get_many(&session_id, &["name", "theme", "unknown"])
+            .await;
+        assert_eq!(2, values.len());
+        assert_eq!(Some(&"Jane".to_string()), values.get("name"));
+        assert_eq!(Some(&"dark".to_string()), values.get("theme"));
+
+        let _ = session_manager.remove(&session_id, "name").await;
+        assert_eq!(None, session_manager.get(&session_id, "name").await);
+
+        let new_session_id = Uuid::new_v4().to_string();
+        let _ = session_manager.rename(&session_id, &new_session_id).await;
+        assert_eq!(None, session_manager.get(&session_id, "location").await);
+
+        let location = session_manager.get(&new_session_id, "location").await;
+        assert_eq!(Some("ktm".to_string()), location);
+
+        let destroy_result = session_manager.destroy(&new_session_id).await;
+        assert_eq!(true, destroy_result.is_ok());
+        assert_eq!(None, session_manager.get(&new_session_id, "location").await);
+        assert_eq!(true, session_manager.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_memory_session_max_sessions() {
+        let session_manager = MemorySessionManager::new().max_sessions(2);
+
+        for session_id in ["first", "second", "third"] {
+            let _ = session_manager.set(&session_id.to_string(), "name", "John").await;
+        }
+
+        assert_eq!(2, session_manager.len());
+        let first = session_manager.get(&"first".to_string(), "name").await;
+        assert_eq!(None, first);
+        let third = session_manager.get(&"third".to_string(), "name").await;
+        assert_eq!(Some("John".to_string()), third);
+
+        // Updating existing session does not evict others.
+        let _ = session_manager.set(&"second".to_string(), "age", "20").await;
+        assert_eq!(2, session_manager.len());
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_redis_session_connection_error() {
+        use super::RedisSessionManager;
+
+        assert_eq!("session:abc", RedisSessionManager::session_key("abc"));
+
+        let result = RedisSessionManager::new("invalid://127.0.0.1").await;
+        assert_eq!(true, result.is_err());
+
+        // Nothing listens on port 1.
+        let result = RedisSessionManager::new("redis://127.0.0.1:1/0").await;
+        assert_eq!(true, result.is_err());
+    }
 }
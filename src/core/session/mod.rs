@@ -1,5 +1,6 @@
 pub mod managers;
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
@@ -9,7 +10,7 @@ use uuid::Uuid;
 
 use crate::core::headers::Headers;
 
-use super::cookie;
+use super::cookie::{self, CookieBuilder, SameSite};
 
 pub type SessionResult<T> = Box<dyn Future<Output = T> + Send + Unpin>;
 
@@ -25,11 +26,75 @@ pub trait AbstractSessionManager: Sync + Send {
     /// Returns session value of the client.
     fn get(&self, session_id: &String, name: &str) -> SessionResult<Option<String>>;
 
+    /// Sets or updates multiple session values of the client. The default implementation calls
+    /// `set` for each value, so override it if the storage can write them at once.
+    fn set_many(
+        &self,
+        session_id: &String,
+        values: &[(&str, &str)],
+    ) -> SessionResult<std::io::Result<()>> {
+        let results: Vec<_> = values
+            .iter()
+            .map(|(name, value)| self.set(session_id, name, value))
+            .collect();
+
+        Box::new(Box::pin(async move {
+            for result in results {
+                result.await?;
+            }
+            Ok(())
+        }))
+    }
+
+    /// Returns session values of the client with the given names. Names without value are not
+    /// included. The default implementation calls `get` for each name, so override it if the
+    /// storage can fetch them at once.
+    fn get_many(
+        &self,
+        session_id: &String,
+        names: &[&str],
+    ) -> SessionResult<HashMap<String, String>> {
+        let values: Vec<_> = names
+            .iter()
+            .map(|name| (name.to_string(), self.get(session_id, name)))
+            .collect();
+
+        Box::new(Box::pin(async move {
+            let mut found = HashMap::new();
+            for (name, value) in values {
+                if let Some(value) = value.await {
+                    found.insert(name, value);
+                }
+            }
+            found
+        }))
+    }
+
     /// Removes session key and value of the client.
     fn remove(&self, session_id: &String, name: &str) -> SessionResult<std::io::Result<()>>;
 
     /// Removes all session key and value of the client.
     fn destroy(&self, session_id: &String) -> SessionResult<std::io::Result<()>>;
+
+    /// Moves all session key and value of the client to the new session id. No values remain
+    /// under the old session id.
+    ///
+    /// Session values cannot be listed with the other methods, so the default implementation
+    /// returns `ErrorKind::Unsupported` error. Custom session managers must implement it to
+    /// support `Session::regenerate_id`.
+    fn rename(
+        &self,
+        session_id: &String,
+        new_session_id: &String,
+    ) -> SessionResult<std::io::Result<()>> {
+        let _ = (session_id, new_session_id);
+        Box::new(Box::pin(async move {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Session manager does not implement rename required by regenerate_id.",
+            ))
+        }))
+    }
 }
 
 pub type SessionManager = Box<dyn AbstractSessionManager>;
@@ -38,6 +103,9 @@ pub struct Session {
     session_manager: Arc<SessionManager>,
     session_id: Arc<Mutex<Option<String>>>,
     response_headers: Arc<Mutex<Headers>>,
+    /// Adds `Secure` attribute to the session cookie.
+    secure: bool,
+    same_site: SameSite,
 }
 
 impl Clone for Session {
@@ -46,6 +114,8 @@ impl Clone for Session {
             session_manager: self.session_manager.clone(),
             session_id: self.session_id.clone(),
             response_headers: self.response_headers.clone(),
+            secure: self.secure,
+            same_site: self.same_site,
         }
     }
 }
@@ -68,9 +138,26 @@ impl Session {
             session_manager,
             session_id: Arc::new(Mutex::new(session_id_value)),
             response_headers: response_headers.clone(),
+            secure: false,
+            same_site: SameSite::Lax,
         }
     }
 
+    pub(crate) fn set_secure(&mut self, secure: bool) {
+        self.secure = secure;
+    }
+
+    pub(crate) fn set_same_site(&mut self, same_site: SameSite) {
+        self.same_site = same_site;
+    }
+
+    fn session_cookie(&self, session_id: &str) -> CookieBuilder {
+        CookieBuilder::new("sessionid", session_id)
+            .max_age(Duration::from_secs(7 * 86400))
+            .secure(self.secure)
+            .same_site(self.same_site)
+    }
+
     ///
     /// Session id of the client received from the cookie header `sessionid`. The request instance automatically initializes
     /// with new value if the `sessionid` header is not present.
@@ -103,37 +190,53 @@ impl Session {
         // response.
 
         let mut session_id_lock = self.session_id.lock().await;
-        let session_id;
-
-        if !session_id_lock.is_some() {
-            // Lazily creates sessionid when set method is called.
-            session_id = Uuid::new_v4().to_string();
+        let session_id = self.create_session_id(&mut session_id_lock).await;
+
+        match self
+            .session_manager
+            .set(&session_id, name.as_ref(), value.as_ref())
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(error) => Err(std::io::Error::other(error)),
+        }
+    }
 
-            let mut response_headers = self.response_headers.lock().await;
-            cookie::set_cookie(
-                &mut response_headers,
-                "sessionid",
-                &session_id,
-                Duration::from_secs(7 * 86400),
-            );
+    ///
+    /// Sets or updates multiple session values at once.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    ///
+    /// async fn home(request: Request) {
+    ///   let session = request.session;
+    ///   let _ = session.set_many(&[("name", "John"), ("location", "Ktm")]).await;
+    /// }
+    /// ```
+    ///
+    pub async fn set_many(&self, values: &[(&str, &str)]) -> std::io::Result<()> {
+        let mut session_id_lock = self.session_id.lock().await;
+        let session_id = self.create_session_id(&mut session_id_lock).await;
+        self.session_manager.set_many(&session_id, values).await
+    }
 
-            *session_id_lock = Some(session_id);
+    ///
+    /// Returns current session id. If sessionid was not present in cookie, creates new session id
+    /// and puts additional Set-Cookie header in the response.
+    ///
+    async fn create_session_id(&self, session_id: &mut Option<String>) -> String {
+        if let Some(session_id) = session_id {
+            return session_id.clone();
         }
 
-        if let Some(session_id) = &*session_id_lock {
-            match self
-                .session_manager
-                .set(session_id, name.as_ref(), value.as_ref())
-                .await
-            {
-                Ok(()) => return Ok(()),
-                Err(error) => {
-                    return Err(std::io::Error::other(error));
-                }
-            };
-        }
+        let new_session_id = Uuid::new_v4().to_string();
+        let mut response_headers = self.response_headers.lock().await;
+        let session_cookie = self.session_cookie(&new_session_id);
+        cookie::set_cookie_builder(&mut response_headers, &session_cookie);
 
-        Ok(())
+        *session_id = Some(new_session_id.clone());
+        new_session_id
     }
 
     ///
@@ -167,6 +270,30 @@ impl Session {
         None
     }
 
+    ///
+    /// Returns session values with the given names. Names without value are not included.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    ///
+    /// async fn home(request: Request) {
+    ///   let session = request.session;
+    ///   let values = session.get_many(&["name", "location"]).await;
+    ///   let name = values.get("name");
+    /// }
+    /// ```
+    ///
+    pub async fn get_many(&self, names: &[&str]) -> HashMap<String, String> {
+        let session_id_lock = self.session_id.lock().await;
+
+        if let Some(session_id) = &*session_id_lock {
+            return self.session_manager.get_many(session_id, names).await;
+        }
+
+        HashMap::new()
+    }
+
     ///
     /// Removes session value.
     ///
@@ -190,6 +317,50 @@ impl Session {
         Ok(())
     }
 
+    ///
+    /// Replaces the session id with a new one while keeping the session values. The old session id
+    /// becomes invalid. Call it after login or other privilege changes to prevent session
+    /// fixation attacks.
+    ///
+    /// Returns error and keeps the session id if the session manager does not implement
+    /// `AbstractSessionManager::rename`. Built-in session managers implement it.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    ///
+    /// async fn login(request: Request) {
+    ///   let session = request.session;
+    ///   // Authenticates user
+    ///   let _ = session.regenerate_id().await;
+    ///   let _ = session.set("user_id", "1").await;
+    /// }
+    /// ```
+    ///
+    pub async fn regenerate_id(&self) -> std::io::Result<()> {
+        let mut session_id_lock = self.session_id.lock().await;
+        let new_session_id = Uuid::new_v4().to_string();
+
+        if let Some(session_id) = &*session_id_lock {
+            self.session_manager
+                .rename(session_id, &new_session_id)
+                .await?;
+        }
+
+        let mut response_headers = self.response_headers.lock().await;
+
+        // Removes sessionid cookie set earlier in this request.
+        if let Some(values) = response_headers.get_mut("Set-Cookie") {
+            values.retain(|value| !value.starts_with(b"sessionid="));
+        }
+
+        let session_cookie = self.session_cookie(&new_session_id);
+        cookie::set_cookie_builder(&mut response_headers, &session_cookie);
+
+        *session_id_lock = Some(new_session_id);
+        Ok(())
+    }
+
     ///
     /// Removes all session values of the client.
     ///
@@ -215,3 +386,125 @@ impl Session {
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use tokio::sync::Mutex;
+
+    use crate::core::cookie::SameSite;
+    use crate::core::headers::{HeaderValue, Headers};
+
+    use super::{AbstractSessionManager, Session, SessionManager, SessionResult};
+
+    /// Stores session values as (session id, key) pairs.
+    #[derive(Default)]
+    struct TestSessionManager {
+        values: Arc<StdMutex<HashMap<(String, String), String>>>,
+    }
+
+    impl AbstractSessionManager for TestSessionManager {
+        fn set(
+            &self,
+            session_id: &String,
+            name: &str,
+            value: &str,
+        ) -> SessionResult<std::io::Result<()>> {
+            let mut values = self.values.lock().unwrap();
+            values.insert((session_id.clone(), name.to_string()), value.to_string());
+            Box::new(Box::pin(async move { Ok(()) }))
+        }
+
+        fn get(&self, session_id: &String, name: &str) -> SessionResult<Option<String>> {
+            let values = self.values.lock().unwrap();
+            let value = values.get(&(session_id.clone(), name.to_string())).cloned();
+            Box::new(Box::pin(async move { value }))
+        }
+
+        fn remove(&self, session_id: &String, name: &str) -> SessionResult<std::io::Result<()>> {
+            let mut values = self.values.lock().unwrap();
+            values.remove(&(session_id.clone(), name.to_string()));
+            Box::new(Box::pin(async move { Ok(()) }))
+        }
+
+        fn destroy(&self, session_id: &String) -> SessionResult<std::io::Result<()>> {
+            let mut values = self.values.lock().unwrap();
+            values.retain(|(id, _), _| id != session_id);
+            Box::new(Box::pin(async move { Ok(()) }))
+        }
+
+        fn rename(
+            &self,
+            session_id: &String,
+            new_session_id: &String,
+        ) -> SessionResult<std::io::Result<()>> {
+            let mut values = self.values.lock().unwrap();
+            let old_values: Vec<_> = values
+                .iter()
+                .filter(|((id, _), _)| id == session_id)
+                .map(|((_, key), value)| (key.clone(), value.clone()))
+                .collect();
+
+            values.retain(|(id, _), _| id != session_id);
+            for (key, value) in old_values {
+                values.insert((new_session_id.clone(), key), value);
+            }
+            Box::new(Box::pin(async move { Ok(()) }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_id() {
+        let session_manager: SessionManager = Box::new(TestSessionManager::default());
+        let session_manager = Arc::new(session_manager);
+        let response_headers = Arc::new(Mutex::new(Headers::new()));
+
+        let old_session_id = "old".to_string();
+        session_manager
+            .set(&old_session_id, "name", "John")
+            .await
+            .unwrap();
+
+        let session = Session::from(
+            session_manager.clone(),
+            Some(&old_session_id),
+            response_headers.clone(),
+        );
+        assert_eq!(true, session.regenerate_id().await.is_ok());
+
+        let new_session_id = session.session_id().await.unwrap();
+        assert_ne!(old_session_id, new_session_id);
+        assert_eq!(Some("John".to_string()), session.get("name").await);
+        assert_eq!(None, session_manager.get(&old_session_id, "name").await);
+
+        // Only the new session id is sent in the cookie.
+        assert_eq!(true, session.regenerate_id().await.is_ok());
+        let newest_session_id = session.session_id().await.unwrap();
+
+        let response_headers = response_headers.lock().await;
+        let cookies = response_headers.get("Set-Cookie").unwrap();
+        assert_eq!(1, cookies.len());
+
+        let cookie = String::from_utf8_lossy(&cookies[0]);
+        assert_eq!(true, cookie.starts_with(&format!("sessionid={};", newest_session_id)));
+    }
+
+    #[tokio::test]
+    async fn test_session_cookie_attributes() {
+        let session_manager: SessionManager = Box::new(TestSessionManager::default());
+        let response_headers = Arc::new(Mutex::new(Headers::new()));
+
+        let mut session = Session::from(Arc::new(session_manager), None, response_headers.clone());
+        session.set_secure(true);
+        session.set_same_site(SameSite::Strict);
+        assert_eq!(true, session.set("name", "John").await.is_ok());
+
+        let response_headers = response_headers.lock().await;
+        let cookie = response_headers.value("Set-Cookie").unwrap();
+        assert_eq!(true, cookie.starts_with("sessionid="));
+        let attributes = "; Path=/; Secure; HttpOnly; SameSite=Strict";
+        assert_eq!(true, cookie.ends_with(attributes));
+    }
+}
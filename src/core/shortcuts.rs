@@ -1,11 +1,11 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
 pub trait SingleText {
     /// Performs case-insensitive lookup and returns first value found.
     fn value<S: AsRef<str>>(&self, name: S) -> Option<&String>;
 }
 
-
 impl SingleText for HashMap<String, Vec<String>> {
     fn value<S: AsRef<str>>(&self, name: S) -> Option<&String> {
         let name = name.as_ref();
@@ -22,3 +22,103 @@ impl SingleText for HashMap<String, Vec<String>> {
         None
     }
 }
+
+/// Value which could not be parsed by `MultipleText::parse_all`.
+#[derive(Debug)]
+pub struct ValueParseError<E> {
+    pub value: String,
+    pub error: E,
+}
+
+pub trait MultipleText {
+    /// Performs case-insensitive lookup and parses every value found into type `T`.
+    ///
+    /// Returns all the values which failed to parse as error. Missing key returns empty vector.
+    ///
+    /// ```
+    /// use racoon::core::shortcuts::MultipleText;
+    ///
+    /// // ?id=1&id=2&id=3
+    /// # let mut query_params = std::collections::HashMap::new();
+    /// # query_params.insert("id".to_string(), vec!["1".to_string(), "2".to_string(), "3".to_string()]);
+    /// let ids = query_params.parse_all::<u64>("id");
+    /// assert_eq!(vec![1, 2, 3], ids.unwrap());
+    /// ```
+    fn parse_all<T: FromStr>(&self, name: &str) -> Result<Vec<T>, Vec<ValueParseError<T::Err>>>;
+}
+
+impl MultipleText for HashMap<String, Vec<String>> {
+    fn parse_all<T: FromStr>(&self, name: &str) -> Result<Vec<T>, Vec<ValueParseError<T::Err>>> {
+        let name = name.to_lowercase();
+
+        let mut parsed = vec![];
+        let mut errors = vec![];
+
+        for (key, values) in self.iter() {
+            if key.to_lowercase() != name {
+                continue;
+            }
+
+            for value in values {
+                match value.parse::<T>() {
+                    Ok(t) => parsed.push(t),
+                    Err(error) => errors.push(ValueParseError {
+                        value: value.to_owned(),
+                        error,
+                    }),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(parsed)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashMap;
+
+    use super::MultipleText;
+
+    #[test]
+    fn test_parse_all() {
+        let mut query_params: HashMap<String, Vec<String>> = HashMap::new();
+        query_params.insert(
+            "id".to_string(),
+            vec!["1".to_string(), "2".to_string(), "3".to_string()],
+        );
+
+        let ids = query_params.parse_all::<u32>("ID").unwrap();
+        assert_eq!(vec![1, 2, 3], ids);
+
+        let missing = query_params.parse_all::<u32>("page").unwrap();
+        assert_eq!(true, missing.is_empty());
+    }
+
+    #[test]
+    fn test_parse_all_invalid_values() {
+        let mut query_params: HashMap<String, Vec<String>> = HashMap::new();
+        query_params.insert(
+            "id".to_string(),
+            vec![
+                "1".to_string(),
+                "two".to_string(),
+                "3".to_string(),
+                "-4".to_string(),
+            ],
+        );
+
+        let errors = query_params.parse_all::<u32>("id").unwrap_err();
+        assert_eq!(2, errors.len());
+        assert_eq!("two", errors[0].value);
+        assert_eq!("-4", errors[1].value);
+
+        // Same values are valid for signed integers except text.
+        let errors = query_params.parse_all::<i32>("id").unwrap_err();
+        assert_eq!(1, errors.len());
+    }
+}
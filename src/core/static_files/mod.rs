@@ -0,0 +1,481 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::core::headers::{accepts_encoding, add_vary, HeaderValue};
+use crate::core::method::Method;
+use crate::core::mime;
+use crate::core::parser::path::path_and_raw_query;
+use crate::core::request::Request;
+use crate::core::response::file::FileResponse;
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::{AbstractResponse, HttpResponse, Response};
+use crate::racoon_debug;
+
+#[derive(Debug, PartialEq)]
+pub enum StaticFileError {
+    /// Requested path tries to access files outside the root directory.
+    Forbidden,
+    NotFound,
+}
+
+///
+/// Serves files from a directory. Files are streamed with `FileResponse`, so `Range` requests are
+/// supported.
+///
+/// Requested paths never resolve outside the root directory. Paths containing `..` segments
+/// (including percent encoded ones) or absolute path components are rejected with
+/// `403 Forbidden`, and resolved files are canonicalized to make sure symbolic links do not point
+/// outside the root.
+///
+/// Precompressed variants like `app.js.br` or `app.js.gz` next to the requested file are served
+/// with `Content-Encoding` header when the client accepts the encoding, so assets compressed at
+/// build time are served without runtime compression cost.
+///
+/// Since views are plain functions, `StaticFiles` is used inside a view or the server fallback.
+///
+/// # Examples
+/// ```
+/// use racoon::core::request::Request;
+/// use racoon::core::response::Response;
+/// use racoon::core::static_files::StaticFiles;
+///
+/// async fn assets(request: Request) -> Response {
+///     StaticFiles::new("./static").prefix("/static").serve(&request).await
+/// }
+/// ```
+///
+pub struct StaticFiles {
+    root: PathBuf,
+    index: String,
+    prefix: String,
+    spa_fallback: bool,
+    precompressed: bool,
+}
+
+/// Precompressed file extensions in the order of preference, with their content codings.
+const PRECOMPRESSED_VARIANTS: [(&str, &str); 2] = [("br", "br"), ("gz", "gzip")];
+
+impl StaticFiles {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into(),
+            index: "index.html".to_string(),
+            prefix: "".to_string(),
+            spa_fallback: false,
+            precompressed: true,
+        }
+    }
+
+    /// File served for directory requests. Defaults to `index.html`.
+    pub fn index<S: AsRef<str>>(mut self, index: S) -> Self {
+        self.index = index.as_ref().to_string();
+        self
+    }
+
+    /// URL prefix removed from the request path before looking up the file.
+    pub fn prefix<S: AsRef<str>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.as_ref().trim_end_matches("/").to_string();
+        self
+    }
+
+    ///
+    /// If enabled, index file of the root directory is served for missing paths which are not
+    /// file requests (last segment without extension), so single page applications can handle
+    /// their client-side routes.
+    ///
+    pub fn spa_fallback(mut self, enabled: bool) -> Self {
+        self.spa_fallback = enabled;
+        self
+    }
+
+    ///
+    /// Serves precompressed `.br` and `.gz` variants of the files if they exist and the client
+    /// accepts the encoding. Enabled by default.
+    ///
+    pub fn precompressed(mut self, enabled: bool) -> Self {
+        self.precompressed = enabled;
+        self
+    }
+
+    ///
+    /// Returns file path inside the root directory for the given URL path.
+    ///
+    pub fn resolve(&self, url_path: &str) -> Result<PathBuf, StaticFileError> {
+        let (url_path, _) = path_and_raw_query(url_path);
+
+        let relative_path = match url_path.strip_prefix(&self.prefix) {
+            // Prefix must match complete path segments.
+            Some(path) if path.is_empty() || path.starts_with("/") => path,
+            _ => return Err(StaticFileError::NotFound),
+        };
+
+        let decoded_path = match urlencoding::decode(relative_path) {
+            Ok(path) => path.to_string(),
+            Err(_) => return Err(StaticFileError::NotFound),
+        };
+
+        let mut file_path = self.root.clone();
+        for segment in decoded_path.split("/") {
+            if segment.is_empty() || segment == "." {
+                continue;
+            }
+
+            // Each segment must be a plain file or directory name. Parent directory references,
+            // Windows separators, drive prefixes and NUL bytes are rejected.
+            if segment == ".." || segment.contains('\\') || segment.contains('\0') {
+                return Err(StaticFileError::Forbidden);
+            }
+
+            let mut components = std::path::Path::new(segment).components();
+            if !matches!(
+                (components.next(), components.next()),
+                (Some(Component::Normal(_)), None)
+            ) {
+                return Err(StaticFileError::Forbidden);
+            }
+
+            file_path.push(segment);
+        }
+
+        if file_path.is_dir() {
+            file_path.push(&self.index);
+        }
+
+        if file_path.is_file() {
+            return self.within_root(file_path);
+        }
+
+        if self.spa_fallback && !is_file_request(&decoded_path) {
+            let index_path = self.root.join(&self.index);
+            if index_path.is_file() {
+                return self.within_root(index_path);
+            }
+        }
+
+        Err(StaticFileError::NotFound)
+    }
+
+    ///
+    /// Canonicalizes the file path and makes sure it is still inside the canonicalized root
+    /// directory, so symbolic links cannot be used to escape the root.
+    ///
+    fn within_root(&self, file_path: PathBuf) -> Result<PathBuf, StaticFileError> {
+        let root = match self.root.canonicalize() {
+            Ok(root) => root,
+            Err(_) => return Err(StaticFileError::NotFound),
+        };
+
+        let canonical_path = match file_path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => return Err(StaticFileError::NotFound),
+        };
+
+        if !canonical_path.starts_with(&root) {
+            return Err(StaticFileError::Forbidden);
+        }
+
+        Ok(file_path)
+    }
+
+    ///
+    /// Serves file for the request path. Responds with 403 for paths escaping the root directory
+    /// and 404 for missing files.
+    ///
+    pub async fn serve(&self, request: &Request) -> Response {
+        if !matches!(request.method, Method::Get | Method::Head) {
+            let mut response = HttpResponse::method_not_allowed();
+            response.get_headers().set("Allow", "GET, HEAD");
+            return response.body("Method Not Allowed");
+        }
+
+        let file_path = match self.resolve(&request.path) {
+            Ok(path) => path,
+            Err(StaticFileError::Forbidden) => {
+                racoon_debug!("Denied access to path: {}", request.path);
+                return HttpResponse::forbidden().body("Forbidden");
+            }
+            Err(StaticFileError::NotFound) => {
+                return HttpResponse::not_found().body("404 Page not found");
+            }
+        };
+
+        if self.precompressed {
+            if let Some(response) = self.serve_precompressed(request, &file_path).await {
+                return response;
+            }
+        }
+
+        FileResponse::from_request(request, &file_path).await
+    }
+
+    ///
+    /// Returns response with precompressed variant of the file accepted by the client. Returns
+    /// `None` if no such variant exists.
+    ///
+    async fn serve_precompressed(&self, request: &Request, file_path: &Path) -> Option<Response> {
+        let accept_encoding = request.headers.value("Accept-Encoding").unwrap_or_default();
+        let mut has_variants = false;
+        let mut accepted_variant = None;
+
+        for (extension, coding) in PRECOMPRESSED_VARIANTS {
+            let mut variant_path = file_path.as_os_str().to_owned();
+            variant_path.push(".");
+            variant_path.push(extension);
+
+            let variant_path = match self.within_root(PathBuf::from(variant_path)) {
+                Ok(path) if path.is_file() => path,
+                _ => continue,
+            };
+
+            has_variants = true;
+            if accepted_variant.is_none() && accepts_encoding(&accept_encoding, coding) {
+                accepted_variant = Some((variant_path, coding));
+            }
+        }
+
+        let (variant_path, coding) = match accepted_variant {
+            Some(variant) => variant,
+            None if has_variants => {
+                // Caches must not serve compressed variants to this client.
+                let mut response = FileResponse::from_request(request, file_path).await;
+                add_vary(response.get_headers(), "Accept-Encoding");
+                return Some(response);
+            }
+            None => return None,
+        };
+
+        let mut response = FileResponse::from_request(request, &variant_path).await;
+        let (status_code, _) = response.status();
+        let headers = response.get_headers();
+        add_vary(headers, "Accept-Encoding");
+
+        if status_code == 200 || status_code == 206 {
+            headers.set("Content-Type", mime::from_path(file_path));
+            headers.set("Content-Encoding", coding);
+        }
+        Some(response)
+    }
+}
+
+/// Returns true if the last path segment looks like a file name.
+fn is_file_request(path: &str) -> bool {
+    let last_segment = path.trim_end_matches("/").rsplit("/").next().unwrap_or("");
+    std::path::Path::new(last_segment).extension().is_some()
+}
+
+///
+/// Serves built single page application from the directory. Files are served if they exist,
+/// otherwise the index file is served for client-side routes.
+///
+/// # Examples
+/// ```no_run
+/// use racoon::core::request::Request;
+/// use racoon::core::response::Response;
+/// use racoon::core::server::Server;
+/// use racoon::core::static_files::static_files;
+/// use racoon::view;
+///
+/// async fn frontend(request: Request) -> Response {
+///     static_files(&request, "./dist", "index.html").await
+/// }
+///
+/// # async fn run() {
+/// let _ = Server::bind("127.0.0.1:8080")
+///     .fallback(view!(frontend))
+///     .run()
+///     .await;
+/// # }
+/// ```
+///
+pub async fn static_files<P: Into<PathBuf>, S: AsRef<str>>(
+    request: &Request,
+    dir: P,
+    index: S,
+) -> Response {
+    StaticFiles::new(dir)
+        .index(index)
+        .spa_fallback(true)
+        .serve(request)
+        .await
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::PathBuf;
+
+    use uuid::Uuid;
+
+    use crate::core::request::Request;
+    use crate::core::response::Response;
+    use crate::core::server::tests::{serve_raw, test_state};
+    use crate::core::shortcuts::SingleText;
+
+    use super::{StaticFileError, StaticFiles};
+
+    fn create_site() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("racoon-static-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("assets")).unwrap();
+        std::fs::write(root.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(root.join("assets/app.js"), "console.log(1);").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_resolve_files() {
+        let root = create_site();
+        let static_files = StaticFiles::new(&root);
+
+        assert_eq!(Ok(root.join("index.html")), static_files.resolve("/"));
+        assert_eq!(
+            Ok(root.join("assets/app.js")),
+            static_files.resolve("/assets/app.js?v=1")
+        );
+        assert_eq!(Err(StaticFileError::NotFound), static_files.resolve("/dashboard"));
+        assert_eq!(
+            Err(StaticFileError::Forbidden),
+            static_files.resolve("/assets/../../secret")
+        );
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_resolve_spa_fallback() {
+        let root = create_site();
+        let static_files = StaticFiles::new(&root).prefix("/app/").spa_fallback(true);
+
+        assert_eq!(
+            Ok(root.join("index.html")),
+            static_files.resolve("/app/dashboard/settings")
+        );
+        assert_eq!(
+            Ok(root.join("assets/app.js")),
+            static_files.resolve("/app/assets/app.js")
+        );
+
+        assert_eq!(Err(StaticFileError::NotFound), static_files.resolve("/application"));
+
+        // Missing files are not replaced with index file.
+        assert_eq!(
+            Err(StaticFileError::NotFound),
+            static_files.resolve("/app/assets/missing.js")
+        );
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_resolve_path_traversal() {
+        let root = create_site();
+        let static_files = StaticFiles::new(&root).prefix("/static").spa_fallback(true);
+
+        for path in [
+            "/static/../../etc/passwd",
+            "/static/..",
+            "/static/assets/%2e%2e/%2e%2e/etc/passwd",
+            "/static/..%2f..%2fetc/passwd",
+            "/static/..\\..\\etc\\passwd",
+        ] {
+            assert_eq!(Err(StaticFileError::Forbidden), static_files.resolve(path));
+        }
+
+        // Repeated slashes do not make the path absolute.
+        assert_eq!(
+            Ok(root.join("assets/app.js")),
+            static_files.resolve("/static//assets/app.js")
+        );
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_symlink_outside_root() {
+        let root = create_site();
+        let outside = std::env::temp_dir().join(format!("racoon-outside-{}", Uuid::new_v4()));
+        std::fs::write(&outside, "secret").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("secret.txt")).unwrap();
+
+        let static_files = StaticFiles::new(&root);
+        assert_eq!(Err(StaticFileError::Forbidden), static_files.resolve("/secret.txt"));
+
+        let _ = std::fs::remove_dir_all(root);
+        let _ = std::fs::remove_file(outside);
+    }
+
+    #[tokio::test]
+    async fn test_serve_path_traversal() {
+        async fn serve_static(request: Request) -> Response {
+            StaticFiles::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src"))
+                .prefix("/static")
+                .serve(&request)
+                .await
+        }
+
+        let mut state = test_state(vec![]).await;
+        state.fallback = Some(|request| Box::pin(serve_static(request)));
+        let response = serve_raw(state, b"GET /static/../../etc/passwd HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 403"));
+        assert_eq!(false, response.contains("root:"));
+
+        let mut state = test_state(vec![]).await;
+        state.fallback = Some(|request| Box::pin(serve_static(request)));
+        let response = serve_raw(state, b"GET /static/lib.rs HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(true, response.contains("pub mod core;"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_precompressed() {
+        async fn serve_site(request: Request) -> Response {
+            let root = request.query_params.value("root").unwrap();
+            StaticFiles::new(root).serve(&request).await
+        }
+
+        let root = create_site();
+        std::fs::write(root.join("assets/app.js.gz"), "gzip encoded").unwrap();
+        std::fs::write(root.join("assets/app.js.br"), "brotli encoded").unwrap();
+        let root = root.to_string_lossy().to_string();
+
+        let serve = |path: &str, accept_encoding: Option<&str>| {
+            let mut request = format!("GET {}?root={} HTTP/1.1\r\n", path, root);
+            if let Some(accept_encoding) = accept_encoding {
+                request.push_str(&format!("Accept-Encoding: {}\r\n", accept_encoding));
+            }
+            request.push_str("\r\n");
+
+            async move {
+                let mut state = test_state(vec![]).await;
+                state.fallback = Some(|request| Box::pin(serve_site(request)));
+                serve_raw(state, request.as_bytes()).await
+            }
+        };
+
+        let response = serve("/assets/app.js", Some("gzip, deflate, br")).await;
+        assert_eq!(true, response.contains("Content-Encoding: br\r\n"));
+        assert_eq!(true, response.contains("Content-Type: text/javascript"));
+        assert_eq!(true, response.contains("Vary: Accept-Encoding\r\n"));
+        assert_eq!(true, response.ends_with("brotli encoded"));
+
+        let response = serve("/assets/app.js", Some("gzip")).await;
+        assert_eq!(true, response.contains("Content-Encoding: gzip\r\n"));
+        assert_eq!(true, response.ends_with("gzip encoded"));
+
+        // Client does not accept the available encodings.
+        let response = serve("/assets/app.js", Some("gzip;q=0")).await;
+        assert_eq!(false, response.contains("Content-Encoding"));
+        assert_eq!(true, response.contains("Vary: Accept-Encoding\r\n"));
+        assert_eq!(true, response.ends_with("console.log(1);"));
+
+        let response = serve("/assets/app.js", None).await;
+        assert_eq!(false, response.contains("Content-Encoding"));
+        assert_eq!(true, response.ends_with("console.log(1);"));
+
+        // Precompressed variant does not exist.
+        let response = serve("/index.html", Some("gzip, br")).await;
+        assert_eq!(false, response.contains("Content-Encoding"));
+        assert_eq!(false, response.contains("Vary"));
+        assert_eq!(true, response.ends_with("<html></html>"));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
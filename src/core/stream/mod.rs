@@ -1,10 +1,14 @@
+use std::fmt;
 use std::future::Future;
 use std::io::ErrorKind;
-use std::net::Shutdown;
+use std::net::{IpAddr, Shutdown, SocketAddr};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use socket2::{SockRef, TcpKeepalive};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::io::{ReadHalf, WriteHalf};
 use tokio::net::{TcpStream, UnixStream};
 use tokio::sync::Mutex;
@@ -17,14 +21,220 @@ use crate::{racoon_debug, racoon_error};
 pub type StreamResult<'a, T> = Box<dyn Future<Output = T> + Sync + Send + Unpin + 'a>;
 pub type Stream = Box<dyn AbstractStream>;
 
+///
+/// Address of the connected client.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerAddr {
+    Tcp(SocketAddr),
+    /// Path the client socket is bound to. `None` for unnamed sockets, which is the case for most
+    /// clients as they connect without binding.
+    Unix(Option<PathBuf>),
+}
+
+impl PeerAddr {
+    ///
+    /// Returns IP address of the client. `None` for Unix socket clients.
+    ///
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.socket_addr().map(|socket_addr| socket_addr.ip())
+    }
+
+    ///
+    /// Returns IP address and port of the client. `None` for Unix socket clients.
+    ///
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match self {
+            PeerAddr::Tcp(socket_addr) => Some(*socket_addr),
+            PeerAddr::Unix(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PeerAddr {
+    ///
+    /// Formats TCP address as `127.0.0.1:8080` and Unix socket address as `unix:/path/to/socket`
+    /// or `unix:` for unnamed sockets.
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerAddr::Tcp(socket_addr) => write!(f, "{}", socket_addr),
+            PeerAddr::Unix(Some(path)) => write!(f, "unix:{}", path.display()),
+            PeerAddr::Unix(None) => write!(f, "unix:"),
+        }
+    }
+}
+
+impl From<SocketAddr> for PeerAddr {
+    fn from(socket_addr: SocketAddr) -> Self {
+        PeerAddr::Tcp(socket_addr)
+    }
+}
+
 pub trait AbstractStream: Sync + Send {
     fn buffer_size(&self) -> StreamResult<usize>;
-    fn peer_addr(&self) -> StreamResult<Option<String>>;
+    fn peer_addr(&self) -> StreamResult<Option<PeerAddr>>;
     fn restore_payload<'a>(&'a self, bytes: &[u8]) -> StreamResult<std::io::Result<()>>;
     fn restored_len(&self) -> StreamResult<usize>;
     fn read_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>>;
     fn write_chunk<'a>(&'a self, bytes: &'a [u8]) -> StreamResult<std::io::Result<()>>;
     fn shutdown(&self) -> StreamResult<std::io::Result<()>>;
+
+    ///
+    /// Reads chunk while waiting for the next request on an idle keep-alive connection. Read
+    /// timeout and minimum read rate do not apply, since the idle time is limited by the server.
+    ///
+    fn read_idle_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
+        self.read_chunk()
+    }
+
+    ///
+    /// Starts measuring minimum read rate again, for example before reading the next request on
+    /// the same connection. Does nothing if the stream has no minimum read rate.
+    ///
+    fn restart_read_rate(&self) {}
+
+    ///
+    /// Stops enforcing minimum read rate until `restart_read_rate` is called, for example for
+    /// long-lived WebSocket connections where the client sends messages only occasionally.
+    ///
+    fn stop_read_rate(&self) {}
+}
+
+///
+/// Socket options applied to accepted TCP connections.
+///
+#[derive(Debug, Clone, Copy)]
+pub struct TcpOptions {
+    /// Disables Nagle's algorithm. Without it, small responses may be delayed by up to the
+    /// delayed ACK timeout of the client (commonly 40ms) while the kernel waits to coalesce them.
+    pub nodelay: bool,
+    /// Idle time before TCP keepalive probes are sent. `None` leaves keepalive disabled.
+    pub keepalive: Option<Duration>,
+}
+
+impl Default for TcpOptions {
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive: None,
+        }
+    }
+}
+
+impl TcpOptions {
+    pub fn apply(&self, tcp_stream: &TcpStream) -> std::io::Result<()> {
+        tcp_stream.set_nodelay(self.nodelay)?;
+
+        if let Some(keepalive_time) = self.keepalive {
+            let keepalive = TcpKeepalive::new().with_time(keepalive_time);
+            SockRef::from(tcp_stream).set_tcp_keepalive(&keepalive)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Time allowed after the first byte before minimum read rate is enforced.
+const READ_RATE_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy)]
+enum ReadRateWindow {
+    /// Waiting for the first byte. Idle connections are not limited.
+    Waiting,
+    /// Time of the first byte and total bytes received since then.
+    Started(Instant, u64),
+    Stopped,
+}
+
+///
+/// Tracks bytes received since the first byte of the current request to enforce minimum read
+/// rate.
+///
+#[derive(Debug)]
+struct ReadRate {
+    bytes_per_sec: u64,
+    window: StdMutex<ReadRateWindow>,
+}
+
+impl ReadRate {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            window: StdMutex::new(ReadRateWindow::Waiting),
+        }
+    }
+
+    ///
+    /// Returns time by which more bytes must be received to keep up with the minimum rate.
+    ///
+    fn deadline(&self) -> Option<Instant> {
+        let window = self.window.lock().unwrap();
+
+        if let ReadRateWindow::Started(started, bytes) = *window {
+            let allowed_time = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+            return Some(started + READ_RATE_GRACE_PERIOD + allowed_time);
+        }
+
+        None
+    }
+
+    fn record(&self, bytes: usize) {
+        let mut window = self.window.lock().unwrap();
+
+        *window = match *window {
+            ReadRateWindow::Waiting => ReadRateWindow::Started(Instant::now(), bytes as u64),
+            ReadRateWindow::Started(started, total) => {
+                ReadRateWindow::Started(started, total + bytes as u64)
+            }
+            ReadRateWindow::Stopped => ReadRateWindow::Stopped,
+        };
+    }
+
+    fn set_window(&self, window: ReadRateWindow) {
+        *self.window.lock().unwrap() = window;
+    }
+}
+
+///
+/// Reads bytes from the reader. Fails with `TimedOut` error if no bytes are received within the
+/// timeout or the client sends bytes slower than the minimum read rate.
+///
+async fn read_with_limits<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut [u8],
+    timeout: Option<Duration>,
+    read_rate: Option<&ReadRate>,
+) -> std::io::Result<usize> {
+    let timeout_deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let rate_deadline = read_rate.and_then(|read_rate| read_rate.deadline());
+
+    let deadline = match (timeout_deadline, rate_deadline) {
+        (Some(timeout_deadline), Some(rate_deadline)) => Some(timeout_deadline.min(rate_deadline)),
+        (timeout_deadline, rate_deadline) => timeout_deadline.or(rate_deadline),
+    };
+
+    let result = match deadline {
+        Some(deadline) => {
+            match tokio::time::timeout_at(deadline.into(), reader.read(buffer)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    let message = if Some(deadline) == rate_deadline {
+                        "Client is sending bytes slower than the minimum read rate."
+                    } else {
+                        "Timed out while reading from the stream."
+                    };
+                    Err(std::io::Error::new(ErrorKind::TimedOut, message))
+                }
+            }
+        }
+        None => reader.read(buffer).await,
+    };
+
+    if let (Ok(read_size), Some(read_rate)) = (&result, read_rate) {
+        read_rate.record(*read_size);
+    }
+    result
 }
 
 #[derive(Debug)]
@@ -34,10 +244,22 @@ pub struct TcpStreamWrapper {
     writer: Arc<Mutex<WriteHalf<TcpStream>>>,
     buffer_size: usize,
     restored_payload: Arc<Mutex<Option<Vec<u8>>>>,
+    read_timeout: Option<Duration>,
+    read_rate: Option<Arc<ReadRate>>,
 }
 
 impl TcpStreamWrapper {
     pub fn from(tcp_stream: TcpStream, buffer_size: usize) -> std::io::Result<Self> {
+        Self::with_options(tcp_stream, buffer_size, TcpOptions::default())
+    }
+
+    pub fn with_options(
+        tcp_stream: TcpStream,
+        buffer_size: usize,
+        tcp_options: TcpOptions,
+    ) -> std::io::Result<Self> {
+        tcp_options.apply(&tcp_stream)?;
+
         // May return "Too many open files error" if all file descriptors are used.
         let std_tcp_stream = tcp_stream.into_std()?;
 
@@ -62,8 +284,73 @@ impl TcpStreamWrapper {
             writer: Arc::new(Mutex::new(writer)),
             buffer_size,
             restored_payload: Arc::new(Mutex::new(None)),
+            read_timeout: None,
+            read_rate: None,
         })
     }
+
+    ///
+    /// Maximum time to wait for bytes in a single read. Reads fail with `TimedOut` error when
+    /// exceeded. Unlimited by default.
+    ///
+    pub fn set_read_timeout(&mut self, read_timeout: Option<Duration>) {
+        self.read_timeout = read_timeout;
+    }
+
+    ///
+    /// Minimum bytes per second the client must send once it starts sending a request. Reads fail
+    /// with `TimedOut` error when the client is slower. Not limited by default.
+    ///
+    pub fn set_min_read_rate(&mut self, bytes_per_sec: Option<u64>) {
+        self.read_rate = bytes_per_sec
+            .filter(|bytes_per_sec| *bytes_per_sec > 0)
+            .map(|bytes_per_sec| Arc::new(ReadRate::new(bytes_per_sec)));
+    }
+
+    ///
+    /// Reads chunk, failing if no bytes are received within `read_timeout` or slower than
+    /// `read_rate`.
+    ///
+    fn read_chunk_with_limits(
+        &self,
+        read_timeout: Option<Duration>,
+        read_rate: Option<Arc<ReadRate>>,
+    ) -> StreamResult<std::io::Result<Vec<u8>>> {
+        let restored_payload_ref = self.restored_payload.clone();
+        let reader_ref = self.reader.clone();
+        let buffer_size = self.buffer_size.clone();
+
+        Box::new(Box::pin(async move {
+            // If payload of some bytes is restored after reading the chunk, returns the same bytes
+            // back to the reader again.
+            // Reading from stream wrapper is skipped because there may not be any bytes to read.
+            let mut restored_payload = restored_payload_ref.lock().await;
+
+            if let Some(payload) = restored_payload.take() {
+                // Leaves None
+                return Ok(payload);
+            }
+
+            let mut buffer = vec![0u8; buffer_size];
+            let mut reader = reader_ref.lock().await;
+
+            return match read_with_limits(&mut *reader, &mut buffer, read_timeout, read_rate.as_deref()).await {
+                Ok(read_size) => {
+                    if read_size == 0 {
+                        return Err(std::io::Error::new(
+                            ErrorKind::BrokenPipe,
+                            "Read size is 0. Probably connection broken.",
+                        ));
+                    }
+
+                    let chunk: Vec<u8> = buffer.drain(0..read_size).collect();
+                    Ok(chunk)
+                }
+                Err(error) if error.kind() == ErrorKind::TimedOut => Err(error),
+                Err(error) => Err(std::io::Error::other(error)),
+            };
+        }))
+    }
 }
 
 impl AbstractStream for TcpStreamWrapper {
@@ -72,14 +359,14 @@ impl AbstractStream for TcpStreamWrapper {
         Box::new(Box::pin(async move { buffer_size }))
     }
 
-    fn peer_addr(&self) -> StreamResult<Option<String>> {
+    fn peer_addr(&self) -> StreamResult<Option<PeerAddr>> {
         let stream_ref = self.stream.clone();
 
         Box::new(Box::pin(async move {
             let stream = stream_ref.lock().await;
 
             return match stream.peer_addr() {
-                Ok(addr) => Some(addr.to_string()),
+                Ok(addr) => Some(PeerAddr::Tcp(addr)),
                 Err(error) => {
                     racoon_debug!("Failed to get peer addr. Error: {}", error);
                     None
@@ -114,39 +401,11 @@ impl AbstractStream for TcpStreamWrapper {
     }
 
     fn read_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
-        let restored_payload_ref = self.restored_payload.clone();
-        let reader_ref = self.reader.clone();
-        let buffer_size = self.buffer_size.clone();
-
-        Box::new(Box::pin(async move {
-            // If payload of some bytes is restored after reading the chunk, returns the same bytes
-            // back to the reader again.
-            // Reading from stream wrapper is skipped because there may not be any bytes to read.
-            let mut restored_payload = restored_payload_ref.lock().await;
-
-            if let Some(payload) = restored_payload.take() {
-                // Leaves None
-                return Ok(payload);
-            }
-
-            let mut buffer = vec![0u8; buffer_size];
-            let mut reader = reader_ref.lock().await;
-
-            return match reader.read(&mut buffer).await {
-                Ok(read_size) => {
-                    if read_size == 0 {
-                        return Err(std::io::Error::new(
-                            ErrorKind::BrokenPipe,
-                            "Read size is 0. Probably connection broken.",
-                        ));
-                    }
+        self.read_chunk_with_limits(self.read_timeout, self.read_rate.clone())
+    }
 
-                    let chunk: Vec<u8> = buffer.drain(0..read_size).collect();
-                    Ok(chunk)
-                }
-                Err(error) => Err(std::io::Error::other(error)),
-            };
-        }))
+    fn read_idle_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
+        self.read_chunk_with_limits(None, None)
     }
 
     fn write_chunk<'a>(&'a self, data: &'a [u8]) -> StreamResult<std::io::Result<()>> {
@@ -168,6 +427,18 @@ impl AbstractStream for TcpStreamWrapper {
             Ok(())
         }))
     }
+
+    fn restart_read_rate(&self) {
+        if let Some(read_rate) = &self.read_rate {
+            read_rate.set_window(ReadRateWindow::Waiting);
+        }
+    }
+
+    fn stop_read_rate(&self) {
+        if let Some(read_rate) = &self.read_rate {
+            read_rate.set_window(ReadRateWindow::Stopped);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -177,6 +448,8 @@ pub struct UnixStreamWrapper {
     writer: Arc<Mutex<WriteHalf<UnixStream>>>,
     buffer_size: usize,
     restored_payload: Arc<Mutex<Option<Vec<u8>>>>,
+    read_timeout: Option<Duration>,
+    read_rate: Option<Arc<ReadRate>>,
 }
 
 impl UnixStreamWrapper {
@@ -202,50 +475,38 @@ impl UnixStreamWrapper {
             writer: Arc::new(Mutex::new(writer)),
             buffer_size,
             restored_payload: Arc::new(Mutex::new(None)),
+            read_timeout: None,
+            read_rate: None,
         })
     }
-}
 
-impl AbstractStream for UnixStreamWrapper {
-    fn buffer_size(&self) -> StreamResult<usize> {
-        let buffer_size = self.buffer_size.clone();
-        Box::new(Box::pin(async move { buffer_size }))
+    ///
+    /// Maximum time to wait for bytes in a single read. Reads fail with `TimedOut` error when
+    /// exceeded. Unlimited by default.
+    ///
+    pub fn set_read_timeout(&mut self, read_timeout: Option<Duration>) {
+        self.read_timeout = read_timeout;
     }
 
-    fn peer_addr(&self) -> StreamResult<Option<String>> {
-        Box::new(Box::pin(async move {
-            return None;
-        }))
+    ///
+    /// Minimum bytes per second the client must send once it starts sending a request. Reads fail
+    /// with `TimedOut` error when the client is slower. Not limited by default.
+    ///
+    pub fn set_min_read_rate(&mut self, bytes_per_sec: Option<u64>) {
+        self.read_rate = bytes_per_sec
+            .filter(|bytes_per_sec| *bytes_per_sec > 0)
+            .map(|bytes_per_sec| Arc::new(ReadRate::new(bytes_per_sec)));
     }
 
-    fn restore_payload(&self, bytes: &[u8]) -> StreamResult<std::io::Result<()>> {
-        let restored_payload = self.restored_payload.clone();
-        let bytes = bytes.to_vec();
-
-        Box::new(Box::pin(async move {
-            let restored_payload_ref = restored_payload.clone();
-            let mut restored_payload = restored_payload_ref.lock().await;
-            *restored_payload = Some(bytes);
-            Ok(())
-        }))
-    }
-
-    fn restored_len(&self) -> StreamResult<usize> {
-        let restored_payload = self.restored_payload.clone();
-
-        Box::new(Box::pin(async move {
-            let restored_payload_ref = restored_payload.clone();
-            let restored_payload = restored_payload_ref.lock().await;
-
-            if let Some(restored) = restored_payload.as_ref() {
-                return restored.len();
-            }
-
-            0
-        }))
-    }
-
-    fn read_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
+    ///
+    /// Reads chunk, failing if no bytes are received within `read_timeout` or slower than
+    /// `read_rate`.
+    ///
+    fn read_chunk_with_limits(
+        &self,
+        read_timeout: Option<Duration>,
+        read_rate: Option<Arc<ReadRate>>,
+    ) -> StreamResult<std::io::Result<Vec<u8>>> {
         // If payload of some bytes is restored after reading the chunk, returns the same bytes
         // back to the reader again.
         // Reading from stream wrapper is skipped because there may not be any bytes to read.
@@ -268,7 +529,7 @@ impl AbstractStream for UnixStreamWrapper {
             let reader_ref = reader.clone();
             let mut reader = reader_ref.lock().await;
 
-            return match reader.read(&mut buffer).await {
+            return match read_with_limits(&mut *reader, &mut buffer, read_timeout, read_rate.as_deref()).await {
                 Ok(read_size) => {
                     if read_size == 0 {
                         return Err(std::io::Error::new(
@@ -280,10 +541,69 @@ impl AbstractStream for UnixStreamWrapper {
                     let chunk = &buffer[0..read_size];
                     Ok(chunk.to_vec())
                 }
+                Err(error) if error.kind() == ErrorKind::TimedOut => Err(error),
                 Err(error) => Err(std::io::Error::other(error)),
             };
         }))
     }
+}
+
+impl AbstractStream for UnixStreamWrapper {
+    fn buffer_size(&self) -> StreamResult<usize> {
+        let buffer_size = self.buffer_size.clone();
+        Box::new(Box::pin(async move { buffer_size }))
+    }
+
+    fn peer_addr(&self) -> StreamResult<Option<PeerAddr>> {
+        let stream_ref = self.stream.clone();
+
+        Box::new(Box::pin(async move {
+            let stream = stream_ref.lock().await;
+
+            match stream.peer_addr() {
+                Ok(addr) => Some(PeerAddr::Unix(addr.as_pathname().map(PathBuf::from))),
+                Err(error) => {
+                    racoon_debug!("Failed to get peer addr. Error: {}", error);
+                    None
+                }
+            }
+        }))
+    }
+
+    fn restore_payload(&self, bytes: &[u8]) -> StreamResult<std::io::Result<()>> {
+        let restored_payload = self.restored_payload.clone();
+        let bytes = bytes.to_vec();
+
+        Box::new(Box::pin(async move {
+            let restored_payload_ref = restored_payload.clone();
+            let mut restored_payload = restored_payload_ref.lock().await;
+            *restored_payload = Some(bytes);
+            Ok(())
+        }))
+    }
+
+    fn restored_len(&self) -> StreamResult<usize> {
+        let restored_payload = self.restored_payload.clone();
+
+        Box::new(Box::pin(async move {
+            let restored_payload_ref = restored_payload.clone();
+            let restored_payload = restored_payload_ref.lock().await;
+
+            if let Some(restored) = restored_payload.as_ref() {
+                return restored.len();
+            }
+
+            0
+        }))
+    }
+
+    fn read_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
+        self.read_chunk_with_limits(self.read_timeout, self.read_rate.clone())
+    }
+
+    fn read_idle_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
+        self.read_chunk_with_limits(None, None)
+    }
 
     fn write_chunk(&self, data: &[u8]) -> StreamResult<std::io::Result<()>> {
         let writer_ref = self.writer.clone();
@@ -305,16 +625,30 @@ impl AbstractStream for UnixStreamWrapper {
             Ok(())
         }))
     }
+
+    fn restart_read_rate(&self) {
+        if let Some(read_rate) = &self.read_rate {
+            read_rate.set_window(ReadRateWindow::Waiting);
+        }
+    }
+
+    fn stop_read_rate(&self) {
+        if let Some(read_rate) = &self.read_rate {
+            read_rate.set_window(ReadRateWindow::Stopped);
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct TlsTcpStreamWrapper {
-    peer_addr: String,
+    peer_addr: SocketAddr,
     stream: Arc<Mutex<TcpStream>>,
     reader: Arc<Mutex<ReadHalf<TlsStream<TcpStream>>>>,
     writer: Arc<Mutex<WriteHalf<TlsStream<TcpStream>>>>,
     buffer_size: usize,
     restored_payload: Arc<Mutex<Option<Vec<u8>>>>,
+    read_timeout: Option<Duration>,
+    read_rate: Option<Arc<ReadRate>>,
 }
 
 impl TlsTcpStreamWrapper {
@@ -323,7 +657,18 @@ impl TlsTcpStreamWrapper {
         tls_acceptor: &TlsAcceptor,
         buffer_size: usize,
     ) -> std::io::Result<Self> {
-        let peer_addr = tcp_stream.peer_addr()?.to_string();
+        Self::with_options(tcp_stream, tls_acceptor, buffer_size, TcpOptions::default()).await
+    }
+
+    pub async fn with_options(
+        tcp_stream: TcpStream,
+        tls_acceptor: &TlsAcceptor,
+        buffer_size: usize,
+        tcp_options: TcpOptions,
+    ) -> std::io::Result<Self> {
+        tcp_options.apply(&tcp_stream)?;
+
+        let peer_addr = tcp_stream.peer_addr()?;
         let std_tcp_stream = tcp_stream.into_std()?;
 
         // Stream for shutting down reader/writer later
@@ -340,8 +685,74 @@ impl TlsTcpStreamWrapper {
             writer: Arc::new(Mutex::new(writer)),
             buffer_size,
             restored_payload: Arc::new(Mutex::new(None)),
+            read_timeout: None,
+            read_rate: None,
         })
     }
+
+    ///
+    /// Maximum time to wait for bytes in a single read. Reads fail with `TimedOut` error when
+    /// exceeded. Unlimited by default.
+    ///
+    pub fn set_read_timeout(&mut self, read_timeout: Option<Duration>) {
+        self.read_timeout = read_timeout;
+    }
+
+    ///
+    /// Minimum bytes per second the client must send once it starts sending a request. Reads fail
+    /// with `TimedOut` error when the client is slower. Not limited by default.
+    ///
+    pub fn set_min_read_rate(&mut self, bytes_per_sec: Option<u64>) {
+        self.read_rate = bytes_per_sec
+            .filter(|bytes_per_sec| *bytes_per_sec > 0)
+            .map(|bytes_per_sec| Arc::new(ReadRate::new(bytes_per_sec)));
+    }
+
+    ///
+    /// Reads chunk, failing if no bytes are received within `read_timeout` or slower than
+    /// `read_rate`.
+    ///
+    fn read_chunk_with_limits(
+        &self,
+        read_timeout: Option<Duration>,
+        read_rate: Option<Arc<ReadRate>>,
+    ) -> StreamResult<std::io::Result<Vec<u8>>> {
+        // If payload of some bytes is restored after reading the chunk, returns the same bytes
+        // back to the reader again.
+        // Reading from stream wrapper is skipped because there may not be any bytes to read.
+        let restored_payload_ref = self.restored_payload.clone();
+        let buffer_size = self.buffer_size.clone();
+        let reader = self.reader.clone();
+
+        Box::new(Box::pin(async move {
+            let mut restored_payload = restored_payload_ref.lock().await;
+
+            if let Some(payload) = restored_payload.as_ref() {
+                let buffer = payload.to_owned();
+                *restored_payload = None;
+                return Ok(buffer);
+            }
+
+            let mut buffer = vec![0u8; buffer_size];
+            let mut reader = reader.lock().await;
+
+            return match read_with_limits(&mut *reader, &mut buffer, read_timeout, read_rate.as_deref()).await {
+                Ok(read_size) => {
+                    if read_size == 0 {
+                        return Err(std::io::Error::new(
+                            ErrorKind::BrokenPipe,
+                            "Read size is 0. Probably connection broken.",
+                        ));
+                    }
+
+                    let chunk = &buffer[0..read_size];
+                    Ok(chunk.to_vec())
+                }
+                Err(error) if error.kind() == ErrorKind::TimedOut => Err(error),
+                Err(error) => Err(std::io::Error::other(error)),
+            };
+        }))
+    }
 }
 
 impl AbstractStream for TlsTcpStreamWrapper {
@@ -350,10 +761,10 @@ impl AbstractStream for TlsTcpStreamWrapper {
         Box::new(Box::pin(async move { buffer_size }))
     }
 
-    fn peer_addr(&self) -> StreamResult<Option<String>> {
-        let peer_addr = self.peer_addr.clone();
+    fn peer_addr(&self) -> StreamResult<Option<PeerAddr>> {
+        let peer_addr = self.peer_addr;
 
-        Box::new(Box::pin(async move { Some(peer_addr) }))
+        Box::new(Box::pin(async move { Some(PeerAddr::Tcp(peer_addr)) }))
     }
 
     fn restore_payload(&self, bytes: &[u8]) -> StreamResult<std::io::Result<()>> {
@@ -383,40 +794,11 @@ impl AbstractStream for TlsTcpStreamWrapper {
     }
 
     fn read_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
-        // If payload of some bytes is restored after reading the chunk, returns the same bytes
-        // back to the reader again.
-        // Reading from stream wrapper is skipped because there may not be any bytes to read.
-        let restored_payload_ref = self.restored_payload.clone();
-        let buffer_size = self.buffer_size.clone();
-        let reader = self.reader.clone();
-
-        Box::new(Box::pin(async move {
-            let mut restored_payload = restored_payload_ref.lock().await;
-
-            if let Some(payload) = restored_payload.as_ref() {
-                let buffer = payload.to_owned();
-                *restored_payload = None;
-                return Ok(buffer);
-            }
-
-            let mut buffer = vec![0u8; buffer_size];
-            let mut reader = reader.lock().await;
-
-            return match reader.read(&mut buffer).await {
-                Ok(read_size) => {
-                    if read_size == 0 {
-                        return Err(std::io::Error::new(
-                            ErrorKind::BrokenPipe,
-                            "Read size is 0. Probably connection broken.",
-                        ));
-                    }
+        self.read_chunk_with_limits(self.read_timeout, self.read_rate.clone())
+    }
 
-                    let chunk = &buffer[0..read_size];
-                    Ok(chunk.to_vec())
-                }
-                Err(error) => Err(std::io::Error::other(error)),
-            };
-        }))
+    fn read_idle_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
+        self.read_chunk_with_limits(None, None)
     }
 
     fn write_chunk(&self, data: &[u8]) -> StreamResult<std::io::Result<()>> {
@@ -439,24 +821,74 @@ impl AbstractStream for TlsTcpStreamWrapper {
             Ok(())
         }))
     }
+
+    fn restart_read_rate(&self) {
+        if let Some(read_rate) = &self.read_rate {
+            read_rate.set_window(ReadRateWindow::Waiting);
+        }
+    }
+
+    fn stop_read_rate(&self) {
+        if let Some(read_rate) = &self.read_rate {
+            read_rate.set_window(ReadRateWindow::Stopped);
+        }
+    }
 }
 
 pub struct TestStreamWrapper {
     test_data: Arc<Mutex<Vec<u8>>>,
+    written_data: Arc<Mutex<Vec<u8>>>,
     buffer_size: usize,
     is_shutdown: Arc<AtomicBool>,
     restored_payload: Arc<Mutex<Option<Vec<u8>>>>,
+    peer_addr: Option<PeerAddr>,
+    read_timeout: Option<Duration>,
 }
 
 impl TestStreamWrapper {
     pub fn new(test_data: Vec<u8>, buffer_size: usize) -> Self {
         Self {
             test_data: Arc::new(Mutex::new(test_data)),
+            written_data: Arc::new(Mutex::new(vec![])),
             buffer_size,
             is_shutdown: Arc::new(AtomicBool::new(false)),
             restored_payload: Arc::new(Mutex::new(None)),
+            peer_addr: None,
+            read_timeout: None,
         }
     }
+
+    ///
+    /// Simulates stalled client. Once the test data is read completely, reads wait for the
+    /// timeout and fail with `TimedOut` error like the network streams, instead of failing
+    /// immediately as if the client disconnected.
+    ///
+    pub fn set_read_timeout(&mut self, read_timeout: Option<Duration>) {
+        self.read_timeout = read_timeout;
+    }
+
+    ///
+    /// Sets address returned as the peer address of the stream.
+    ///
+    pub fn set_peer_addr<A: Into<PeerAddr>>(&mut self, peer_addr: A) {
+        self.peer_addr = Some(peer_addr.into());
+    }
+
+    ///
+    /// Returns buffer of the bytes not read yet. Bytes appended to the buffer are returned by the
+    /// later reads.
+    ///
+    pub fn test_data(&self) -> Arc<Mutex<Vec<u8>>> {
+        self.test_data.clone()
+    }
+
+    ///
+    /// Returns buffer containing all the bytes written to the stream. The buffer is shared, so it
+    /// can be inspected after the stream is moved.
+    ///
+    pub fn written_data(&self) -> Arc<Mutex<Vec<u8>>> {
+        self.written_data.clone()
+    }
 }
 
 impl AbstractStream for TestStreamWrapper {
@@ -464,8 +896,9 @@ impl AbstractStream for TestStreamWrapper {
         Box::new(Box::pin(async move { self.buffer_size.clone() }))
     }
 
-    fn peer_addr(&self) -> StreamResult<Option<String>> {
-        Box::new(Box::pin(async move { None }))
+    fn peer_addr(&self) -> StreamResult<Option<PeerAddr>> {
+        let peer_addr = self.peer_addr.clone();
+        Box::new(Box::pin(async move { peer_addr }))
     }
 
     fn shutdown(&self) -> StreamResult<std::io::Result<()>> {
@@ -473,13 +906,16 @@ impl AbstractStream for TestStreamWrapper {
         Box::new(Box::pin(async move { Ok(()) }))
     }
 
-    fn write_chunk(&self, _: &[u8]) -> StreamResult<std::io::Result<()>> {
+    fn write_chunk<'a>(&'a self, bytes: &'a [u8]) -> StreamResult<std::io::Result<()>> {
         Box::new(Box::pin(async move {
             if self.is_shutdown.load(Ordering::Relaxed) {
                 return Err(std::io::Error::other(
                     "Test Stream is already shutdown. Failed to write chunk.",
                 ));
             }
+
+            let mut written_data = self.written_data.lock().await;
+            written_data.extend_from_slice(bytes);
             Ok(())
         }))
     }
@@ -508,7 +944,19 @@ impl AbstractStream for TestStreamWrapper {
             // Reads bytes from test data
             let read_size = std::cmp::min(self.buffer_size, test_data.len());
             if read_size == 0 {
-                return Err(std::io::Error::other("No bytes left to read."));
+                if let Some(read_timeout) = self.read_timeout {
+                    drop(test_data);
+                    tokio::time::sleep(read_timeout).await;
+                    return Err(std::io::Error::new(
+                        ErrorKind::TimedOut,
+                        "Timed out while reading from the stream.",
+                    ));
+                }
+                // Same error kind as the closed connection of the other streams.
+                return Err(std::io::Error::new(
+                    ErrorKind::BrokenPipe,
+                    "No bytes left to read.",
+                ));
             }
 
             let removed_bytes = test_data.drain(0..read_size).collect();
@@ -540,3 +988,294 @@ impl AbstractStream for TestStreamWrapper {
         }))
     }
 }
+
+///
+/// Raw bytes recorded by `TeeStreamWrapper`. Clones share the same record.
+///
+#[derive(Clone)]
+pub struct BodyTee {
+    limit: usize,
+    record: Arc<StdMutex<TeeRecord>>,
+}
+
+struct TeeRecord {
+    bytes: Vec<u8>,
+    /// Number of bytes read through the tee, including the ones exceeding the limit.
+    read_len: usize,
+    /// Bytes are neither recorded nor unrecorded while paused.
+    paused: bool,
+}
+
+impl BodyTee {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            record: Arc::new(StdMutex::new(TeeRecord {
+                bytes: vec![],
+                read_len: 0,
+                paused: false,
+            })),
+        }
+    }
+
+    ///
+    /// Returns recorded bytes. At most `limit` bytes are recorded.
+    ///
+    pub fn bytes(&self) -> Vec<u8> {
+        let record = self.lock_record();
+        record.bytes.clone()
+    }
+
+    ///
+    /// Returns true if more bytes were read than recorded.
+    ///
+    pub fn is_truncated(&self) -> bool {
+        let record = self.lock_record();
+        record.read_len > record.bytes.len()
+    }
+
+    ///
+    /// Pauses recording, for example while raw chunked body is decoded. Decoded body restored to
+    /// the stream is recorded after resuming.
+    ///
+    pub(crate) fn set_paused(&self, paused: bool) {
+        self.lock_record().paused = paused;
+    }
+
+    fn lock_record(&self) -> std::sync::MutexGuard<'_, TeeRecord> {
+        self.record
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn record(&self, chunk: &[u8]) {
+        let mut record = self.lock_record();
+        if record.paused {
+            return;
+        }
+
+        let available = self.limit.saturating_sub(record.bytes.len());
+        let record_len = std::cmp::min(available, chunk.len());
+
+        record.bytes.extend_from_slice(&chunk[..record_len]);
+        record.read_len += chunk.len();
+    }
+
+    /// Restored bytes are read again later, so they are removed from the end of the record.
+    fn unrecord(&self, restored_len: usize) {
+        let mut record = self.lock_record();
+        if record.paused {
+            return;
+        }
+
+        record.read_len = record.read_len.saturating_sub(restored_len);
+
+        let read_len = record.read_len;
+        record.bytes.truncate(read_len);
+    }
+}
+
+///
+/// Stream recording the bytes read from the inner stream to `BodyTee`, so the request body can be
+/// inspected after parsers consume it.
+///
+pub struct TeeStreamWrapper {
+    inner: Arc<Stream>,
+    tee: BodyTee,
+}
+
+impl TeeStreamWrapper {
+    pub fn new(inner: Arc<Stream>, tee: BodyTee) -> Self {
+        Self { inner, tee }
+    }
+}
+
+impl AbstractStream for TeeStreamWrapper {
+    fn buffer_size(&self) -> StreamResult<usize> {
+        self.inner.buffer_size()
+    }
+
+    fn peer_addr(&self) -> StreamResult<Option<PeerAddr>> {
+        self.inner.peer_addr()
+    }
+
+    fn restore_payload<'a>(&'a self, bytes: &[u8]) -> StreamResult<std::io::Result<()>> {
+        let restored_len = bytes.len();
+        let restore = self.inner.restore_payload(bytes);
+
+        Box::new(Box::pin(async move {
+            restore.await?;
+            self.tee.unrecord(restored_len);
+            Ok(())
+        }))
+    }
+
+    fn restored_len(&self) -> StreamResult<usize> {
+        self.inner.restored_len()
+    }
+
+    fn read_chunk(&self) -> StreamResult<std::io::Result<Vec<u8>>> {
+        Box::new(Box::pin(async move {
+            let chunk = self.inner.read_chunk().await?;
+            self.tee.record(&chunk);
+            Ok(chunk)
+        }))
+    }
+
+    fn write_chunk<'a>(&'a self, bytes: &'a [u8]) -> StreamResult<std::io::Result<()>> {
+        self.inner.write_chunk(bytes)
+    }
+
+    fn shutdown(&self) -> StreamResult<std::io::Result<()>> {
+        self.inner.shutdown()
+    }
+
+    fn restart_read_rate(&self) {
+        self.inner.restart_read_rate()
+    }
+
+    fn stop_read_rate(&self) {
+        self.inner.stop_read_rate()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::{Duration, Instant};
+
+    use std::io::ErrorKind;
+
+    use socket2::SockRef;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+
+    use std::sync::Arc;
+
+    use super::{AbstractStream, BodyTee, PeerAddr, Stream, TcpOptions, TcpStreamWrapper};
+    use super::{TeeStreamWrapper, TestStreamWrapper, UnixStreamWrapper};
+
+    #[tokio::test]
+    async fn test_tcp_options() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(address).await.unwrap();
+        let (tcp_stream, _) = listener.accept().await.unwrap();
+
+        let tcp_options = TcpOptions::default();
+        assert_eq!(true, tcp_options.apply(&tcp_stream).is_ok());
+        assert_eq!(true, tcp_stream.nodelay().unwrap());
+        assert_eq!(false, SockRef::from(&tcp_stream).keepalive().unwrap());
+
+        let tcp_options = TcpOptions {
+            nodelay: false,
+            keepalive: Some(Duration::from_secs(60)),
+        };
+        assert_eq!(true, tcp_options.apply(&tcp_stream).is_ok());
+        assert_eq!(false, tcp_stream.nodelay().unwrap());
+        assert_eq!(true, SockRef::from(&tcp_stream).keepalive().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_peer_addr() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = TcpStream::connect(address).await.unwrap();
+        let (tcp_stream, _) = listener.accept().await.unwrap();
+
+        let client_addr = client.local_addr().unwrap();
+
+        let stream = TcpStreamWrapper::from(tcp_stream, 1024).unwrap();
+        let peer_addr = stream.peer_addr().await.unwrap();
+        assert_eq!(PeerAddr::Tcp(client_addr), peer_addr);
+        assert_eq!(Some(client_addr.ip()), peer_addr.ip());
+        assert_eq!(client_addr.to_string(), peer_addr.to_string());
+
+        let socket_name = format!("racoon-{}.sock", uuid::Uuid::new_v4());
+        let socket_path = std::env::temp_dir().join(socket_name);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        let _client = UnixStream::connect(&socket_path).await.unwrap();
+        let (unix_stream, _) = listener.accept().await.unwrap();
+
+        // Connecting clients are not bound to any path.
+        let stream = UnixStreamWrapper::from(unix_stream, 1024).unwrap();
+        let peer_addr = stream.peer_addr().await.unwrap();
+        assert_eq!(PeerAddr::Unix(None), peer_addr);
+        assert_eq!(None, peer_addr.ip());
+        assert_eq!("unix:", peer_addr.to_string());
+        let _ = std::fs::remove_file(socket_path);
+
+        let peer_addr = PeerAddr::Unix(Some("/tmp/client.sock".into()));
+        assert_eq!("unix:/tmp/client.sock", peer_addr.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let (tcp_stream, _) = listener.accept().await.unwrap();
+
+        let mut stream = TcpStreamWrapper::from(tcp_stream, 1024).unwrap();
+        stream.set_read_timeout(Some(Duration::from_millis(100)));
+
+        client.write_all(b"GET").await.unwrap();
+        assert_eq!(b"GET".to_vec(), stream.read_chunk().await.unwrap());
+
+        // Client stops sending bytes.
+        let error = stream.read_chunk().await.unwrap_err();
+        assert_eq!(ErrorKind::TimedOut, error.kind());
+    }
+
+    #[tokio::test]
+    async fn test_min_read_rate() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let (tcp_stream, _) = listener.accept().await.unwrap();
+
+        let mut stream = TcpStreamWrapper::from(tcp_stream, 1024).unwrap();
+        stream.set_min_read_rate(Some(1000));
+
+        // Idle time before the first byte is not limited.
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        client.write_all(b"GET").await.unwrap();
+        assert_eq!(b"GET".to_vec(), stream.read_chunk().await.unwrap());
+
+        // 3 bytes allow waiting for about 1 second.
+        let started = Instant::now();
+        let error = stream.read_chunk().await.unwrap_err();
+        assert_eq!(ErrorKind::TimedOut, error.kind());
+        assert_eq!(true, started.elapsed() < Duration::from_millis(1500));
+
+        // Measurement starts again for the next request.
+        stream.restart_read_rate();
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        client.write_all(b"GET").await.unwrap();
+        assert_eq!(b"GET".to_vec(), stream.read_chunk().await.unwrap());
+
+        stream.stop_read_rate();
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        client.write_all(b"GET").await.unwrap();
+        assert_eq!(b"GET".to_vec(), stream.read_chunk().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_tee_stream() {
+        let inner: Stream = Box::new(TestStreamWrapper::new(b"name=John&age=20".to_vec(), 10));
+        let tee = BodyTee::new(12);
+        let stream = TeeStreamWrapper::new(Arc::new(inner), tee.clone());
+
+        assert_eq!(b"name=John&".to_vec(), stream.read_chunk().await.unwrap());
+        assert_eq!(b"name=John&".to_vec(), tee.bytes());
+
+        // Restored bytes are recorded again when read.
+        stream.restore_payload(b"&").await.unwrap();
+        assert_eq!(b"name=John".to_vec(), tee.bytes());
+        assert_eq!(b"&".to_vec(), stream.read_chunk().await.unwrap());
+        assert_eq!(false, tee.is_truncated());
+
+        assert_eq!(b"age=20".to_vec(), stream.read_chunk().await.unwrap());
+        assert_eq!(b"name=John&ag".to_vec(), tee.bytes());
+        assert_eq!(true, tee.is_truncated());
+    }
+}
@@ -0,0 +1,427 @@
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use tokio::sync::watch;
+
+use crate::core::headers::{HeaderValue, Headers};
+use crate::core::parser::chunked;
+use crate::core::server::{Server, ServerState};
+use crate::core::stream::{AbstractStream, TestStreamWrapper};
+
+///
+/// Sends requests through the router, middleware and constraints of the server without opening a
+/// socket, for testing views end to end. Each request is served on a new connection which is
+/// closed after the response.
+///
+/// Sessions are stored in memory unless other session manager is set on the server.
+///
+/// # Examples
+/// ```
+/// use racoon::core::path::Path;
+/// use racoon::core::request::Request;
+/// use racoon::core::response::{HttpResponse, Response};
+/// use racoon::core::response::status::ResponseStatus;
+/// use racoon::core::server::Server;
+/// use racoon::core::testing::TestClient;
+/// use racoon::view;
+///
+/// async fn hello(request: Request) -> Response {
+///     HttpResponse::ok().body("Hello World")
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut server = Server::bind("127.0.0.1:8080");
+///     server.urls(vec![Path::new("/", view!(hello))]);
+///
+///     let client = TestClient::new(&server);
+///     let response = client.get("/").send().await;
+///     assert_eq!(200, response.status_code);
+///     assert_eq!("Hello World", response.text());
+/// }
+/// ```
+///
+pub struct TestClient {
+    state: Arc<ServerState>,
+    buffer_size: usize,
+    /// Keeps the server from being seen as shutting down.
+    _shutdown: watch::Sender<bool>,
+}
+
+impl TestClient {
+    pub fn new(server: &Server) -> Self {
+        let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+        let (state, buffer_size) = server.test_client_state(shutdown_receiver);
+
+        Self {
+            state: Arc::new(state),
+            buffer_size,
+            _shutdown: shutdown_sender,
+        }
+    }
+
+    pub fn request(&self, method: &str, path: &str) -> TestRequest {
+        TestRequest {
+            state: self.state.clone(),
+            buffer_size: self.buffer_size,
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    pub fn get(&self, path: &str) -> TestRequest {
+        self.request("GET", path)
+    }
+
+    pub fn head(&self, path: &str) -> TestRequest {
+        self.request("HEAD", path)
+    }
+
+    pub fn post(&self, path: &str) -> TestRequest {
+        self.request("POST", path)
+    }
+
+    pub fn put(&self, path: &str) -> TestRequest {
+        self.request("PUT", path)
+    }
+
+    pub fn patch(&self, path: &str) -> TestRequest {
+        self.request("PATCH", path)
+    }
+
+    pub fn delete(&self, path: &str) -> TestRequest {
+        self.request("DELETE", path)
+    }
+
+    ///
+    /// Sends raw request bytes as they are, for example request captured with
+    /// `Server::capture_requests`.
+    ///
+    pub async fn replay<B: AsRef<[u8]>>(&self, raw_request: B) -> TestResponse {
+        let raw_request = raw_request.as_ref().to_vec();
+        let is_head = raw_request.starts_with(b"HEAD ");
+        send_bytes(self.state.clone(), self.buffer_size, raw_request, is_head).await
+    }
+}
+
+///
+/// Request built by `TestClient`. `Host`, `Content-Length` and `Connection: close` headers are
+/// added unless they are set explicitly.
+///
+pub struct TestRequest {
+    state: Arc<ServerState>,
+    buffer_size: usize,
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl TestRequest {
+    pub fn header<S: AsRef<str>>(mut self, name: &str, value: S) -> Self {
+        self.headers
+            .push((name.to_string(), value.as_ref().to_string()));
+        self
+    }
+
+    pub fn body<B: AsRef<[u8]>>(mut self, body: B) -> Self {
+        self.body = body.as_ref().to_vec();
+        self
+    }
+
+    ///
+    /// Sends JSON value as body with `application/json` content type.
+    ///
+    pub fn json(self, json: &serde_json::Value) -> Self {
+        self.header("Content-Type", "application/json")
+            .body(json.to_string())
+    }
+
+    ///
+    /// Sends fields as `application/x-www-form-urlencoded` body.
+    ///
+    pub fn form(self, fields: &[(&str, &str)]) -> Self {
+        let body = fields
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "{}={}",
+                    urlencoding::encode(name),
+                    urlencoding::encode(value)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("&");
+
+        self.header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)
+    }
+
+    pub async fn send(self) -> TestResponse {
+        let is_head = self.method.eq_ignore_ascii_case("HEAD");
+        let request_bytes = self.to_bytes();
+        send_bytes(self.state, self.buffer_size, request_bytes, is_head).await
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let is_set = |name: &str| {
+            self.headers
+                .iter()
+                .any(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+        };
+
+        let mut head = format!("{} {} HTTP/1.1\r\n", self.method, self.path);
+        if !is_set("Host") {
+            head.push_str("Host: localhost\r\n");
+        }
+
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+
+        if !self.body.is_empty() && !is_set("Content-Length") && !is_set("Transfer-Encoding") {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+
+        if !is_set("Connection") {
+            head.push_str("Connection: close\r\n");
+        }
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        bytes.extend(&self.body);
+        bytes
+    }
+}
+
+async fn send_bytes(
+    state: Arc<ServerState>,
+    buffer_size: usize,
+    request_bytes: Vec<u8>,
+    is_head: bool,
+) -> TestResponse {
+    let stream = TestStreamWrapper::new(request_bytes, buffer_size);
+    let written_data = stream.written_data();
+    Server::handle_stream(Box::new(stream), state).await;
+
+    let written_data = written_data.lock().await;
+    TestResponse::parse(&written_data, is_head).await
+}
+
+///
+/// Response received by `TestClient`. Chunked body is decoded.
+///
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status_code: u32,
+    pub status_text: String,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    async fn parse(response_bytes: &[u8], is_head: bool) -> Self {
+        let head_end = response_bytes
+            .windows(4)
+            .position(|bytes| bytes == b"\r\n\r\n")
+            .expect("Response header is not complete.");
+
+        let head = String::from_utf8_lossy(&response_bytes[..head_end]);
+        let mut lines = head.split("\r\n");
+
+        let status_line = lines.next().unwrap_or_default();
+        let mut status_parts = status_line.splitn(3, ' ').skip(1);
+        let status_code = status_parts
+            .next()
+            .and_then(|status_code| status_code.parse().ok())
+            .expect("Invalid response status line.");
+        let status_text = status_parts.next().unwrap_or_default().to_string();
+
+        let mut headers = Headers::new();
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':') {
+                headers.set_multiple(name.trim(), value.trim());
+            }
+        }
+
+        let mut body = response_bytes[head_end + 4..].to_vec();
+        if is_head {
+            body.clear();
+        } else if chunked::is_chunked(&headers) {
+            let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(body, 1024));
+            let (decoded_body, _) = chunked::read_chunked_body(&Arc::new(stream), usize::MAX)
+                .await
+                .expect("Invalid chunked response body.");
+            body = decoded_body;
+        }
+
+        Self {
+            status_code,
+            status_text,
+            headers,
+            body,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use serde_json::json;
+
+    use crate::core::headers::HeaderValue;
+    use crate::core::middleware::next;
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::streaming::StreamingResponse;
+    use crate::core::response::{HttpResponse, JsonResponse, Response};
+    use crate::core::server::Server;
+    use crate::core::shortcuts::SingleText;
+
+    use super::TestClient;
+
+    async fn echo(request: Request) -> Response {
+        let (form_data, _) = request.parse().await;
+        let name = form_data.value("name").cloned().unwrap_or_default();
+        JsonResponse::ok().body(json!({"method": request.method.to_string(), "name": name}))
+    }
+
+    async fn streamed(_: Request) -> Response {
+        StreamingResponse::ok().body(|mut writer| async move {
+            writer.write("Hello").await?;
+            writer.write(" World").await
+        })
+    }
+
+    async fn powered_by(request: Request, view: Option<View>) -> Response {
+        let mut response = next(request, view).await;
+        response.get_headers().set("X-Powered-By", "racoon");
+        response
+    }
+
+    #[tokio::test]
+    async fn test_client() {
+        let echo_view: View = |request| Box::pin(echo(request));
+        let streamed_view: View = |request| Box::pin(streamed(request));
+
+        let mut server = Server::bind("127.0.0.1:0");
+        server
+            .urls(vec![
+                Path::new("/echo", echo_view),
+                Path::new("/streamed", streamed_view),
+            ])
+            .wrap(|request, view| Box::pin(powered_by(request, view)));
+        let client = TestClient::new(&server);
+
+        let response = client
+            .post("/echo")
+            .form(&[("name", "John Doe")])
+            .send()
+            .await;
+        assert_eq!(200, response.status_code);
+        assert_eq!("OK", response.status_text);
+        assert_eq!(
+            Some("racoon".to_string()),
+            response.headers.value("X-Powered-By")
+        );
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(json!({"method": "POST", "name": "John Doe"}), body);
+
+        let response = client.get("/streamed").send().await;
+        assert_eq!("Hello World", response.text());
+
+        let response = client.head("/streamed").send().await;
+        assert_eq!(true, response.body.is_empty());
+
+        let response = client.get("/missing").send().await;
+        assert_eq!(404, response.status_code);
+    }
+
+    async fn not_used(_: Request) -> Response {
+        HttpResponse::ok().empty()
+    }
+
+    #[tokio::test]
+    async fn test_client_method_not_allowed() {
+        let view: View = |request| Box::pin(not_used(request));
+        let mut server = Server::bind("127.0.0.1:0");
+        server.urls(vec![Path::route("/").get(view)]);
+
+        let client = TestClient::new(&server);
+        let response = client.delete("/").send().await;
+        assert_eq!(405, response.status_code);
+    }
+
+    #[tokio::test]
+    async fn test_capture_requests() {
+        let dir = std::env::temp_dir().join(format!("racoon-capture-{}", uuid::Uuid::new_v4()));
+        let echo_view: View = |request| Box::pin(echo(request));
+
+        let mut server = Server::bind("127.0.0.1:0");
+        server
+            .urls(vec![Path::new("/echo", echo_view)])
+            .capture_requests(&dir);
+        let client = TestClient::new(&server);
+
+        let response = client
+            .post("/echo?page=1")
+            .header("X-Custom", "Value")
+            .form(&[("name", "John")])
+            .send()
+            .await;
+        assert_eq!(200, response.status_code);
+
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(1, entries.len());
+        let captured = std::fs::read(entries[0].as_ref().unwrap().path()).unwrap();
+
+        let captured_text = String::from_utf8_lossy(&captured);
+        assert_eq!(
+            true,
+            captured_text.starts_with("POST /echo?page=1 HTTP/1.1\r\n")
+        );
+        assert_eq!(true, captured_text.contains("X-Custom: Value\r\n"));
+        assert_eq!(true, captured_text.ends_with("\r\n\r\nname=John"));
+
+        let replayed = client.replay(&captured).await;
+        assert_eq!(response.body, replayed.body);
+        // Replayed request is captured too.
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Chunked body is captured decoded with Content-Length.
+        let response = client
+            .post("/echo")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Transfer-Encoding", "chunked")
+            .body("5\r\nname=\r\n4\r\nJohn\r\n0\r\n\r\n")
+            .send()
+            .await;
+        assert_eq!(200, response.status_code);
+
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(1, entries.len());
+        let captured = std::fs::read(entries[0].as_ref().unwrap().path()).unwrap();
+
+        let captured_text = String::from_utf8_lossy(&captured);
+        assert_eq!(false, captured_text.contains("Transfer-Encoding"));
+        assert_eq!(
+            true,
+            captured_text.ends_with("Content-Length: 9\r\n\r\nname=John")
+        );
+
+        let replayed = client.replay(&captured).await;
+        assert_eq!(response.body, replayed.body);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,198 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use sqlx::{Database, Pool, Transaction};
+use tokio::sync::{Mutex, OwnedMappedMutexGuard, OwnedMutexGuard};
+
+use crate::core::request::Request;
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::{HttpResponse, Response};
+use crate::racoon_error;
+
+///
+/// Locked transaction returned by `RequestTransaction::lock`.
+///
+pub type TransactionGuard<DB> =
+    OwnedMappedMutexGuard<Option<Transaction<'static, DB>>, Transaction<'static, DB>>;
+
+///
+/// Database transaction shared with the view handling the request. The transaction is committed
+/// or rolled back by `with_transaction` after the view returns.
+///
+pub struct RequestTransaction<DB: Database> {
+    transaction: Arc<Mutex<Option<Transaction<'static, DB>>>>,
+}
+
+impl<DB: Database> Clone for RequestTransaction<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            transaction: self.transaction.clone(),
+        }
+    }
+}
+
+impl<DB: Database> RequestTransaction<DB> {
+    ///
+    /// Locks the transaction for executing queries. Returns `None` if the transaction is already
+    /// committed or rolled back.
+    ///
+    pub async fn lock(&self) -> Option<TransactionGuard<DB>> {
+        let guard = self.transaction.clone().lock_owned().await;
+        OwnedMutexGuard::try_map(guard, |transaction| transaction.as_mut()).ok()
+    }
+
+    async fn take(&self) -> Option<Transaction<'static, DB>> {
+        self.transaction.lock().await.take()
+    }
+}
+
+///
+/// Runs the view inside a database transaction. The transaction is committed if the view returns
+/// `2xx` response, otherwise it is rolled back. If the transaction cannot be started or committed,
+/// `500 Internal Server Error` response is returned instead.
+///
+/// # Examples
+/// ```
+/// use racoon::core::request::Request;
+/// use racoon::core::response::{HttpResponse, Response};
+/// use racoon::core::response::status::ResponseStatus;
+/// use racoon::core::transaction::{with_transaction, RequestTransaction};
+///
+/// use sqlx::{Sqlite, SqlitePool};
+///
+/// async fn create_user(request: Request, transaction: RequestTransaction<Sqlite>) -> Response {
+///     if let Some(mut transaction) = transaction.lock().await {
+///         let result = sqlx::query("INSERT INTO users (name) VALUES ('John')")
+///             .execute(&mut **transaction)
+///             .await;
+///
+///         if result.is_err() {
+///             return HttpResponse::bad_request().body("Failed to create user.");
+///         }
+///     }
+///
+///     HttpResponse::created().body("Created")
+/// }
+///
+/// async fn users(request: Request) -> Response {
+///     let pool = request.context::<SqlitePool>().unwrap().clone();
+///     with_transaction(&pool, request, create_user).await
+/// }
+/// ```
+///
+pub async fn with_transaction<DB, F, Fut>(pool: &Pool<DB>, request: Request, view: F) -> Response
+where
+    DB: Database,
+    F: FnOnce(Request, RequestTransaction<DB>) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(error) => {
+            racoon_error!("Failed to begin transaction. Error: {}", error);
+            return HttpResponse::internal_server_error().body("Internal Server Error");
+        }
+    };
+
+    let request_transaction = RequestTransaction {
+        transaction: Arc::new(Mutex::new(Some(transaction))),
+    };
+
+    let response = view(request, request_transaction.clone()).await;
+    let (status_code, _) = response.status();
+
+    let transaction = match request_transaction.take().await {
+        Some(transaction) => transaction,
+        None => return response,
+    };
+
+    if (200..300).contains(&status_code) {
+        if let Err(error) = transaction.commit().await {
+            racoon_error!("Failed to commit transaction. Error: {}", error);
+            return HttpResponse::internal_server_error().body("Internal Server Error");
+        }
+    } else if let Err(error) = transaction.rollback().await {
+        racoon_error!("Failed to rollback transaction. Error: {}", error);
+    }
+
+    response
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::{Sqlite, SqlitePool};
+
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::status::ResponseStatus;
+    use crate::core::response::{HttpResponse, Response};
+    use crate::core::server::tests::{serve_raw, test_state};
+    use crate::core::shortcuts::SingleText;
+
+    use super::{with_transaction, RequestTransaction};
+
+    async fn create_user(request: Request, transaction: RequestTransaction<Sqlite>) -> Response {
+        let name = request
+            .query_params
+            .value("name")
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(mut transaction) = transaction.lock().await {
+            sqlx::query("INSERT INTO users (name) VALUES (?)")
+                .bind(&name)
+                .execute(&mut **transaction)
+                .await
+                .unwrap();
+        }
+
+        if name == "invalid" {
+            return HttpResponse::bad_request().body("Invalid name.");
+        }
+        HttpResponse::created().body("Created")
+    }
+
+    async fn users(request: Request) -> Response {
+        let pool = request.context::<SqlitePool>().unwrap().clone();
+        with_transaction(&pool, request, create_user).await
+    }
+
+    async fn user_count(pool: &SqlitePool) -> i64 {
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        row.0
+    }
+
+    #[tokio::test]
+    async fn test_with_transaction() {
+        // Single connection so the in-memory database is shared.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE users (name TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let view: View = |request| Box::pin(users(request));
+
+        let mut state = test_state(vec![Path::new("/users", view)]).await;
+        state.context = Arc::new(Box::pin(pool.clone()));
+        let response = serve_raw(state, b"POST /users?name=John HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 201"));
+        assert_eq!(1, user_count(&pool).await);
+
+        let mut state = test_state(vec![Path::new("/users", view)]).await;
+        state.context = Arc::new(Box::pin(pool.clone()));
+        let response = serve_raw(state, b"POST /users?name=invalid HTTP/1.1\r\n\r\n").await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 400"));
+        assert_eq!(1, user_count(&pool).await);
+    }
+}
@@ -25,6 +25,8 @@
 ///
 pub struct Frame {
     pub fin: u8,
+    /// Set on the first frame of messages compressed with `permessage-deflate` extension.
+    pub rsv1: u8,
     pub op_code: u8,
     pub payload: Vec<u8>,
 }
@@ -48,6 +50,7 @@ pub mod reader {
 
         let first_byte = buffer[0];
         let fin = fin_bit_to_u8(&first_byte);
+        let rsv1 = rsv1_bit_to_u8(&first_byte);
         let op_code = opcode_bit_to_u8(&first_byte);
 
         // 1 bit mask and 7 bit payload length
@@ -139,6 +142,7 @@ pub mod reader {
 
         Ok(Frame {
             fin,
+            rsv1,
             op_code,
             payload: buffer,
         })
@@ -151,6 +155,13 @@ pub mod reader {
         byte >> 7
     }
 
+    ///
+    /// Converts RSV1 bit value to unsigned number.
+    ///
+    fn rsv1_bit_to_u8(byte: &u8) -> u8 {
+        (byte >> 6) & 1
+    }
+
     ///
     /// Converts 4 bit opcode to unsigned number.
     ///
@@ -215,6 +226,7 @@ pub mod reader {
         async fn test_read_single_frame() {
             let frame = Frame {
                 fin: 1,
+                rsv1: 0,
                 op_code: 1,
                 payload: "Hello World".as_bytes().to_vec(),
             };
@@ -238,6 +250,7 @@ pub mod reader {
         async fn test_read_multiple_frames() {
             let frame = Frame {
                 fin: 1,
+                rsv1: 0,
                 op_code: 1,
                 payload: "Hello World".as_bytes().to_vec(),
             };
@@ -246,6 +259,7 @@ pub mod reader {
 
             let frame2 = Frame {
                 fin: 1,
+                rsv1: 0,
                 op_code: 9,
                 payload: "PING".as_bytes().to_vec(),
             };
@@ -292,8 +306,9 @@ pub mod builder {
 
         // Moves fin byte towards MSB
         let fin_byte = frame.fin << 7;
+        let rsv1_byte = (frame.rsv1 & 1) << 6;
         let opcode_byte = frame.op_code;
-        let first_byte = fin_byte | opcode_byte;
+        let first_byte = fin_byte | rsv1_byte | opcode_byte;
         buffer.push(first_byte);
 
         let actual_payload_length = frame.payload.len();
@@ -310,14 +325,16 @@ pub mod builder {
             buffer.push(second_byte);
         } else if actual_payload_length < (2_usize.pow(16)) {
             // Payload length is between 126 and 65535 bytes
-            buffer.push(126); // Indicates length is in next 2 bytes
+            // Indicates length is in next 2 bytes
+            buffer.push(if mask { 126 | 0b10000000 } else { 126 });
 
             // Convert the length to 2 bytes and push them
             let length_bytes: [u8; 2] = (actual_payload_length as u16).to_be_bytes();
             buffer.extend_from_slice(&length_bytes);
         } else {
             // Payload length is greater than or equal to 65536 bytes
-            buffer.push(127); // Indicates length is in next 8 bytes
+            // Indicates length is in next 8 bytes
+            buffer.push(if mask { 127 | 0b10000000 } else { 127 });
 
             // Convert the length to 8 bytes and push them
             let length_bytes: [u8; 8] = (actual_payload_length as u64).to_be_bytes();
@@ -361,6 +378,7 @@ pub mod builder {
         async fn test_frame_build_server() {
             let frame = Frame {
                 fin: 0,
+                rsv1: 0,
                 op_code: 1,
                 payload: "Hello World".as_bytes().to_vec(),
             };
@@ -381,3 +399,172 @@ pub mod builder {
         }
     }
 }
+
+///
+/// Payload compression of `permessage-deflate` extension.
+///
+/// More information: <https://datatracker.ietf.org/doc/html/rfc7692#section-7.2>
+///
+pub mod deflate {
+    use std::io::{ErrorKind, Write};
+
+    use flate2::write::DeflateEncoder;
+    use flate2::{Compression, Decompress, FlushDecompress, Status};
+
+    /// Empty stored block ending each compressed message. It is removed by the sender and appended
+    /// back by the receiver.
+    const MESSAGE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+    ///
+    /// Compresses message payload. Messages are compressed independently, so the server can
+    /// announce `server_no_context_takeover`.
+    ///
+    pub fn compress(payload: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(payload)?;
+
+        // Sync flush ends the output with the empty stored block.
+        encoder.flush()?;
+
+        let mut bytes = encoder.get_ref().clone();
+        if bytes.ends_with(&MESSAGE_TAIL) {
+            bytes.truncate(bytes.len() - MESSAGE_TAIL.len());
+        }
+        Ok(bytes)
+    }
+
+    ///
+    /// Decompresses messages received from the client. The sliding window is kept between the
+    /// messages, since clients may reference data of the previous messages.
+    ///
+    pub struct Inflater {
+        decompress: Decompress,
+    }
+
+    impl Inflater {
+        pub fn new() -> Self {
+            Self {
+                decompress: Decompress::new(false),
+            }
+        }
+
+        ///
+        /// Decompresses message payload. Returns error with `ErrorKind::InvalidData` if the payload
+        /// is not valid DEFLATE data and with `ErrorKind::Other` if the decompressed payload is
+        /// larger than `max_size` bytes.
+        ///
+        pub fn decompress(&mut self, payload: &[u8], max_size: usize) -> std::io::Result<Vec<u8>> {
+            let mut input = payload.to_vec();
+            input.extend_from_slice(&MESSAGE_TAIL);
+
+            let mut input = input.as_slice();
+            let mut output = Vec::with_capacity(payload.len() * 2);
+
+            loop {
+                if output.len() > max_size {
+                    return Err(std::io::Error::other(
+                        "Decompressed payload is larger than the maximum allowed size.",
+                    ));
+                }
+                output.reserve(16 * 1024);
+
+                let total_in = self.decompress.total_in();
+                let total_out = self.decompress.total_out();
+
+                let status = self
+                    .decompress
+                    .decompress_vec(input, &mut output, FlushDecompress::Sync)
+                    .map_err(|error| std::io::Error::new(ErrorKind::InvalidData, error))?;
+
+                let read_size = (self.decompress.total_in() - total_in) as usize;
+                let is_progressed = read_size > 0 || self.decompress.total_out() > total_out;
+                input = &input[read_size..];
+
+                if status == Status::StreamEnd {
+                    break;
+                }
+
+                // Output is not full, so all the input is decompressed.
+                let is_output_full = output.len() == output.capacity();
+                if input.is_empty() && !is_output_full {
+                    break;
+                }
+
+                if !is_progressed {
+                    return Err(std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        "Failed to decompress payload.",
+                    ));
+                }
+            }
+
+            if output.len() > max_size {
+                return Err(std::io::Error::other(
+                    "Decompressed payload is larger than the maximum allowed size.",
+                ));
+            }
+            Ok(output)
+        }
+    }
+
+    impl Default for Inflater {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    #[cfg(test)]
+    pub mod test {
+        use std::sync::Arc;
+
+        use crate::core::stream::{AbstractStream, TestStreamWrapper};
+        use crate::core::websocket::frame::reader::read_frame;
+        use crate::core::websocket::frame::{builder, Frame};
+
+        use super::{compress, Inflater};
+
+        #[tokio::test]
+        async fn test_compressed_frame() {
+            let message = "Hello World. ".repeat(100);
+            let frame = Frame {
+                fin: 1,
+                rsv1: 1,
+                op_code: 1,
+                payload: compress(message.as_bytes()).unwrap(),
+            };
+            assert_eq!(true, frame.payload.len() < message.len());
+
+            let frame_bytes = builder::build_opt(&frame, true);
+            let test_stream_wrapper = TestStreamWrapper::new(frame_bytes, 1024);
+            let stream: Arc<Box<dyn AbstractStream + 'static>> =
+                Arc::new(Box::new(test_stream_wrapper));
+
+            let decoded_frame = read_frame(stream, 5000).await.unwrap();
+            assert_eq!(1, decoded_frame.rsv1);
+            assert_eq!(1, decoded_frame.op_code);
+
+            let mut inflater = Inflater::new();
+            let payload = inflater.decompress(&decoded_frame.payload, 5000).unwrap();
+            assert_eq!(message.as_bytes().to_vec(), payload);
+
+            // Same inflater decompresses the next message.
+            let payload = compress(b"Bye").unwrap();
+            assert_eq!(
+                b"Bye".to_vec(),
+                inflater.decompress(&payload, 5000).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_decompress_limit() {
+            let payload = compress(&[b'a'; 10000]).unwrap();
+            let mut inflater = Inflater::new();
+            let error = inflater.decompress(&payload, 1000).unwrap_err();
+            assert_eq!(std::io::ErrorKind::Other, error.kind());
+
+            let mut inflater = Inflater::new();
+            let error = inflater.decompress(&[0xff, 0xff, 0xff], 1000).unwrap_err();
+            assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+        }
+    }
+}
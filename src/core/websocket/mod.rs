@@ -1,24 +1,51 @@
 pub mod frame;
+pub mod testing;
 
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::any::Any;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use base64::Engine;
 use serde_json::Value;
 use sha1::{Digest, Sha1};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::{Mutex as AsyncMutex, Notify};
 use uuid::Uuid;
 
 use crate::core::headers::{HeaderValue, Headers};
+use crate::core::method::Method;
+use crate::core::request::extensions::Extensions;
 use crate::core::request::Request;
 use crate::core::response::status::ResponseStatus;
 use crate::core::response::{response_to_bytes, AbstractResponse, HttpResponse};
-use crate::core::stream::Stream;
+use crate::core::stream::{PeerAddr, Stream};
+use crate::core::websocket::frame::deflate::{self, Inflater};
 use crate::core::websocket::frame::{reader, Frame};
 use crate::{racoon_debug, racoon_error};
 
 const DEFAULT_MAX_PAYLOAD_SIZE: u64 = 5 * 1024 * 1024; // 5 MiB
 
+/// Maximum time to wait for the client to echo the close frame.
+const CLOSE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Control frames must not have payload larger than 125 bytes.
+/// More information: <https://datatracker.ietf.org/doc/html/rfc6455#section-5.5>
+const MAX_CONTROL_PAYLOAD_SIZE: usize = 125;
+
+/// Close status code for protocol errors.
+const PROTOCOL_ERROR: u16 = 1002;
+
+/// Close status code for message data not consistent with the type of the message.
+const INVALID_PAYLOAD_DATA: u16 = 1007;
+
+/// Close status code for messages violating the policy of the server.
+const POLICY_VIOLATION: u16 = 1008;
+
+/// Close status code for messages too big to process.
+const MESSAGE_TOO_BIG: u16 = 1009;
+
 pub enum Message {
     Continue(Vec<u8>),
     Text(String),
@@ -29,24 +56,155 @@ pub enum Message {
     Others(Vec<u8>),
 }
 
+///
+/// Options for accepting WebSocket connection.
+///
+/// # Examples
+/// ```
+/// use racoon::core::request::Request;
+/// use racoon::core::response::Response;
+/// use racoon::core::websocket::{WebSocket, WebSocketOptions};
+///
+/// async fn chat(request: Request) -> Response {
+///     let options = WebSocketOptions {
+///         max_messages: Some(1000),
+///         max_total_bytes: Some(1024 * 1024),
+///         ..WebSocketOptions::default()
+///     };
+///
+///     let (websocket, connected) = WebSocket::from_options(&request, options).await;
+///     if !connected {
+///         return websocket.bad_request().await;
+///     }
+///
+///     while let Some(message) = websocket.message().await {
+///         // Handle message
+///     }
+///     websocket.exit()
+/// }
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct WebSocketOptions {
+    /// Sends ping frame every 10 seconds if enabled.
+    pub periodic_ping: bool,
+    /// Maximum number of data messages the client can send. Connection is closed with status
+    /// code 1008 when exceeded.
+    pub max_messages: Option<usize>,
+    /// Maximum number of payload bytes the client can send in data messages. Connection is
+    /// closed with status code 1008 when exceeded.
+    pub max_total_bytes: Option<usize>,
+    /// Subprotocols supported by the server. The first one requested by the client in
+    /// `Sec-WebSocket-Protocol` header is selected.
+    pub protocols: Vec<String>,
+    /// Accepts `permessage-deflate` extension if offered by the client, so that data messages
+    /// are compressed. Disabled by default.
+    pub permessage_deflate: bool,
+}
+
+impl Default for WebSocketOptions {
+    fn default() -> Self {
+        Self {
+            periodic_ping: true,
+            max_messages: None,
+            max_total_bytes: None,
+            protocols: vec![],
+            permessage_deflate: false,
+        }
+    }
+}
+
+///
+/// Counter of the open WebSocket connections limited by `Server::max_websocket_connections`.
+///
+pub(crate) struct WebSocketConnections {
+    max: usize,
+    open: AtomicUsize,
+}
+
+impl WebSocketConnections {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            max,
+            open: AtomicUsize::new(0),
+        }
+    }
+
+    ///
+    /// Reserves a connection. Returns `None` if the maximum connections are already open.
+    ///
+    fn acquire(self: &Arc<Self>) -> Option<WebSocketConnectionSlot> {
+        self.open
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |open| {
+                (open < self.max).then_some(open + 1)
+            })
+            .ok()?;
+        Some(WebSocketConnectionSlot(self.clone()))
+    }
+}
+
+/// Reserved WebSocket connection, released when dropped.
+struct WebSocketConnectionSlot(Arc<WebSocketConnections>);
+
+impl Drop for WebSocketConnectionSlot {
+    fn drop(&mut self) {
+        self.0.open.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
 pub struct WebSocket {
     pub uid: String,
+    /// Subprotocol negotiated in the handshake. `None` if the client did not request any of the
+    /// supported subprotocols.
+    pub protocol: Option<String>,
     stream: Arc<Stream>,
     request_validated: bool,
     receive_next: Arc<AtomicBool>,
+    close_sent: Arc<AtomicBool>,
+    close_received: Arc<AtomicBool>,
+    /// Notified when the close frame is received or reading fails, so `close` waiting for the
+    /// client to echo the close frame can return.
+    close_notify: Arc<Notify>,
+    /// Held while frames are read, so the frames are never read concurrently by the clones.
+    reading: Arc<AsyncMutex<()>>,
+    peer_addr: Option<PeerAddr>,
+    /// Application data attached to the connection with `set_context`.
+    context: Arc<Mutex<Extensions>>,
+    options: WebSocketOptions,
+    messages_received: Arc<AtomicUsize>,
+    bytes_received: Arc<AtomicUsize>,
     headers: Headers,
     body: Vec<u8>,
+    /// Decompresses received messages. `None` if `permessage-deflate` is not negotiated.
+    inflater: Option<Arc<Mutex<Inflater>>>,
+    /// Counted against the server limit until the connection is closed or all clones are dropped.
+    connection_slot: Arc<Mutex<Option<WebSocketConnectionSlot>>>,
+    /// Upgrade failed because the server limit of WebSocket connections is reached.
+    at_capacity: bool,
 }
 
 impl Clone for WebSocket {
     fn clone(&self) -> Self {
         Self {
             uid: self.uid.clone(),
+            protocol: self.protocol.clone(),
             stream: self.stream.clone(),
             request_validated: self.request_validated.clone(),
             receive_next: self.receive_next.clone(),
+            close_sent: self.close_sent.clone(),
+            close_received: self.close_received.clone(),
+            close_notify: self.close_notify.clone(),
+            reading: self.reading.clone(),
+            peer_addr: self.peer_addr.clone(),
+            context: self.context.clone(),
+            options: self.options.clone(),
+            messages_received: self.messages_received.clone(),
+            bytes_received: self.bytes_received.clone(),
             headers: self.headers.clone(),
             body: self.body.clone(),
+            inflater: self.inflater.clone(),
+            connection_slot: self.connection_slot.clone(),
+            at_capacity: self.at_capacity,
         }
     }
 }
@@ -71,6 +229,10 @@ impl AbstractResponse for WebSocket {
     fn should_close(&mut self) -> bool {
         true
     }
+
+    fn was_custom_served(&self) -> bool {
+        true
+    }
 }
 
 impl WebSocket {
@@ -79,23 +241,92 @@ impl WebSocket {
     }
 
     pub async fn from_opt(request: &Request, periodic_ping: bool) -> (Self, bool) {
-        let instance = match WebSocket::validate(request).await {
+        let options = WebSocketOptions {
+            periodic_ping,
+            ..WebSocketOptions::default()
+        };
+        Self::from_options(request, options).await
+    }
+
+    ///
+    /// Accepts connection with the subprotocols supported by the server. The negotiated
+    /// subprotocol is available in `protocol` field.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::Response;
+    /// use racoon::core::websocket::WebSocket;
+    ///
+    /// async fn graphql(request: Request) -> Response {
+    ///     let protocols = ["graphql-transport-ws", "graphql-ws"];
+    ///     let (websocket, connected) = WebSocket::from_with_protocols(&request, &protocols).await;
+    ///     if !connected {
+    ///         return websocket.bad_request().await;
+    ///     }
+    ///
+    ///     match websocket.protocol.as_deref() {
+    ///         Some("graphql-transport-ws") => { /* Handle messages */ }
+    ///         _ => websocket.close().await,
+    ///     }
+    ///     websocket.exit()
+    /// }
+    /// ```
+    ///
+    pub async fn from_with_protocols<S: AsRef<str>>(
+        request: &Request,
+        protocols: &[S],
+    ) -> (Self, bool) {
+        let options = WebSocketOptions {
+            protocols: protocols
+                .iter()
+                .map(|protocol| protocol.as_ref().to_string())
+                .collect(),
+            ..WebSocketOptions::default()
+        };
+        Self::from_options(request, options).await
+    }
+
+    ///
+    /// Accepts WebSocket connection with the options. Upgrade fails if the request is not a valid
+    /// WebSocket handshake, or if `Server::max_websocket_connections` connections are already
+    /// open. In the latter case, `bad_request` responds with `503 Service Unavailable`.
+    ///
+    pub async fn from_options(request: &Request, options: WebSocketOptions) -> (Self, bool) {
+        let periodic_ping = options.periodic_ping;
+        let peer_addr = request.remote_addr().await;
+
+        let mut connection_slot = None;
+        if let Some(websocket_connections) = request.websocket_connections() {
+            connection_slot = websocket_connections.acquire();
+
+            if connection_slot.is_none() {
+                racoon_error!("WS Error: Maximum WebSocket connections reached.");
+
+                let mut failed = Self::new(request.stream.clone(), false, peer_addr, options);
+                failed.at_capacity = true;
+                failed.receive_next.store(true, Ordering::Relaxed);
+                return (failed, false);
+            }
+        }
+
+        let instance = match WebSocket::validate(request, peer_addr.clone(), options.clone()).await
+        {
             Ok(instance) => instance,
             Err(error) => {
                 racoon_error!("WS Error: {}", error);
 
-                let failed = Self {
-                    uid: Uuid::new_v4().to_string(),
-                    stream: request.stream.clone(),
-                    request_validated: false,
-                    receive_next: Arc::new(AtomicBool::new(true)),
-                    headers: Headers::new(),
-                    body: Vec::new(),
-                };
+                let failed = Self::new(request.stream.clone(), false, peer_addr, options);
+                failed.receive_next.store(true, Ordering::Relaxed);
                 return (failed, false);
             }
         };
 
+        *instance
+            .connection_slot
+            .lock()
+            .unwrap_or_else(|error| error.into_inner()) = connection_slot;
+
         if periodic_ping {
             instance.ping_with_interval(Duration::from_secs(10)).await;
         }
@@ -103,8 +334,97 @@ impl WebSocket {
         (instance, true)
     }
 
-    async fn validate(request: &Request) -> Result<Self, String> {
-        if request.method != "GET" {
+    fn new(
+        stream: Arc<Stream>,
+        request_validated: bool,
+        peer_addr: Option<PeerAddr>,
+        options: WebSocketOptions,
+    ) -> Self {
+        Self {
+            uid: Uuid::new_v4().to_string(),
+            protocol: None,
+            stream,
+            request_validated,
+            receive_next: Arc::new(AtomicBool::new(false)),
+            close_sent: Arc::new(AtomicBool::new(false)),
+            close_received: Arc::new(AtomicBool::new(false)),
+            close_notify: Arc::new(Notify::new()),
+            reading: Arc::new(AsyncMutex::new(())),
+            peer_addr,
+            context: Arc::new(Mutex::new(Extensions::new())),
+            options,
+            messages_received: Arc::new(AtomicUsize::new(0)),
+            bytes_received: Arc::new(AtomicUsize::new(0)),
+            headers: Headers::new(),
+            body: Vec::new(),
+            inflater: None,
+            connection_slot: Arc::new(Mutex::new(None)),
+            at_capacity: false,
+        }
+    }
+
+    ///
+    /// Returns address of the connected client. The address is copied from the request when the
+    /// WebSocket is created, so it remains available for the whole connection.
+    ///
+    pub fn peer_addr(&self) -> Option<PeerAddr> {
+        self.peer_addr.clone()
+    }
+
+    ///
+    /// Attaches value to the connection. Only one value is stored per type, so setting value of
+    /// the same type replaces the previous one. The value is shared with the clones of this
+    /// WebSocket.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::Response;
+    /// use racoon::core::websocket::WebSocket;
+    ///
+    /// #[derive(Clone)]
+    /// struct Room(String);
+    ///
+    /// async fn chat(request: Request) -> Response {
+    ///     let (websocket, connected) = WebSocket::from(&request).await;
+    ///     if !connected {
+    ///         return websocket.bad_request().await;
+    ///     }
+    ///
+    ///     websocket.set_context(Room("general".to_string()));
+    ///
+    ///     if let Some(room) = websocket.context::<Room>() {
+    ///         let _ = websocket.send_text(format!("Joined {}", room.0)).await;
+    ///     }
+    ///     websocket.exit()
+    /// }
+    /// ```
+    ///
+    pub fn set_context<T: Any + Send + Sync>(&self, value: T) {
+        let mut context = self
+            .context
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        context.insert(value);
+    }
+
+    ///
+    /// Returns copy of the value of type `T` attached with `set_context`.
+    ///
+    pub fn context<T: Any + Send + Sync + Clone>(&self) -> Option<T> {
+        let context = self
+            .context
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        context.get::<T>().cloned()
+    }
+
+    async fn validate(
+        request: &Request,
+        peer_addr: Option<PeerAddr>,
+        options: WebSocketOptions,
+    ) -> Result<Self, String> {
+        if request.method != Method::Get {
             return Err("Invalid request method.".to_owned());
         }
 
@@ -139,22 +459,38 @@ impl WebSocket {
             return Err("Upgrade header is not set to websocket.".to_string());
         }
 
-        let instance = Self {
-            uid: Uuid::new_v4().to_string(),
-            stream: request.stream.clone(),
-            request_validated: true,
-            receive_next: Arc::new(AtomicBool::new(false)),
-            headers: Headers::new(),
-            body: Vec::new(),
-        };
+        let requested_protocols = request.headers.value("Sec-WebSocket-Protocol");
+        let protocol = select_protocol(requested_protocols.as_deref(), &options.protocols);
 
-        match Self::handshake(request.stream.clone(), &sec_websocket_key).await {
+        let mut extension = None;
+        if options.permessage_deflate {
+            let offers = request.headers.value("Sec-WebSocket-Extensions");
+            extension = negotiate_permessage_deflate(offers.as_deref());
+        }
+
+        let mut instance = Self::new(request.stream.clone(), true, peer_addr, options);
+        instance.protocol = protocol;
+        if extension.is_some() {
+            instance.inflater = Some(Arc::new(Mutex::new(Inflater::new())));
+        }
+
+        let handshake = Self::handshake(
+            request.stream.clone(),
+            &sec_websocket_key,
+            instance.protocol.as_deref(),
+            extension.as_deref(),
+        );
+
+        match handshake.await {
             Ok(()) => {}
             Err(error) => {
                 return Err(format!("Failed to handshake. {}", error));
             }
         };
 
+        // Clients may send messages only occasionally after the handshake.
+        instance.stream.stop_read_rate();
+
         instance.receive_next.store(true, Ordering::Relaxed);
         Ok(instance)
     }
@@ -162,7 +498,12 @@ impl WebSocket {
     ///
     /// More information: <https://datatracker.ietf.org/doc/html/rfc6455#section-1.3>
     ///
-    async fn handshake(stream: Arc<Stream>, sec_websocket_key: &str) -> std::io::Result<()> {
+    async fn handshake(
+        stream: Arc<Stream>,
+        sec_websocket_key: &str,
+        protocol: Option<&str>,
+        extension: Option<&str>,
+    ) -> std::io::Result<()> {
         let base64_hash = Self::handshake_key_base64(sec_websocket_key);
 
         let mut http_response = HttpResponse::switching_protocols();
@@ -171,6 +512,14 @@ impl WebSocket {
         headers.set("Upgrade", "websocket");
         headers.set("Sec-WebSocket-Accept", base64_hash.as_bytes());
 
+        if let Some(protocol) = protocol {
+            headers.set("Sec-WebSocket-Protocol", protocol);
+        }
+
+        if let Some(extension) = extension {
+            headers.set("Sec-WebSocket-Extensions", extension);
+        }
+
         let mut response: Box<dyn AbstractResponse> = http_response.empty();
         let response_bytes = response_to_bytes(&mut response);
         Ok(stream.write_chunk(&response_bytes).await?)
@@ -200,13 +549,10 @@ impl WebSocket {
             let mut interval = tokio::time::interval(duration);
 
             // More information: https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.2
-            let frame = Frame {
-                fin: 1,
-                op_code: 9,
-                payload: vec![],
+            let bytes = match control_frame_bytes(9, vec![]) {
+                Ok(bytes) => bytes,
+                Err(_) => return,
             };
-
-            let bytes = frame::builder::build(&frame);
             interval.tick().await;
 
             loop {
@@ -226,17 +572,16 @@ impl WebSocket {
         });
     }
 
-    async fn send_pong(&self) {
+    async fn send_pong(&self, payload: &[u8]) {
         racoon_debug!("Sending pong frame.");
 
-        // More information: https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.2
-        let frame = Frame {
-            fin: 1,
-            op_code: 10,
-            payload: vec![],
+        // Pong frame must contain the application data of the ping frame.
+        // More information: https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.3
+        let bytes = match control_frame_bytes(10, payload.to_vec()) {
+            Ok(bytes) => bytes,
+            Err(_) => return,
         };
 
-        let bytes = frame::builder::build(&frame);
         match self.stream.write_chunk(&bytes).await {
             Ok(()) => {}
             Err(error) => {
@@ -252,20 +597,126 @@ impl WebSocket {
             return None;
         };
 
+        let _reading = self.reading.lock().await;
+
+        // Connection may be closed while waiting for the other reader.
+        if !self.receive_next.load(Ordering::Relaxed) {
+            return None;
+        }
+
         let mut response: Vec<u8> = vec![];
 
+        // Opcode of the first frame of the data message being received.
+        let mut message_op_code: Option<u8> = None;
+        let mut is_compressed = false;
+
         loop {
             let frame = match reader::read_frame(self.stream.clone(), max_payload_size).await {
                 Ok(frame) => frame,
                 Err(error) => {
                     // Stops waiting for new messages
                     self.receive_next.store(false, Ordering::Relaxed);
+                    self.close_notify.notify_waiters();
                     return Some(Message::Close(1000, error.to_string()));
                 }
             };
 
+            // Messages received after sending close frame are discarded until the client echoes
+            // the close frame.
+            if self.close_sent.load(Ordering::Relaxed) && frame.op_code != 8 {
+                continue;
+            }
+
+            // Control frames must not be fragmented and must have short payload.
+            // More information: https://datatracker.ietf.org/doc/html/rfc6455#section-5.5
+            if frame.op_code >= 8
+                && (frame.fin != 1 || frame.payload.len() > MAX_CONTROL_PAYLOAD_SIZE)
+            {
+                racoon_debug!("Invalid control frame with opcode {}.", frame.op_code);
+                self.receive_next.store(false, Ordering::Relaxed);
+                let _ = self.send_close(PROTOCOL_ERROR, "").await;
+                return Some(Message::Close(
+                    PROTOCOL_ERROR,
+                    "Control frame is fragmented or too large.".to_string(),
+                ));
+            }
+
+            // RSV1 bit marks compressed message, so it is only valid on the first frame of data
+            // messages when `permessage-deflate` is negotiated.
+            // More information: https://datatracker.ietf.org/doc/html/rfc7692#section-6.1
+            if frame.rsv1 == 1
+                && (self.inflater.is_none() || frame.op_code == 0 || frame.op_code >= 8)
+            {
+                racoon_debug!(
+                    "Unexpected RSV1 bit in frame with opcode {}.",
+                    frame.op_code
+                );
+                self.receive_next.store(false, Ordering::Relaxed);
+                let _ = self.send_close(PROTOCOL_ERROR, "").await;
+                return Some(Message::Close(
+                    PROTOCOL_ERROR,
+                    "Unexpected RSV1 bit.".to_string(),
+                ));
+            }
+
+            // Control frames may be received between fragments of a data message. They are
+            // handled without being added to the message. Ping and pong frames are only returned
+            // if no message is being received.
+            if frame.op_code >= 8 {
+                let is_fragmenting = message_op_code.is_some();
+
+                if frame.op_code == 8 {
+                    // Connection close frame
+                    return Some(self.handle_close_frame(&frame.payload).await);
+                } else if frame.op_code == 9 {
+                    // Ping frame
+                    self.send_pong(&frame.payload).await;
+                    if !is_fragmenting {
+                        return Some(Message::Ping());
+                    }
+                } else if frame.op_code == 10 {
+                    // Pong frame
+                    if !is_fragmenting {
+                        return Some(Message::Pong());
+                    }
+                } else if !is_fragmenting {
+                    return Some(Message::Others(frame.payload));
+                }
+                continue;
+            }
+
+            if frame.op_code != 0 {
+                if message_op_code.is_some() {
+                    // New data message must not start before the current one is completed.
+                    self.receive_next.store(false, Ordering::Relaxed);
+                    let _ = self.send_close(PROTOCOL_ERROR, "").await;
+                    return Some(Message::Close(
+                        PROTOCOL_ERROR,
+                        "Expected continuation frame.".to_string(),
+                    ));
+                }
+                message_op_code = Some(frame.op_code);
+                is_compressed = frame.rsv1 == 1;
+            } else if message_op_code.is_none() {
+                // Continuation frame without the starting frame.
+                message_op_code = Some(0);
+            }
+
             response.extend(&frame.payload);
 
+            let bytes_received = self
+                .bytes_received
+                .fetch_add(frame.payload.len(), Ordering::Relaxed)
+                + frame.payload.len();
+            if let Some(max_total_bytes) = self.options.max_total_bytes {
+                if bytes_received > max_total_bytes {
+                    return Some(
+                        self.close_policy_violation("Max total bytes exceeded.")
+                            .await,
+                    );
+                }
+            }
+
             // Checks response size
             if response.len() > DEFAULT_MAX_PAYLOAD_SIZE as usize {
                 return Some(Message::Close(0, "Max payload size exceed.".to_string()));
@@ -273,35 +724,114 @@ impl WebSocket {
 
             // If fin is 1, the complete message is received.
             if frame.fin == 1 {
-                return if frame.op_code == 0 {
-                    Some(Message::Continue(frame.payload))
-                } else if frame.op_code == 1 {
-                    // Text Frame
-                    let payload_text = String::from_utf8_lossy(frame.payload.as_slice());
-                    Some(Message::Text(payload_text.to_string()))
-                } else if frame.op_code == 2 {
-                    // Binary frame
-                    Some(Message::Binary(frame.payload))
-                } else if frame.op_code == 8 {
-                    // Connection close frame
-                    self.receive_next.store(false, Ordering::Relaxed);
-                    let close_code = self.close_code_from_payload(&frame.payload);
-                    let close_message = self.close_message_from_payload(&frame.payload);
-                    Some(Message::Close(close_code, close_message))
-                } else if frame.op_code == 9 {
-                    // Ping frame
-                    self.send_pong().await;
-                    Some(Message::Ping())
-                } else if frame.op_code == 10 {
-                    // Pong frame
-                    Some(Message::Pong())
+                let messages_received = self.messages_received.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(max_messages) = self.options.max_messages {
+                    if messages_received > max_messages {
+                        return Some(self.close_policy_violation("Max messages exceeded.").await);
+                    }
+                }
+
+                if is_compressed {
+                    response = match self.decompress(&response, max_payload_size).await {
+                        Ok(payload) => payload,
+                        Err(message) => return Some(message),
+                    };
+                }
+
+                return match message_op_code {
+                    Some(0) => Some(Message::Continue(response)),
+                    Some(1) => {
+                        // Text Frame
+                        let payload_text = String::from_utf8_lossy(response.as_slice());
+                        Some(Message::Text(payload_text.to_string()))
+                    }
+                    Some(2) => {
+                        // Binary frame
+                        Some(Message::Binary(response))
+                    }
+                    _ => Some(Message::Others(response)),
+                };
+            }
+        }
+    }
+
+    ///
+    /// Decompresses message compressed with `permessage-deflate`. Returns close message if the
+    /// payload is invalid or too large.
+    ///
+    async fn decompress(&self, payload: &[u8], max_payload_size: u64) -> Result<Vec<u8>, Message> {
+        // Decompressed size is limited too, so small frames cannot inflate to huge messages.
+        let max_size = usize::try_from(max_payload_size).unwrap_or(usize::MAX);
+        let result = match &self.inflater {
+            Some(inflater) => {
+                let mut inflater = inflater.lock().unwrap_or_else(|error| error.into_inner());
+                inflater.decompress(payload, max_size)
+            }
+            None => return Ok(payload.to_vec()),
+        };
+
+        match result {
+            Ok(payload) => Ok(payload),
+            Err(error) => {
+                racoon_debug!("Failed to decompress message. Error: {}", error);
+                let close_code = if error.kind() == ErrorKind::InvalidData {
+                    INVALID_PAYLOAD_DATA
                 } else {
-                    Some(Message::Others(frame.payload))
+                    MESSAGE_TOO_BIG
                 };
+
+                self.receive_next.store(false, Ordering::Relaxed);
+                let _ = self.send_close(close_code, "").await;
+                Err(Message::Close(close_code, error.to_string()))
             }
         }
     }
 
+    ///
+    /// Closes connection because the client exceeded limits set in `WebSocketOptions`.
+    ///
+    async fn close_policy_violation(&self, reason: &str) -> Message {
+        racoon_debug!("WebSocket policy violation. {}", reason);
+        self.receive_next.store(false, Ordering::Relaxed);
+        let _ = self.send_close(POLICY_VIOLATION, "").await;
+        Message::Close(POLICY_VIOLATION, reason.to_string())
+    }
+
+    ///
+    /// Handles close frame received from the client and echoes it to complete the closing
+    /// handshake.
+    ///
+    async fn handle_close_frame(&self, payload: &[u8]) -> Message {
+        self.receive_next.store(false, Ordering::Relaxed);
+        self.close_received.store(true, Ordering::Relaxed);
+        self.close_notify.notify_waiters();
+
+        let (close_code, close_message) = match close_payload(payload) {
+            Ok(value) => value,
+            Err(error) => {
+                racoon_debug!("Invalid close frame. {}", error);
+                let _ = self.send_close(PROTOCOL_ERROR, "").await;
+                return Message::Close(PROTOCOL_ERROR, error);
+            }
+        };
+
+        // Reserved codes such as 1005 and 1006 must not be sent by the client.
+        // More information: https://datatracker.ietf.org/doc/html/rfc6455#section-7.4.1
+        if close_code != 0 && !is_valid_close_code(close_code) {
+            racoon_debug!("Invalid close status code {}.", close_code);
+            let _ = self.send_close(PROTOCOL_ERROR, "").await;
+            return Message::Close(
+                PROTOCOL_ERROR,
+                format!("Invalid close status code {}.", close_code),
+            );
+        }
+
+        // More information: https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.1
+        let echo_code = if close_code == 0 { 1000 } else { close_code };
+        let _ = self.send_close(echo_code, "").await;
+        Message::Close(close_code, close_message)
+    }
+
     pub async fn message(&self) -> Option<Message> {
         self.receive_message_with_limit(DEFAULT_MAX_PAYLOAD_SIZE)
             .await
@@ -310,72 +840,961 @@ impl WebSocket {
     pub async fn send_text<S: AsRef<str>>(&self, message: S) -> std::io::Result<()> {
         let message = message.as_ref();
 
-        let frame = Frame {
-            fin: 1,
-            op_code: 1,
-            payload: message.as_bytes().to_vec(),
-        };
-
-        let bytes = frame::builder::build(&frame);
+        let bytes = self.data_frame_bytes(1, message.as_bytes())?;
         self.stream.write_chunk(&bytes).await?;
         Ok(())
     }
 
     pub async fn send_bytes<B: AsRef<[u8]>>(&self, bytes: B) -> std::io::Result<()> {
-        let payload = Vec::from(bytes.as_ref());
+        let bytes = self.data_frame_bytes(2, bytes.as_ref())?;
+        self.stream.write_chunk(&bytes).await?;
 
-        let frame = Frame {
+        Ok(())
+    }
+
+    ///
+    /// Builds unfragmented data frame. The payload is compressed if `permessage-deflate` is
+    /// negotiated and compression makes it smaller.
+    ///
+    fn data_frame_bytes(&self, op_code: u8, payload: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut frame = Frame {
             fin: 1,
-            op_code: 2,
-            payload,
+            rsv1: 0,
+            op_code,
+            payload: payload.to_vec(),
         };
 
-        let bytes = frame::builder::build(&frame);
-        self.stream.write_chunk(&bytes).await?;
+        if self.inflater.is_some() {
+            let compressed_payload = deflate::compress(payload)?;
+            if compressed_payload.len() < payload.len() {
+                frame.rsv1 = 1;
+                frame.payload = compressed_payload;
+            }
+        }
+        Ok(frame::builder::build(&frame))
+    }
 
-        Ok(())
+    ///
+    /// Sends binary message read from the reader as multiple frames of at most `fragment_size`
+    /// bytes, so large payloads are streamed without loading them completely in memory.
+    ///
+    /// The first frame has binary opcode, following frames are continuation frames and only the
+    /// last frame has FIN bit set.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::Response;
+    /// use racoon::core::websocket::WebSocket;
+    ///
+    /// async fn download(request: Request) -> Response {
+    ///     let (websocket, connected) = WebSocket::from(&request).await;
+    ///     if !connected {
+    ///         return websocket.bad_request().await;
+    ///     }
+    ///
+    ///     if let Ok(file) = tokio::fs::File::open("video.mp4").await {
+    ///         let _ = websocket.send_bytes_fragmented(file, 64 * 1024).await;
+    ///     }
+    ///     websocket.exit()
+    /// }
+    /// ```
+    ///
+    pub async fn send_bytes_fragmented<R: AsyncRead + Unpin>(
+        &self,
+        mut reader: R,
+        fragment_size: usize,
+    ) -> std::io::Result<()> {
+        let fragment_size = fragment_size.max(1);
+
+        // Next fragment is read before sending the current one to know which one is the last.
+        let mut current = read_fragment(&mut reader, fragment_size).await?;
+        let mut op_code = 2;
+
+        loop {
+            let next = read_fragment(&mut reader, fragment_size).await?;
+            let is_last = next.is_empty();
+
+            let frame = Frame {
+                fin: if is_last { 1 } else { 0 },
+                rsv1: 0,
+                op_code,
+                payload: current,
+            };
+
+            let bytes = frame::builder::build(&frame);
+            self.stream.write_chunk(&bytes).await?;
+
+            if is_last {
+                return Ok(());
+            }
+
+            // Remaining frames are continuation frames.
+            op_code = 0;
+            current = next;
+        }
     }
 
     pub async fn send_json(&self, json: &Value) -> std::io::Result<()> {
         self.send_text(json.to_string().as_str()).await
     }
 
+    ///
+    /// Responds to the failed upgrade with `400 Bad Request`, or `503 Service Unavailable` if the
+    /// server limit of WebSocket connections is reached.
+    ///
     pub async fn bad_request(self) -> Box<Self> {
-        let mut response: Box<dyn AbstractResponse> =
-            HttpResponse::bad_request().body("Bad Request");
+        let mut response: Box<dyn AbstractResponse> = if self.at_capacity {
+            HttpResponse::service_unavailable().body("Too many WebSocket connections.")
+        } else {
+            HttpResponse::bad_request().body("Bad Request")
+        };
         let response_bytes = response_to_bytes(&mut response);
         let _ = self.stream.write_chunk(&response_bytes).await;
         Box::new(self)
     }
 
+    ///
+    /// Closes the connection with the closing handshake. Sends close frame and waits up to 5
+    /// seconds for the client to echo it before shutting down the stream, so clients do not see
+    /// abnormal closure (1006). Messages received meanwhile are discarded.
+    ///
+    /// If `message` is being awaited in another task, the echoed close frame is received there and
+    /// `message` returns `Message::Close`.
+    ///
     pub async fn close(&self) {
+        let _ = self.close_with_reason(1000, "").await;
+    }
+
+    ///
+    /// Same as `close` but sends the given status code and reason to the client, e.g. `4001` with
+    /// `"Session expired"`.
+    ///
+    /// Returns error without closing the connection if the status code is not allowed in close
+    /// frames or the reason is longer than 123 bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::Response;
+    /// use racoon::core::websocket::WebSocket;
+    ///
+    /// async fn chat(request: Request) -> Response {
+    ///     let (websocket, connected) = WebSocket::from(&request).await;
+    ///     if !connected {
+    ///         return websocket.bad_request().await;
+    ///     }
+    ///
+    ///     let _ = websocket.close_with_reason(4001, "Session expired").await;
+    ///     websocket.exit()
+    /// }
+    /// ```
+    ///
+    /// More information: <https://datatracker.ietf.org/doc/html/rfc6455#section-7.4>
+    ///
+    pub async fn close_with_reason(&self, code: u16, reason: &str) -> std::io::Result<()> {
+        if !is_valid_close_code(code) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Status code {} is not allowed in close frame.", code),
+            ));
+        }
+
+        // Two bytes of the payload are used by the status code.
+        if reason.len() > MAX_CONTROL_PAYLOAD_SIZE - 2 {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Close reason must not exceed {} bytes.",
+                    MAX_CONTROL_PAYLOAD_SIZE - 2
+                ),
+            ));
+        }
+
+        self.receive_next.store(false, Ordering::Relaxed);
+
+        let result = self.send_close(code, reason).await;
+        if result.is_ok() {
+            let _ = tokio::time::timeout(CLOSE_HANDSHAKE_TIMEOUT, self.wait_close_frame()).await;
+        }
+
         let _ = self.stream.shutdown().await;
+
+        // Closed connection no longer counts against the server limit.
+        self.connection_slot
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .take();
+        result
+    }
+
+    ///
+    /// Sends close frame with status code and reason. Close frame is sent only once.
+    ///
+    async fn send_close(&self, code: u16, reason: &str) -> std::io::Result<()> {
+        if self.close_sent.swap(true, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend(reason.as_bytes());
+
+        let bytes = control_frame_bytes(8, payload)?;
+        self.stream.write_chunk(&bytes).await
+    }
+
+    ///
+    /// Waits for the client to echo the close frame. If another task is receiving messages, the
+    /// close frame is received there, otherwise frames are read here once the reader is free.
+    ///
+    async fn wait_close_frame(&self) {
+        let close_notified = self.close_notify.notified();
+        tokio::pin!(close_notified);
+        close_notified.as_mut().enable();
+
+        if self.close_received.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let _reading = tokio::select! {
+            _ = &mut close_notified => return,
+            reading = self.reading.lock() => reading,
+        };
+
+        while !self.close_received.load(Ordering::Relaxed) {
+            match reader::read_frame(self.stream.clone(), DEFAULT_MAX_PAYLOAD_SIZE).await {
+                Ok(frame) => {
+                    if frame.op_code == 8 {
+                        self.close_received.store(true, Ordering::Relaxed);
+                        self.close_notify.notify_waiters();
+                    }
+                }
+                Err(_) => return,
+            }
+        }
     }
 
     pub fn exit(self) -> Box<Self> {
         Box::new(self)
     }
+}
+
+///
+/// Builds control frame bytes. Control frames are never fragmented and their payload must not
+/// exceed 125 bytes.
+///
+fn control_frame_bytes(op_code: u8, payload: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    if payload.len() > MAX_CONTROL_PAYLOAD_SIZE {
+        return Err(std::io::Error::other(format!(
+            "Control frame payload must not exceed {} bytes.",
+            MAX_CONTROL_PAYLOAD_SIZE
+        )));
+    }
+
+    let frame = Frame {
+        fin: 1,
+        rsv1: 0,
+        op_code,
+        payload,
+    };
+    Ok(frame::builder::build(&frame))
+}
+
+///
+/// Returns true if the status code is allowed in close frames of both peers. Codes 1004 to 1006
+/// and 1015 are reserved for reporting, other codes below 3000 are defined by the protocol and
+/// codes from 3000 to 4999 are available to applications.
+///
+fn is_valid_close_code(code: u16) -> bool {
+    matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
+}
+
+///
+/// Returns status code and reason from the close frame payload. Status code is 0 if the payload is
+/// empty. Returns error if the payload is not a valid close frame payload.
+///
+/// More information: <https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.1>
+///
+fn close_payload(payload: &[u8]) -> Result<(u16, String), String> {
+    if payload.len() > MAX_CONTROL_PAYLOAD_SIZE {
+        return Err(format!(
+            "Close frame payload is larger than {} bytes.",
+            MAX_CONTROL_PAYLOAD_SIZE
+        ));
+    }
+
+    if payload.is_empty() {
+        return Ok((0, "No close message specified.".to_string()));
+    }
+
+    if payload.len() == 1 {
+        return Err("Close frame payload is missing status code byte.".to_string());
+    }
+
+    let close_code = u16::from_be_bytes([payload[0], payload[1]]);
+    let close_message = match std::str::from_utf8(&payload[2..]) {
+        Ok(message) => message.to_string(),
+        Err(_) => return Err("Close reason is not valid UTF-8.".to_string()),
+    };
+
+    if close_message.is_empty() {
+        return Ok((close_code, "No close message specified.".to_string()));
+    }
+
+    Ok((close_code, close_message))
+}
+
+///
+/// Reads up to `size` bytes. Returns less bytes only if the reader reaches the end.
+///
+async fn read_fragment<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    size: usize,
+) -> std::io::Result<Vec<u8>> {
+    let mut buffer = vec![0; size];
+    let mut filled = 0;
+
+    while filled < size {
+        let read_size = reader.read(&mut buffer[filled..]).await?;
+        if read_size == 0 {
+            break;
+        }
+        filled += read_size;
+    }
+
+    buffer.truncate(filled);
+    Ok(buffer)
+}
+
+///
+/// Returns the first subprotocol listed in `Sec-WebSocket-Protocol` request header which is
+/// supported by the server. Subprotocol names are case-sensitive.
+///
+fn select_protocol(requested_protocols: Option<&str>, supported: &[String]) -> Option<String> {
+    let requested_protocols = requested_protocols?;
+
+    requested_protocols
+        .split(',')
+        .map(|protocol| protocol.trim())
+        .find(|protocol| supported.iter().any(|supported| supported == protocol))
+        .map(|protocol| protocol.to_string())
+}
+
+///
+/// Returns value of `Sec-WebSocket-Extensions` response header if any of the `permessage-deflate`
+/// offers in the request header can be accepted. Offers limiting the server window size are not
+/// accepted, since the messages are compressed with the default window size.
+///
+/// More information: <https://datatracker.ietf.org/doc/html/rfc7692#section-7.1>
+///
+fn negotiate_permessage_deflate(offers: Option<&str>) -> Option<String> {
+    let offers = offers?;
+
+    for offer in offers.split(',') {
+        let mut params = offer.split(';').map(|param| param.trim());
+        if params.next() != Some("permessage-deflate") {
+            continue;
+        }
+
+        let is_acceptable = params.all(|param| {
+            let (name, value) = match param.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (param, None),
+            };
+
+            match name {
+                "server_no_context_takeover" | "client_no_context_takeover" => value.is_none(),
+                "client_max_window_bits" => true,
+                "server_max_window_bits" => value == Some("15"),
+                _ => false,
+            }
+        });
+
+        if is_acceptable {
+            return Some("permessage-deflate; server_no_context_takeover".to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::net::SocketAddr;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Mutex as StdMutex};
+    use std::time::Duration;
+
+    use tokio::sync::Mutex;
+
+    use crate::core::path::{Path, View};
+    use crate::core::request::Request;
+    use crate::core::response::Response;
+    use crate::core::server::tests::{serve_raw, test_state};
+    use crate::core::server::Server;
+    use crate::core::stream::{AbstractStream, Stream, TestStreamWrapper};
+    use crate::core::websocket::frame::{builder, reader, Frame};
+
+    use super::WebSocketConnections;
+    use super::{deflate, Inflater};
+    use super::{negotiate_permessage_deflate, Message, WebSocket, WebSocketOptions};
+
+    pub(crate) fn test_websocket(client_bytes: Vec<u8>) -> (WebSocket, Arc<Mutex<Vec<u8>>>) {
+        test_websocket_with_options(client_bytes, WebSocketOptions::default())
+    }
+
+    pub(crate) fn test_websocket_with_options(
+        client_bytes: Vec<u8>,
+        options: WebSocketOptions,
+    ) -> (WebSocket, Arc<Mutex<Vec<u8>>>) {
+        let stream = TestStreamWrapper::new(client_bytes, 1024);
+        let written_data = stream.written_data();
+        let stream: Box<dyn AbstractStream> = Box::new(stream);
+
+        let websocket = WebSocket::new(Arc::new(stream), true, None, options);
+        websocket.receive_next.store(true, Ordering::Relaxed);
+
+        (websocket, written_data)
+    }
+
+    pub(crate) fn client_frame(fin: u8, op_code: u8, payload: &[u8]) -> Vec<u8> {
+        let frame = Frame {
+            fin,
+            rsv1: 0,
+            op_code,
+            payload: payload.to_vec(),
+        };
+        builder::build_opt(&frame, true)
+    }
+
+    #[tokio::test]
+    async fn test_close_handshake() {
+        // Client sends a message before echoing close frame.
+        let mut client_bytes = client_frame(1, 1, b"Hello");
+        client_bytes.extend(client_frame(1, 8, &1000u16.to_be_bytes()));
+
+        let (websocket, written_data) = test_websocket(client_bytes);
+        websocket.close().await;
+
+        assert_eq!(true, websocket.close_received.load(Ordering::Relaxed));
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xE8], *written_data.lock().await);
+        assert_eq!(true, websocket.message().await.is_none());
+
+        // Close frame is sent only once.
+        websocket.close().await;
+        assert_eq!(4, written_data.lock().await.len());
+    }
+
+    #[tokio::test]
+    async fn test_close_with_reason() {
+        let (websocket, written_data) = test_websocket(vec![]);
+        let result = websocket.close_with_reason(1005, "").await;
+        assert_eq!(true, result.is_err());
+        let result = websocket.close_with_reason(4000, &"a".repeat(124)).await;
+        assert_eq!(true, result.is_err());
+        assert_eq!(true, written_data.lock().await.is_empty());
+
+        let client_bytes = client_frame(1, 8, &4001u16.to_be_bytes());
+        let (websocket, written_data) = test_websocket(client_bytes);
+        let result = websocket.close_with_reason(4001, "Session expired").await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(true, websocket.close_received.load(Ordering::Relaxed));
+
+        let mut expected = vec![0x88, 17, 0x0F, 0xA1];
+        expected.extend(b"Session expired");
+        assert_eq!(expected, *written_data.lock().await);
+        assert_eq!(true, websocket.message().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_close_while_receiving() {
+        let mut client_bytes = client_frame(1, 1, b"Hello");
+        client_bytes.extend(client_frame(1, 8, &1000u16.to_be_bytes()));
+        let (websocket, written_data) = test_websocket(client_bytes.clone());
+
+        // Frames are not read by close while the other task receives messages.
+        let reading = websocket.reading.clone().lock_owned().await;
+        let closing = websocket.clone();
+        let close = tokio::spawn(async move { closing.close().await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xE8], *written_data.lock().await);
+        assert_eq!(false, close.is_finished());
+
+        // Receiving task gets the echoed close frame and completes the handshake.
+        websocket.handle_close_frame(&1000u16.to_be_bytes()).await;
+        tokio::time::timeout(Duration::from_secs(1), close)
+            .await
+            .unwrap()
+            .unwrap();
+        drop(reading);
+        assert_eq!(4, written_data.lock().await.len());
+
+        // Close reads the frames once the receiving task stops reading.
+        let (websocket, written_data) = test_websocket(client_bytes);
+        let reading = websocket.reading.clone().lock_owned().await;
+        let closing = websocket.clone();
+        let close = tokio::spawn(async move { closing.close().await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(reading);
+
+        tokio::time::timeout(Duration::from_secs(1), close)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(true, websocket.close_received.load(Ordering::Relaxed));
+        assert_eq!(4, written_data.lock().await.len());
+        assert_eq!(true, websocket.message().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_close_echo() {
+        let mut payload = 1001u16.to_be_bytes().to_vec();
+        payload.extend(b"Going away");
+
+        let (websocket, written_data) = test_websocket(client_frame(1, 8, &payload));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1001, code),
+            _ => panic!("Expected close message."),
+        }
+
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xE9], *written_data.lock().await);
+
+        // Handshake already completed by client. Close only shuts down the stream.
+        websocket.close().await;
+        assert_eq!(4, written_data.lock().await.len());
+    }
 
-    fn close_code_from_payload(&self, response: &[u8]) -> u16 {
-        if response.len() == 2 {
-            let mut tmp_bytes = [0u8; 2];
-            tmp_bytes.copy_from_slice(response);
-            return u16::from_be_bytes(tmp_bytes);
+    #[tokio::test]
+    async fn test_send_bytes_fragmented() {
+        let payload: Vec<u8> = (0..250).map(|i| i as u8).collect();
+
+        let (websocket, written_data) = test_websocket(vec![]);
+        let result = websocket
+            .send_bytes_fragmented(payload.as_slice(), 100)
+            .await;
+        assert_eq!(true, result.is_ok());
+
+        // Reads frames written to the client.
+        let written_bytes = written_data.lock().await.clone();
+        let stream: Box<dyn AbstractStream> = Box::new(TestStreamWrapper::new(written_bytes, 1024));
+        let stream = Arc::new(stream);
+
+        let mut frames = vec![];
+        while let Ok(frame) = reader::read_frame(stream.clone(), 1024).await {
+            frames.push(frame);
         }
 
-        racoon_debug!(
-            "Close payload length expected more than 2. But found: {}",
-            response.len()
+        let headers: Vec<(u8, u8, usize)> = frames
+            .iter()
+            .map(|frame| (frame.fin, frame.op_code, frame.payload.len()))
+            .collect();
+        assert_eq!(vec![(0, 2, 100), (0, 0, 100), (1, 0, 50)], headers);
+
+        let received: Vec<u8> = frames.into_iter().flat_map(|frame| frame.payload).collect();
+        assert_eq!(payload, received);
+
+        // Payload fitting in a single fragment is sent as a single frame.
+        let (websocket, written_data) = test_websocket(vec![]);
+        let _ = websocket.send_bytes_fragmented(&b"Hello"[..], 100).await;
+        assert_eq!(
+            vec![0x82, 0x05, b'H', b'e', b'l', b'l', b'o'],
+            *written_data.lock().await
         );
-        return 0;
     }
 
-    fn close_message_from_payload(&self, response: &[u8]) -> String {
-        if response.len() < 3 {
-            return "No close message specified.".to_string();
+    #[tokio::test]
+    async fn test_invalid_close_frame() {
+        // Oversized control frame
+        let mut payload = 1000u16.to_be_bytes().to_vec();
+        payload.extend("a".repeat(124).as_bytes());
+        assert_eq!(126, payload.len());
+
+        let (websocket, written_data) = test_websocket(client_frame(1, 8, &payload));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1002, code),
+            _ => panic!("Expected close message."),
+        }
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xEA], *written_data.lock().await);
+
+        // Invalid UTF-8 reason
+        let mut payload = 1000u16.to_be_bytes().to_vec();
+        payload.extend(b"\xff\xfe");
+
+        let (websocket, written_data) = test_websocket(client_frame(1, 8, &payload));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1002, code),
+            _ => panic!("Expected close message."),
+        }
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xEA], *written_data.lock().await);
+
+        // Single byte payload
+        let (websocket, _) = test_websocket(client_frame(1, 8, &[0x03]));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1002, code),
+            _ => panic!("Expected close message."),
+        }
+
+        // Reserved and undefined status codes
+        for close_code in [999u16, 1004, 1005, 1006, 1015, 1016, 2999, 5000] {
+            let client_bytes = client_frame(1, 8, &close_code.to_be_bytes());
+            let (websocket, written_data) = test_websocket(client_bytes);
+            match websocket.message().await {
+                Some(Message::Close(code, _)) => assert_eq!(1002, code),
+                _ => panic!("Expected close message."),
+            }
+            assert_eq!(vec![0x88, 0x02, 0x03, 0xEA], *written_data.lock().await);
+        }
+
+        // Application status codes are echoed.
+        let (websocket, written_data) = test_websocket(client_frame(1, 8, &4000u16.to_be_bytes()));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(4000, code),
+            _ => panic!("Expected close message."),
+        }
+        assert_eq!(vec![0x88, 0x02, 0x0F, 0xA0], *written_data.lock().await);
+    }
+
+    #[test]
+    fn test_control_frame_payload_limit() {
+        assert_eq!(true, super::control_frame_bytes(9, vec![0; 125]).is_ok());
+        assert_eq!(true, super::control_frame_bytes(9, vec![0; 126]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_control_frames() {
+        // Fragmented ping
+        let mut client_bytes = client_frame(0, 9, b"pi");
+        client_bytes.extend(client_frame(1, 0, b"ng"));
+
+        let (websocket, written_data) = test_websocket(client_bytes);
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1002, code),
+            _ => panic!("Expected close message."),
+        }
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xEA], *written_data.lock().await);
+        assert_eq!(true, websocket.message().await.is_none());
+
+        // Oversized ping
+        let (websocket, written_data) = test_websocket(client_frame(1, 9, &[0; 126]));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1002, code),
+            _ => panic!("Expected close message."),
         }
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xEA], *written_data.lock().await);
+
+        // Valid ping is answered with pong.
+        let (websocket, written_data) = test_websocket(client_frame(1, 9, &[]));
+        assert_eq!(
+            true,
+            matches!(websocket.message().await, Some(Message::Ping()))
+        );
+        assert_eq!(vec![0x8A, 0x00], *written_data.lock().await);
+    }
+
+    #[tokio::test]
+    async fn test_interleaved_control_frames() {
+        let mut client_bytes = client_frame(0, 1, b"Hel");
+        client_bytes.extend(client_frame(1, 9, b"ping"));
+        client_bytes.extend(client_frame(0, 0, b"lo "));
+        client_bytes.extend(client_frame(1, 10, b""));
+        client_bytes.extend(client_frame(1, 0, b"World"));
+
+        let (websocket, written_data) = test_websocket(client_bytes);
+        match websocket.message().await {
+            Some(Message::Text(text)) => assert_eq!("Hello World", text),
+            _ => panic!("Expected text message."),
+        }
+
+        // Ping is answered with its payload.
+        assert_eq!(
+            vec![0x8A, 0x04, b'p', b'i', b'n', b'g'],
+            *written_data.lock().await
+        );
+
+        // New message before the fragmented message is completed.
+        let mut client_bytes = client_frame(0, 2, b"Hel");
+        client_bytes.extend(client_frame(1, 1, b"lo"));
+
+        let (websocket, _) = test_websocket(client_bytes);
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1002, code),
+            _ => panic!("Expected close message."),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_messages() {
+        let mut client_bytes = client_frame(1, 1, b"One");
+        client_bytes.extend(client_frame(1, 1, b"Two"));
+        client_bytes.extend(client_frame(1, 1, b"Three"));
+
+        let options = WebSocketOptions {
+            max_messages: Some(2),
+            ..WebSocketOptions::default()
+        };
+        let (websocket, written_data) = test_websocket_with_options(client_bytes, options);
+        assert_eq!(
+            true,
+            matches!(websocket.message().await, Some(Message::Text(_)))
+        );
+        assert_eq!(
+            true,
+            matches!(websocket.message().await, Some(Message::Text(_)))
+        );
+
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1008, code),
+            _ => panic!("Expected close message."),
+        }
+        assert_eq!(true, websocket.message().await.is_none());
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xF0], *written_data.lock().await);
+    }
+
+    #[tokio::test]
+    async fn test_max_total_bytes() {
+        // Limit is exceeded in the middle of fragmented message.
+        let mut client_bytes = client_frame(1, 2, &[0; 6]);
+        client_bytes.extend(client_frame(0, 2, &[0; 3]));
+        client_bytes.extend(client_frame(1, 0, &[0; 3]));
+
+        let options = WebSocketOptions {
+            max_total_bytes: Some(10),
+            ..WebSocketOptions::default()
+        };
+        let (websocket, _) = test_websocket_with_options(client_bytes, options);
+        assert_eq!(
+            true,
+            matches!(websocket.message().await, Some(Message::Binary(_)))
+        );
+
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1008, code),
+            _ => panic!("Expected close message."),
+        }
+    }
+
+    async fn peer_addr_view(request: Request) -> Response {
+        let (websocket, connected) = WebSocket::from_opt(&request, false).await;
+        if !connected {
+            return websocket.bad_request().await;
+        }
+
+        let peer_addr = websocket.peer_addr().map(|addr| addr.to_string());
+        let peer_addr = peer_addr.unwrap_or_default();
+        let _ = websocket.send_text(peer_addr).await;
+        websocket.exit()
+    }
+
+    #[tokio::test]
+    async fn test_peer_addr() {
+        let view: View = |request| Box::pin(peer_addr_view(request));
+        let state = test_state(vec![Path::new("/ws", view)]).await;
+
+        let request = b"GET /ws HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\n\
+            Upgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+            Sec-WebSocket-Version: 13\r\n\r\n";
+        let mut stream = TestStreamWrapper::new(request.to_vec(), 1024);
+        stream.set_peer_addr("127.0.0.1:5000".parse::<SocketAddr>().unwrap());
+        let written_data = stream.written_data();
+
+        Server::handle_stream(Box::new(stream), Arc::new(state)).await;
+
+        let written_data = written_data.lock().await;
+        let response = String::from_utf8_lossy(&written_data);
+        assert_eq!(true, response.starts_with("HTTP/1.1 101"));
+        assert_eq!(true, response.ends_with("127.0.0.1:5000"));
+    }
+
+    #[tokio::test]
+    async fn test_max_websocket_connections() {
+        let view: View = |request| Box::pin(peer_addr_view(request));
+        let websocket_connections = Arc::new(WebSocketConnections::new(1));
+
+        let request = b"GET /ws HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\n\
+            Upgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+            Sec-WebSocket-Version: 13\r\n\r\n";
+
+        // Another connection is already open.
+        let open_connection = websocket_connections.acquire();
+        let mut state = test_state(vec![Path::new("/ws", view)]).await;
+        state.websocket_connections = Some(websocket_connections.clone());
+        let response = serve_raw(state, request).await;
+        assert_eq!(
+            true,
+            response.starts_with("HTTP/1.1 503 Service Unavailable")
+        );
+        assert_eq!(1, websocket_connections.open.load(Ordering::Acquire));
+
+        drop(open_connection);
+        let mut state = test_state(vec![Path::new("/ws", view)]).await;
+        state.websocket_connections = Some(websocket_connections.clone());
+        let response = serve_raw(state, request).await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 101"));
+
+        // Released once the view returns.
+        assert_eq!(0, websocket_connections.open.load(Ordering::Acquire));
+    }
+
+    async fn protocol_view(request: Request) -> Response {
+        let (websocket, connected) =
+            WebSocket::from_with_protocols(&request, &["chat.v2", "chat.v1"]).await;
+        if !connected {
+            return websocket.bad_request().await;
+        }
+
+        let protocol = websocket.protocol.clone().unwrap_or("none".to_string());
+        let _ = websocket.send_text(protocol).await;
+        websocket.exit()
+    }
+
+    async fn handshake_response(protocol_header: &str) -> String {
+        let view: View = |request| Box::pin(protocol_view(request));
+        let state = test_state(vec![Path::new("/ws", view)]).await;
+
+        let request = format!(
+            "GET /ws HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\n\
+            Upgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+            Sec-WebSocket-Version: 13\r\n{}\r\n",
+            protocol_header
+        );
+        let stream = TestStreamWrapper::new(request.into_bytes(), 1024);
+        let written_data = stream.written_data();
+
+        Server::handle_stream(Box::new(stream), Arc::new(state)).await;
+
+        let written_data = written_data.lock().await;
+        String::from_utf8_lossy(&written_data).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_subprotocol() {
+        let response =
+            handshake_response("Sec-WebSocket-Protocol: chat.v3, chat.v1, chat.v2\r\n").await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 101"));
+        assert_eq!(
+            true,
+            response.contains("Sec-WebSocket-Protocol: chat.v1\r\n")
+        );
+        assert_eq!(true, response.ends_with("chat.v1"));
+
+        // Handshake succeeds without the header if no subprotocol matches.
+        let response = handshake_response("Sec-WebSocket-Protocol: CHAT.V1\r\n").await;
+        assert_eq!(true, response.starts_with("HTTP/1.1 101"));
+        assert_eq!(false, response.contains("Sec-WebSocket-Protocol"));
+        assert_eq!(true, response.ends_with("none"));
+
+        let response = handshake_response("").await;
+        assert_eq!(false, response.contains("Sec-WebSocket-Protocol"));
+        assert_eq!(true, response.ends_with("none"));
+    }
+
+    #[test]
+    fn test_negotiate_permessage_deflate() {
+        let accepted = Some("permessage-deflate; server_no_context_takeover".to_string());
+        assert_eq!(
+            accepted,
+            negotiate_permessage_deflate(Some("permessage-deflate; client_max_window_bits"))
+        );
+
+        // First acceptable offer is selected.
+        let offers = "permessage-deflate; server_max_window_bits=10, permessage-deflate";
+        assert_eq!(accepted, negotiate_permessage_deflate(Some(offers)));
+
+        let offers = "permessage-deflate; server_max_window_bits=10, x-webkit-deflate-frame";
+        assert_eq!(None, negotiate_permessage_deflate(Some(offers)));
+        assert_eq!(None, negotiate_permessage_deflate(None));
+    }
+
+    fn compressed_websocket(client_bytes: Vec<u8>) -> (WebSocket, Arc<Mutex<Vec<u8>>>) {
+        let (mut websocket, written_data) = test_websocket(client_bytes);
+        websocket.inflater = Some(Arc::new(StdMutex::new(Inflater::new())));
+        (websocket, written_data)
+    }
+
+    #[tokio::test]
+    async fn test_permessage_deflate() {
+        let message = "Hello World. ".repeat(100);
+        let compressed_payload = deflate::compress(message.as_bytes()).unwrap();
+
+        // Compressed message split into two frames.
+        let (first, second) = compressed_payload.split_at(compressed_payload.len() / 2);
+        let mut first_frame = Frame {
+            fin: 0,
+            rsv1: 1,
+            op_code: 1,
+            payload: first.to_vec(),
+        };
+        let mut client_bytes = builder::build_opt(&first_frame, true);
+        client_bytes.extend(client_frame(1, 0, second));
+
+        let (websocket, written_data) = compressed_websocket(client_bytes);
+        match websocket.message().await {
+            Some(Message::Text(text)) => assert_eq!(message, text),
+            _ => panic!("Expected text message."),
+        }
+
+        websocket.send_text(&message).await.unwrap();
+        websocket.send_text("Hi").await.unwrap();
+
+        let written_data = written_data.lock().await.clone();
+        let stream: Stream = Box::new(TestStreamWrapper::new(written_data, 1024));
+        let stream = Arc::new(stream);
+
+        let frame = reader::read_frame(stream.clone(), 5000).await.unwrap();
+        assert_eq!(1, frame.rsv1);
+        let payload = Inflater::new().decompress(&frame.payload, 5000).unwrap();
+        assert_eq!(message.as_bytes().to_vec(), payload);
+
+        // Short messages are sent uncompressed.
+        let frame = reader::read_frame(stream, 5000).await.unwrap();
+        assert_eq!(0, frame.rsv1);
+        assert_eq!(b"Hi".to_vec(), frame.payload);
+
+        // RSV1 bit is not allowed without negotiation.
+        first_frame.fin = 1;
+        let (websocket, _) = test_websocket(builder::build_opt(&first_frame, true));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1002, code),
+            _ => panic!("Expected close message."),
+        }
+
+        // Invalid compressed payload
+        first_frame.payload = vec![0xff, 0xff, 0xff];
+        let (websocket, _) = compressed_websocket(builder::build_opt(&first_frame, true));
+        match websocket.message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1007, code),
+            _ => panic!("Expected close message."),
+        }
+
+        // Decompressed message is limited by the payload size given by the caller.
+        let compressed_payload = deflate::compress(&[b'a'; 4096]).unwrap();
+        assert_eq!(true, compressed_payload.len() < 1024);
+        first_frame.payload = compressed_payload;
+        let (websocket, written_data) =
+            compressed_websocket(builder::build_opt(&first_frame, true));
+        match websocket.receive_message_with_limit(1024).await {
+            Some(Message::Close(code, _)) => assert_eq!(1009, code),
+            _ => panic!("Expected close message."),
+        }
+        assert_eq!(vec![0x88, 0x02, 0x03, 0xF1], *written_data.lock().await);
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct UserId(u32);
+
+    #[test]
+    fn test_context() {
+        let (websocket, _) = test_websocket(vec![]);
+        assert_eq!(None, websocket.context::<UserId>());
+
+        websocket.set_context(UserId(1));
+        websocket.set_context("general".to_string());
+        assert_eq!(Some(UserId(1)), websocket.context::<UserId>());
+        assert_eq!(Some("general".to_string()), websocket.context::<String>());
 
-        let message_bytes = &response[2..];
-        String::from_utf8_lossy(&message_bytes).to_string()
+        // Context is shared between clones.
+        let cloned = websocket.clone();
+        cloned.set_context(UserId(2));
+        assert_eq!(Some(UserId(2)), websocket.context::<UserId>());
     }
 }
@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::core::stream::{Stream, TestStreamWrapper};
+use crate::core::websocket::frame::{builder, reader, Frame};
+use crate::core::websocket::{Message, WebSocket, WebSocketOptions};
+
+///
+/// In-memory WebSocket connection for testing WebSocket handlers without sockets. The
+/// `WebSocket` is already handshake validated. Frames sent with `send_*` methods are read by the
+/// handler as if sent by a client, and frames written by the handler are read with
+/// `next_frame` or `next_message`.
+///
+/// When the handler reads after all the sent frames are consumed, it behaves as if the client
+/// disconnected.
+///
+/// # Examples
+/// ```
+/// use racoon::core::websocket::testing::TestWebSocket;
+/// use racoon::core::websocket::{Message, WebSocket};
+///
+/// async fn echo(websocket: WebSocket) {
+///     while let Some(message) = websocket.message().await {
+///         if let Message::Text(text) = message {
+///             let _ = websocket.send_text(text).await;
+///         }
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut client = TestWebSocket::new();
+///     client.send_text("Hello").await;
+///
+///     echo(client.websocket()).await;
+///
+///     match client.next_message().await {
+///         Some(Message::Text(text)) => assert_eq!("Hello", text),
+///         _ => panic!("Expected text message."),
+///     }
+///     assert_eq!(true, client.next_message().await.is_none());
+/// }
+/// ```
+///
+pub struct TestWebSocket {
+    websocket: WebSocket,
+    client_data: Arc<Mutex<Vec<u8>>>,
+    written_data: Arc<Mutex<Vec<u8>>>,
+    server_frames: VecDeque<Frame>,
+}
+
+impl TestWebSocket {
+    ///
+    /// Creates connection with default options except periodic ping, which is disabled so that
+    /// only frames written by the handler are received.
+    ///
+    pub fn new() -> Self {
+        let options = WebSocketOptions {
+            periodic_ping: false,
+            ..WebSocketOptions::default()
+        };
+        Self::with_options(options)
+    }
+
+    pub fn with_options(options: WebSocketOptions) -> Self {
+        let stream = TestStreamWrapper::new(vec![], 1024);
+        let client_data = stream.test_data();
+        let written_data = stream.written_data();
+        let stream: Stream = Box::new(stream);
+
+        let websocket = WebSocket::new(Arc::new(stream), true, None, options);
+        websocket.receive_next.store(true, Ordering::Relaxed);
+
+        Self {
+            websocket,
+            client_data,
+            written_data,
+            server_frames: VecDeque::new(),
+        }
+    }
+
+    ///
+    /// Returns server side `WebSocket` to pass to the handler. All the returned instances share the
+    /// same connection.
+    ///
+    pub fn websocket(&self) -> WebSocket {
+        self.websocket.clone()
+    }
+
+    ///
+    /// Sends masked frame like a client. Useful for fragmented messages or invalid frames.
+    ///
+    pub async fn send_frame(&self, fin: u8, op_code: u8, payload: &[u8]) {
+        let frame = Frame {
+            fin,
+            rsv1: 0,
+            op_code,
+            payload: payload.to_vec(),
+        };
+
+        let mut client_data = self.client_data.lock().await;
+        client_data.extend(builder::build_opt(&frame, true));
+    }
+
+    pub async fn send_text<S: AsRef<str>>(&self, text: S) {
+        self.send_frame(1, 1, text.as_ref().as_bytes()).await;
+    }
+
+    pub async fn send_bytes<B: AsRef<[u8]>>(&self, bytes: B) {
+        self.send_frame(1, 2, bytes.as_ref()).await;
+    }
+
+    pub async fn send_close<S: AsRef<str>>(&self, code: u16, reason: S) {
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend(reason.as_ref().as_bytes());
+        self.send_frame(1, 8, &payload).await;
+    }
+
+    pub async fn send_ping(&self) {
+        self.send_frame(1, 9, &[]).await;
+    }
+
+    ///
+    /// Returns next frame written by the server. Returns `None` if no frames are written yet.
+    ///
+    pub async fn next_frame(&mut self) -> Option<Frame> {
+        if self.server_frames.is_empty() {
+            let written_bytes: Vec<u8> = self.written_data.lock().await.drain(..).collect();
+            let stream: Stream = Box::new(TestStreamWrapper::new(written_bytes, 1024));
+            let stream = Arc::new(stream);
+
+            while let Ok(frame) = reader::read_frame(stream.clone(), u64::MAX).await {
+                self.server_frames.push_back(frame);
+            }
+        }
+
+        self.server_frames.pop_front()
+    }
+
+    ///
+    /// Returns next message written by the server. Fragmented messages are joined.
+    ///
+    pub async fn next_message(&mut self) -> Option<Message> {
+        let first_frame = self.next_frame().await?;
+        let op_code = first_frame.op_code;
+        let mut payload = first_frame.payload;
+        let mut fin = first_frame.fin;
+
+        while fin == 0 {
+            let frame = self.next_frame().await?;
+            payload.extend(frame.payload);
+            fin = frame.fin;
+        }
+
+        let message = match op_code {
+            1 => Message::Text(String::from_utf8_lossy(&payload).to_string()),
+            2 => Message::Binary(payload),
+            8 => {
+                let code = match payload.get(0..2) {
+                    Some(code) => u16::from_be_bytes([code[0], code[1]]),
+                    None => 1005,
+                };
+                let reason = String::from_utf8_lossy(payload.get(2..).unwrap_or(&[])).to_string();
+                Message::Close(code, reason)
+            }
+            9 => Message::Ping(),
+            10 => Message::Pong(),
+            _ => Message::Others(payload),
+        };
+        Some(message)
+    }
+}
+
+impl Default for TestWebSocket {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::core::websocket::{Message, WebSocket};
+
+    use super::TestWebSocket;
+
+    async fn echo(websocket: WebSocket) {
+        while let Some(message) = websocket.message().await {
+            match message {
+                Message::Text(text) => {
+                    let _ = websocket.send_text(text).await;
+                }
+                Message::Binary(bytes) => {
+                    let _ = websocket.send_bytes_fragmented(bytes.as_slice(), 2).await;
+                }
+                Message::Close(_, _) => break,
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_echo() {
+        let mut client = TestWebSocket::new();
+        client.send_text("Hello").await;
+        client.send_frame(0, 1, b"Wor").await;
+        client.send_frame(1, 0, b"ld").await;
+        client.send_bytes(b"12345").await;
+        client.send_close(1000, "Bye").await;
+
+        echo(client.websocket()).await;
+
+        match client.next_message().await {
+            Some(Message::Text(text)) => assert_eq!("Hello", text),
+            _ => panic!("Expected text message."),
+        }
+        match client.next_message().await {
+            Some(Message::Text(text)) => assert_eq!("World", text),
+            _ => panic!("Expected text message."),
+        }
+
+        // Fragments sent by the server are joined.
+        match client.next_message().await {
+            Some(Message::Binary(bytes)) => assert_eq!(b"12345".to_vec(), bytes),
+            _ => panic!("Expected binary message."),
+        }
+
+        // Close frame is echoed.
+        match client.next_message().await {
+            Some(Message::Close(code, _)) => assert_eq!(1000, code),
+            _ => panic!("Expected close message."),
+        }
+        assert_eq!(true, client.next_message().await.is_none());
+    }
+}
@@ -0,0 +1,407 @@
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::core::forms::{Files, FormData};
+use crate::forms::fields::{AbstractFields, FieldResult};
+
+///
+/// Allowed values of `ChoiceField`.
+///
+#[derive(Debug, Clone)]
+pub struct Choices {
+    choices: Vec<String>,
+    case_sensitive: bool,
+}
+
+impl Choices {
+    ///
+    /// Returns the choice matching the value. The choice is returned as declared, so `Admin`
+    /// matched case insensitively returns `admin`.
+    ///
+    pub fn matched(&self, value: &str) -> Option<String> {
+        self.choices
+            .iter()
+            .find(|choice| {
+                if self.case_sensitive {
+                    choice.as_str() == value
+                } else {
+                    choice.eq_ignore_ascii_case(value)
+                }
+            })
+            .cloned()
+    }
+}
+
+pub trait ToTypeT {
+    fn from_vec(values: &mut Vec<String>, choices: &Choices) -> Option<Self>
+    where
+        Self: Sized;
+
+    fn is_optional() -> bool;
+}
+
+impl ToTypeT for String {
+    fn from_vec(values: &mut Vec<String>, choices: &Choices) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if !values.is_empty() {
+            let value = values.remove(0);
+            return choices.matched(&value);
+        }
+
+        None
+    }
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl ToTypeT for Option<String> {
+    fn from_vec(values: &mut Vec<String>, choices: &Choices) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            // Outer Some denotes conversion success with value None.
+            return Some(None);
+        }
+
+        let value = values.remove(0);
+        // Conversion fails if the value is not one of the choices.
+        choices.matched(&value).map(Some)
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl ToTypeT for Vec<String> {
+    fn from_vec(values: &mut Vec<String>, choices: &Choices) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            return None;
+        }
+
+        std::mem::take(values)
+            .iter()
+            .map(|value| choices.matched(value))
+            .collect()
+    }
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl ToTypeT for Option<Vec<String>> {
+    fn from_vec(values: &mut Vec<String>, choices: &Choices) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            return Some(None);
+        }
+
+        Vec::<String>::from_vec(values, choices).map(Some)
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+type BoxResult = Box<dyn Any + Send + Sync>;
+
+pub enum ChoiceFieldError<'a> {
+    /// (field_name)
+    MissingField(&'a String),
+    /// (field_name, values)
+    InvalidChoice(&'a String, &'a Vec<String>),
+}
+
+pub type ErrorHandler = Box<fn(ChoiceFieldError, Vec<String>) -> Vec<String>>;
+
+///
+/// Form field accepting only one of the given choices, for example value selected from the
+/// dropdown. Values are matched case sensitively by default.
+///
+/// # Examples
+///
+/// ```
+/// use racoon::forms::fields::choice_field::ChoiceField;
+///
+/// let role: ChoiceField<String> = ChoiceField::new("role", vec!["admin", "user", "guest"]);
+/// let tags: ChoiceField<Option<Vec<String>>> =
+///     ChoiceField::new("tags", vec!["rust", "python"]).case_sensitive(false);
+/// ```
+///
+pub struct ChoiceField<T> {
+    field_name: String,
+    choices: Choices,
+    result: Arc<Mutex<Option<BoxResult>>>,
+    validated: Arc<AtomicBool>,
+    error_handler: Option<Arc<ErrorHandler>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Clone for ChoiceField<T> {
+    fn clone(&self) -> Self {
+        Self {
+            field_name: self.field_name.clone(),
+            choices: self.choices.clone(),
+            result: self.result.clone(),
+            validated: self.validated.clone(),
+            error_handler: self.error_handler.clone(),
+            phantom: self.phantom,
+        }
+    }
+}
+
+impl<T: ToTypeT + Sync + Send> ChoiceField<T> {
+    pub fn new<S: AsRef<str>, C: AsRef<str>>(field_name: S, choices: Vec<C>) -> Self {
+        let field_name = field_name.as_ref().to_string();
+        let choices = choices
+            .iter()
+            .map(|choice| choice.as_ref().to_string())
+            .collect();
+
+        Self {
+            field_name,
+            choices: Choices {
+                choices,
+                case_sensitive: true,
+            },
+            result: Arc::new(Mutex::new(None)),
+            validated: Arc::new(AtomicBool::new(false)),
+            error_handler: None,
+            phantom: PhantomData,
+        }
+    }
+
+    ///
+    /// Passing false matches values ignoring ASCII case. Default is true.
+    ///
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.choices.case_sensitive = case_sensitive;
+        self
+    }
+
+    pub fn handle_error_message(
+        mut self,
+        callback: fn(ChoiceFieldError, Vec<String>) -> Vec<String>,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(Box::new(callback)));
+        self
+    }
+
+    pub async fn value(self) -> T
+    where
+        T: 'static,
+    {
+        if !self.validated.load(Ordering::Relaxed) {
+            panic!("This field is not validated. Please call form.validate() method before accessing value.");
+        }
+
+        let mut lock = self.result.lock().await;
+        if let Some(result) = lock.take() {
+            if let Ok(t) = result.downcast::<T>() {
+                return *t;
+            }
+        }
+        panic!("Unexpected error. Bug in choice_field.rs file.");
+    }
+}
+
+impl<T: ToTypeT + Sync + Send + 'static> AbstractFields for ChoiceField<T> {
+    fn field_name(&self) -> FieldResult<String> {
+        let field_name = self.field_name.clone();
+        Box::new(Box::pin(async move { field_name }))
+    }
+
+    fn validate(
+        &mut self,
+        form_data: &mut FormData,
+        _: &mut Files,
+    ) -> FieldResult<Result<(), Vec<String>>> {
+        let field_name = self.field_name.clone();
+        let choices = self.choices.clone();
+        let mut values = form_data.remove(&field_name);
+        let result_ref = self.result.clone();
+        let validated = self.validated.clone();
+
+        let error_handler = self.error_handler.clone();
+
+        Box::new(Box::pin(async move {
+            let is_empty;
+            let is_optional = T::is_optional();
+
+            let mut errors: Vec<String> = vec![];
+
+            if let Some(values) = values.as_mut() {
+                is_empty = values.is_empty();
+                // Keeps submitted values for the error handler.
+                let submitted_values = values.clone();
+                let option_t = T::from_vec(values, &choices);
+
+                if let Some(t) = option_t {
+                    let mut result = result_ref.lock().await;
+                    *result = Some(Box::new(t));
+                } else if !is_empty {
+                    let default_invalid_choice_error = "Invalid choice.".to_string();
+                    if let Some(error_handler) = error_handler.clone() {
+                        let invalid_choice_error =
+                            ChoiceFieldError::InvalidChoice(&field_name, &submitted_values);
+                        let custom_errors =
+                            error_handler(invalid_choice_error, vec![default_invalid_choice_error]);
+                        errors.extend_from_slice(&custom_errors);
+                    } else {
+                        errors.push(default_invalid_choice_error);
+                    }
+                }
+            } else {
+                is_empty = true;
+            }
+
+            if !is_optional && is_empty {
+                let default_missing_error = "This field is required.".to_string();
+
+                if let Some(error_handler) = error_handler.clone() {
+                    let missing_error = ChoiceFieldError::MissingField(&field_name);
+                    let custom_errors = error_handler(missing_error, vec![default_missing_error]);
+                    errors.extend_from_slice(&custom_errors);
+                } else {
+                    errors.push(default_missing_error);
+                }
+            }
+
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+
+            if is_optional && is_empty {
+                let value_t = T::from_vec(&mut vec![], &choices);
+
+                if let Some(t) = value_t {
+                    let mut result = result_ref.lock().await;
+                    *result = Some(Box::new(t));
+                }
+            }
+
+            validated.store(true, Ordering::Relaxed);
+            Ok(())
+        }))
+    }
+
+    fn wrap(&self) -> Box<dyn AbstractFields> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::core::forms::{Files, FormData};
+    use crate::forms::fields::choice_field::{ChoiceField, ChoiceFieldError};
+    use crate::forms::fields::AbstractFields;
+
+    #[tokio::test]
+    async fn test_choice_validate_required() {
+        let choices = vec!["admin", "user", "guest"];
+        let mut files = Files::new();
+
+        let mut role: ChoiceField<String> = ChoiceField::new("role", choices.clone());
+        let mut form_data = FormData::new();
+        let result = role.validate(&mut form_data, &mut files).await;
+        assert_eq!(
+            Some(vec!["This field is required.".to_string()]),
+            result.err()
+        );
+
+        let mut role: ChoiceField<String> = ChoiceField::new("role", choices.clone());
+        form_data.insert("role".to_string(), vec!["Admin".to_string()]);
+        let result = role.validate(&mut form_data, &mut files).await;
+        assert_eq!(Some(vec!["Invalid choice.".to_string()]), result.err());
+
+        let role: ChoiceField<String> = ChoiceField::new("role", choices).case_sensitive(false);
+        let mut role_clone = role.clone();
+        form_data.insert("role".to_string(), vec!["Admin".to_string()]);
+        let result = role_clone.validate(&mut form_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!("admin", role.value().await);
+    }
+
+    #[tokio::test]
+    async fn test_choice_optional() {
+        let mut role: ChoiceField<Option<String>> = ChoiceField::new("role", vec!["admin"]);
+        let mut form_data = FormData::new();
+        let mut files = Files::new();
+        let result = role.validate(&mut form_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(None, role.value().await);
+
+        let mut role: ChoiceField<Option<String>> = ChoiceField::new("role", vec!["admin"]);
+        form_data.insert("role".to_string(), vec!["root".to_string()]);
+        let result = role.validate(&mut form_data, &mut files).await;
+        assert_eq!(false, result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_choice_vec() {
+        let choices = vec!["rust", "python", "go"];
+        let mut files = Files::new();
+
+        let mut tags: ChoiceField<Vec<String>> = ChoiceField::new("tags", choices.clone());
+        let mut form_data = FormData::new();
+        form_data.insert(
+            "tags".to_string(),
+            vec!["rust".to_string(), "go".to_string()],
+        );
+        let result = tags.validate(&mut form_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(
+            vec!["rust".to_string(), "go".to_string()],
+            tags.value().await
+        );
+
+        let mut tags: ChoiceField<Vec<String>> = ChoiceField::new("tags", choices.clone());
+        form_data.insert(
+            "tags".to_string(),
+            vec!["rust".to_string(), "java".to_string()],
+        );
+        let result = tags.validate(&mut form_data, &mut files).await;
+        assert_eq!(false, result.is_ok());
+
+        let mut tags: ChoiceField<Option<Vec<String>>> = ChoiceField::new("tags", choices);
+        let result = tags.validate(&mut form_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(None, tags.value().await);
+    }
+
+    #[tokio::test]
+    async fn test_choice_custom_error() {
+        let mut role: ChoiceField<String> = ChoiceField::new("role", vec!["admin", "user"])
+            .handle_error_message(|error, default_errors| match error {
+                ChoiceFieldError::InvalidChoice(_, values) => {
+                    vec![format!("{} is not a valid role.", values[0])]
+                }
+                ChoiceFieldError::MissingField(_) => default_errors,
+            });
+
+        let mut form_data = FormData::new();
+        form_data.insert("role".to_string(), vec!["root".to_string()]);
+        let mut files = Files::new();
+        let result = role.validate(&mut form_data, &mut files).await;
+        assert_eq!(
+            Some(vec!["root is not a valid role.".to_string()]),
+            result.err()
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use tokio::sync::Mutex;
+
+use crate::core::forms::{Files, FormData};
+use crate::forms::fields::{AbstractFields, FieldResult};
+
+/// Default format of `DateField` values, e.g. `2024-06-30`.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+///
+/// Parses date in the given `chrono` format. Surrounding whitespace is ignored.
+///
+pub fn parse_date(value: &str, format: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), format).ok()
+}
+
+pub trait ToTypeT {
+    fn from_vec(values: &mut Vec<String>, format: &str) -> Option<Self>
+    where
+        Self: Sized;
+
+    fn is_optional() -> bool;
+}
+
+impl ToTypeT for NaiveDate {
+    fn from_vec(values: &mut Vec<String>, format: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if !values.is_empty() {
+            let value = values.remove(0);
+            return parse_date(&value, format);
+        }
+
+        None
+    }
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl ToTypeT for Option<NaiveDate> {
+    fn from_vec(values: &mut Vec<String>, format: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            // Outer Some denotes conversion success with value None.
+            return Some(None);
+        }
+
+        let value = values.remove(0);
+        // Conversion fails with invalid date.
+        parse_date(&value, format).map(Some)
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl ToTypeT for Vec<NaiveDate> {
+    fn from_vec(values: &mut Vec<String>, format: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            return None;
+        }
+
+        // Returns None if any of the values is not a valid date.
+        std::mem::take(values)
+            .iter()
+            .map(|value| parse_date(value, format))
+            .collect()
+    }
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl ToTypeT for Option<Vec<NaiveDate>> {
+    fn from_vec(values: &mut Vec<String>, format: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            return Some(None);
+        }
+
+        Vec::<NaiveDate>::from_vec(values, format).map(Some)
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+type BoxResult = Box<dyn Any + Send + Sync>;
+
+pub enum DateFieldError<'a> {
+    /// (field_name)
+    MissingField(&'a String),
+    /// (field_name, values, format)
+    InvalidDate(&'a String, &'a Vec<String>, &'a String),
+}
+
+pub type ErrorHandler = Box<fn(DateFieldError, Vec<String>) -> Vec<String>>;
+
+///
+/// Form field accepting dates. Values are parsed with `%Y-%m-%d` format by default, which can be
+/// changed with `format` using `chrono` format specifiers.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use racoon::forms::fields::date_field::DateField;
+///
+/// let birth_date: DateField<NaiveDate> = DateField::new("birth_date");
+/// let holidays: DateField<Option<Vec<NaiveDate>>> = DateField::new("holidays").format("%d/%m/%Y");
+/// ```
+///
+pub struct DateField<T> {
+    field_name: String,
+    format: String,
+    result: Arc<Mutex<Option<BoxResult>>>,
+    validated: Arc<AtomicBool>,
+    error_handler: Option<Arc<ErrorHandler>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Clone for DateField<T> {
+    fn clone(&self) -> Self {
+        Self {
+            field_name: self.field_name.clone(),
+            format: self.format.clone(),
+            result: self.result.clone(),
+            validated: self.validated.clone(),
+            error_handler: self.error_handler.clone(),
+            phantom: self.phantom,
+        }
+    }
+}
+
+impl<T: ToTypeT + Sync + Send> DateField<T> {
+    pub fn new<S: AsRef<str>>(field_name: S) -> Self {
+        let field_name = field_name.as_ref().to_string();
+
+        Self {
+            field_name,
+            format: DEFAULT_DATE_FORMAT.to_string(),
+            result: Arc::new(Mutex::new(None)),
+            validated: Arc::new(AtomicBool::new(false)),
+            error_handler: None,
+            phantom: PhantomData,
+        }
+    }
+
+    ///
+    /// Sets `chrono` format used to parse the values, e.g. `%d/%m/%Y`.
+    ///
+    pub fn format<S: AsRef<str>>(mut self, format: S) -> Self {
+        self.format = format.as_ref().to_string();
+        self
+    }
+
+    pub fn handle_error_message(
+        mut self,
+        callback: fn(DateFieldError, Vec<String>) -> Vec<String>,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(Box::new(callback)));
+        self
+    }
+
+    pub async fn value(self) -> T
+    where
+        T: 'static,
+    {
+        if !self.validated.load(Ordering::Relaxed) {
+            panic!("This field is not validated. Please call form.validate() method before accessing value.");
+        }
+
+        let mut lock = self.result.lock().await;
+        if let Some(result) = lock.take() {
+            if let Ok(t) = result.downcast::<T>() {
+                return *t;
+            }
+        }
+        panic!("Unexpected error. Bug in date_field.rs file.");
+    }
+}
+
+impl<T: ToTypeT + Sync + Send + 'static> AbstractFields for DateField<T> {
+    fn field_name(&self) -> FieldResult<String> {
+        let field_name = self.field_name.clone();
+        Box::new(Box::pin(async move { field_name }))
+    }
+
+    fn validate(
+        &mut self,
+        form_data: &mut FormData,
+        _: &mut Files,
+    ) -> FieldResult<Result<(), Vec<String>>> {
+        let field_name = self.field_name.clone();
+        let format = self.format.clone();
+        let mut values = form_data.remove(&field_name);
+        let result_ref = self.result.clone();
+        let validated = self.validated.clone();
+
+        let error_handler = self.error_handler.clone();
+
+        Box::new(Box::pin(async move {
+            let is_empty;
+            let is_optional = T::is_optional();
+
+            let mut errors: Vec<String> = vec![];
+
+            if let Some(values) = values.as_mut() {
+                is_empty = values.is_empty();
+                // Keeps submitted values for the error handler.
+                let submitted_values = values.clone();
+                let option_t = T::from_vec(values, &format);
+
+                if let Some(t) = option_t {
+                    let mut result = result_ref.lock().await;
+                    *result = Some(Box::new(t));
+                } else if !is_empty {
+                    let default_date_invalid_error = "Invalid date format.".to_string();
+                    if let Some(error_handler) = error_handler.clone() {
+                        let invalid_date_error =
+                            DateFieldError::InvalidDate(&field_name, &submitted_values, &format);
+                        let custom_errors =
+                            error_handler(invalid_date_error, vec![default_date_invalid_error]);
+                        errors.extend_from_slice(&custom_errors);
+                    } else {
+                        errors.push(default_date_invalid_error);
+                    }
+                }
+            } else {
+                is_empty = true;
+            }
+
+            if !is_optional && is_empty {
+                let default_date_missing_error = "This field is required.".to_string();
+
+                if let Some(error_handler) = error_handler.clone() {
+                    let date_missing_error = DateFieldError::MissingField(&field_name);
+                    let custom_errors =
+                        error_handler(date_missing_error, vec![default_date_missing_error]);
+                    errors.extend_from_slice(&custom_errors);
+                } else {
+                    errors.push(default_date_missing_error);
+                }
+            }
+
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+
+            if is_optional && is_empty {
+                let value_t = T::from_vec(&mut vec![], &format);
+
+                if let Some(t) = value_t {
+                    let mut result = result_ref.lock().await;
+                    *result = Some(Box::new(t));
+                }
+            }
+
+            validated.store(true, Ordering::Relaxed);
+            Ok(())
+        }))
+    }
+
+    fn wrap(&self) -> Box<dyn AbstractFields> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use chrono::NaiveDate;
+
+    use crate::core::forms::{Files, FormData};
+    use crate::forms::fields::date_field::{DateField, DateFieldError};
+    use crate::forms::fields::AbstractFields;
+
+    #[tokio::test]
+    async fn test_date_validate_required() {
+        let mut date_field: DateField<NaiveDate> = DateField::new("date");
+        let mut forms_data = FormData::new();
+        let mut files = Files::new();
+
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(
+            Some(vec!["This field is required.".to_string()]),
+            result.err()
+        );
+
+        let mut date_field2: DateField<NaiveDate> = DateField::new("date");
+        forms_data.insert("date".to_string(), vec!["2024-02-30".to_string()]);
+        let result = date_field2.validate(&mut forms_data, &mut files).await;
+        assert_eq!(Some(vec!["Invalid date format.".to_string()]), result.err());
+
+        let date_field3: DateField<NaiveDate> = DateField::new("date");
+        let mut date_field3_clone = date_field3.clone();
+        forms_data.insert("date".to_string(), vec!["2024-02-29".to_string()]);
+        let result = date_field3_clone
+            .validate(&mut forms_data, &mut files)
+            .await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+            date_field3.value().await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_date_format() {
+        let date_field: DateField<NaiveDate> = DateField::new("date").format("%d/%m/%Y");
+        let mut date_field_clone = date_field.clone();
+        let mut forms_data = FormData::new();
+        forms_data.insert("date".to_string(), vec!["30/06/2024".to_string()]);
+        let mut files = Files::new();
+        let result = date_field_clone.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+            date_field.value().await
+        );
+
+        // Default format is not accepted anymore.
+        let mut date_field: DateField<NaiveDate> = DateField::new("date").format("%d/%m/%Y");
+        forms_data.insert("date".to_string(), vec!["2024-06-30".to_string()]);
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(false, result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_date_optional() {
+        let mut date_field: DateField<Option<NaiveDate>> = DateField::new("date");
+        let mut forms_data = FormData::new();
+        let mut files = Files::new();
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(None, date_field.value().await);
+
+        // Optional field still rejects invalid values.
+        let mut date_field: DateField<Option<NaiveDate>> = DateField::new("date");
+        forms_data.insert("date".to_string(), vec!["tomorrow".to_string()]);
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(false, result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_date_vec() {
+        let mut date_field: DateField<Vec<NaiveDate>> = DateField::new("date");
+        let mut forms_data = FormData::new();
+        forms_data.insert(
+            "date".to_string(),
+            vec!["2024-01-01".to_string(), "2024-12-25".to_string()],
+        );
+        let mut files = Files::new();
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(
+            vec![
+                NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()
+            ],
+            date_field.value().await
+        );
+
+        let mut date_field: DateField<Vec<NaiveDate>> = DateField::new("date");
+        forms_data.insert(
+            "date".to_string(),
+            vec!["2024-01-01".to_string(), "2024-13-01".to_string()],
+        );
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(false, result.is_ok());
+
+        let mut date_field: DateField<Option<Vec<NaiveDate>>> = DateField::new("date");
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(None, date_field.value().await);
+    }
+
+    #[tokio::test]
+    async fn test_date_custom_error() {
+        let mut date_field: DateField<NaiveDate> =
+            DateField::new("date").handle_error_message(|error, default_errors| match error {
+                DateFieldError::InvalidDate(_, _, format) => {
+                    vec![format!("Date must be in {} format.", format)]
+                }
+                DateFieldError::MissingField(_) => default_errors,
+            });
+
+        let mut forms_data = FormData::new();
+        forms_data.insert("date".to_string(), vec!["01-01-2024".to_string()]);
+        let mut files = Files::new();
+        let result = date_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(
+            Some(vec!["Date must be in %Y-%m-%d format.".to_string()]),
+            result.err()
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use regex::Regex;
+use tokio::sync::Mutex;
+
+use crate::core::forms::{Files, FormData};
+use crate::forms::fields::{AbstractFields, FieldResult};
+
+///
+/// Returns true if the value looks like `local@domain.tld`.
+///
+pub fn is_valid_email(value: &str) -> bool {
+    static EMAIL_REGEX: OnceLock<Regex> = OnceLock::new();
+
+    let regex = EMAIL_REGEX.get_or_init(|| {
+        Regex::new(
+            r"^[A-Za-z0-9.!#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?)*\.[A-Za-z]{2,}$",
+        )
+        .unwrap()
+    });
+
+    value.len() <= 254 && regex.is_match(value)
+}
+
+pub trait ToTypeT {
+    fn from_vec(values: &mut Vec<String>) -> Option<Self>
+    where
+        Self: Sized;
+
+    fn is_optional() -> bool;
+}
+
+impl ToTypeT for String {
+    fn from_vec(values: &mut Vec<String>) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if !values.is_empty() {
+            let value = values.remove(0);
+            if is_valid_email(&value) {
+                return Some(value);
+            }
+        }
+
+        None
+    }
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl ToTypeT for Option<String> {
+    fn from_vec(values: &mut Vec<String>) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            // Outer Some denotes conversion success with value None.
+            return Some(None);
+        }
+
+        let value = values.remove(0);
+        if is_valid_email(&value) {
+            return Some(Some(value));
+        }
+
+        // Conversion failed. Invalid email found.
+        None
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl ToTypeT for Vec<String> {
+    fn from_vec(values: &mut Vec<String>) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            return None;
+        }
+
+        if !values.iter().all(|value| is_valid_email(value)) {
+            return None;
+        }
+
+        Some(std::mem::take(values))
+    }
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl ToTypeT for Option<Vec<String>> {
+    fn from_vec(values: &mut Vec<String>) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if values.is_empty() {
+            return Some(None);
+        }
+
+        if !values.iter().all(|value| is_valid_email(value)) {
+            return None;
+        }
+
+        Some(Some(std::mem::take(values)))
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+type BoxResult = Box<dyn Any + Send + Sync>;
+
+pub enum EmailFieldError<'a> {
+    /// (field_name)
+    MissingField(&'a String),
+    /// (field_name, values)
+    InvalidEmail(&'a String, &'a Vec<String>),
+}
+
+pub type ErrorHandler = Box<fn(EmailFieldError, Vec<String>) -> Vec<String>>;
+
+///
+/// Form field accepting email addresses. Values are returned as submitted after validating the
+/// format.
+///
+/// # Examples
+///
+/// ```
+/// use racoon::forms::fields::email_field::EmailField;
+///
+/// let email: EmailField<String> = EmailField::new("email");
+/// let cc: EmailField<Option<Vec<String>>> = EmailField::new("cc");
+/// ```
+///
+pub struct EmailField<T> {
+    field_name: String,
+    result: Arc<Mutex<Option<BoxResult>>>,
+    validated: Arc<AtomicBool>,
+    error_handler: Option<Arc<ErrorHandler>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Clone for EmailField<T> {
+    fn clone(&self) -> Self {
+        Self {
+            field_name: self.field_name.clone(),
+            result: self.result.clone(),
+            validated: self.validated.clone(),
+            error_handler: self.error_handler.clone(),
+            phantom: self.phantom,
+        }
+    }
+}
+
+impl<T: ToTypeT + Sync + Send> EmailField<T> {
+    pub fn new<S: AsRef<str>>(field_name: S) -> Self {
+        let field_name = field_name.as_ref().to_string();
+
+        Self {
+            field_name,
+            result: Arc::new(Mutex::new(None)),
+            validated: Arc::new(AtomicBool::new(false)),
+            error_handler: None,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn handle_error_message(
+        mut self,
+        callback: fn(EmailFieldError, Vec<String>) -> Vec<String>,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(Box::new(callback)));
+        self
+    }
+
+    pub async fn value(self) -> T
+    where
+        T: 'static,
+    {
+        if !self.validated.load(Ordering::Relaxed) {
+            panic!("This field is not validated. Please call form.validate() method before accessing value.");
+        }
+
+        let mut lock = self.result.lock().await;
+        if let Some(result) = lock.take() {
+            if let Ok(t) = result.downcast::<T>() {
+                return *t;
+            }
+        }
+        panic!("Unexpected error. Bug in email_field.rs file.");
+    }
+}
+
+impl<T: ToTypeT + Sync + Send + 'static> AbstractFields for EmailField<T> {
+    fn field_name(&self) -> FieldResult<String> {
+        let field_name = self.field_name.clone();
+        Box::new(Box::pin(async move { field_name }))
+    }
+
+    fn validate(
+        &mut self,
+        form_data: &mut FormData,
+        _: &mut Files,
+    ) -> FieldResult<Result<(), Vec<String>>> {
+        let field_name = self.field_name.clone();
+        let mut values = form_data.remove(&field_name);
+        let result_ref = self.result.clone();
+        let validated = self.validated.clone();
+
+        let error_handler = self.error_handler.clone();
+
+        Box::new(Box::pin(async move {
+            let is_empty;
+            let is_optional = T::is_optional();
+
+            let mut errors: Vec<String> = vec![];
+
+            if let Some(values) = values.as_mut() {
+                is_empty = values.is_empty();
+                // Keeps submitted values for the error handler.
+                let submitted_values = values.clone();
+                let option_t = T::from_vec(values);
+
+                if let Some(t) = option_t {
+                    let mut result = result_ref.lock().await;
+                    *result = Some(Box::new(t));
+                } else if !is_empty {
+                    let default_email_invalid_error = "Invalid email address.".to_string();
+                    if let Some(error_handler) = error_handler.clone() {
+                        let invalid_email_error =
+                            EmailFieldError::InvalidEmail(&field_name, &submitted_values);
+                        let custom_errors =
+                            error_handler(invalid_email_error, vec![default_email_invalid_error]);
+                        errors.extend_from_slice(&custom_errors);
+                    } else {
+                        errors.push(default_email_invalid_error);
+                    }
+                }
+            } else {
+                is_empty = true;
+            }
+
+            if !is_optional && is_empty {
+                let default_email_missing_error = "This field is required.".to_string();
+
+                if let Some(error_handler) = error_handler.clone() {
+                    let email_missing_error = EmailFieldError::MissingField(&field_name);
+                    let custom_errors =
+                        error_handler(email_missing_error, vec![default_email_missing_error]);
+                    errors.extend_from_slice(&custom_errors);
+                } else {
+                    errors.push(default_email_missing_error);
+                }
+            }
+
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+
+            if is_optional && is_empty {
+                let value_t = T::from_vec(&mut vec![]);
+
+                if let Some(t) = value_t {
+                    let mut result = result_ref.lock().await;
+                    *result = Some(Box::new(t));
+                }
+            }
+
+            validated.store(true, Ordering::Relaxed);
+            Ok(())
+        }))
+    }
+
+    fn wrap(&self) -> Box<dyn AbstractFields> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::core::forms::{Files, FormData};
+    use crate::forms::fields::email_field::{is_valid_email, EmailField, EmailFieldError};
+    use crate::forms::fields::AbstractFields;
+
+    #[test]
+    fn test_is_valid_email() {
+        assert_eq!(true, is_valid_email("john@example.com"));
+        assert_eq!(true, is_valid_email("john.doe+news@mail.example.co.uk"));
+        assert_eq!(false, is_valid_email("john"));
+        assert_eq!(false, is_valid_email("john@example"));
+        assert_eq!(false, is_valid_email("@example.com"));
+        assert_eq!(false, is_valid_email("john@-example.com"));
+        assert_eq!(false, is_valid_email("john doe@example.com"));
+        assert_eq!(false, is_valid_email("john@example.c"));
+    }
+
+    #[tokio::test]
+    async fn test_email_validate_required() {
+        let mut email_field: EmailField<String> = EmailField::new("email");
+        let mut forms_data = FormData::new();
+        let mut files = Files::new();
+
+        let result = email_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(Some(vec!["This field is required.".to_string()]), result.err());
+
+        let mut email_field2: EmailField<String> = EmailField::new("email");
+        forms_data.insert("email".to_string(), vec!["john@example".to_string()]);
+        let result = email_field2.validate(&mut forms_data, &mut files).await;
+        assert_eq!(Some(vec!["Invalid email address.".to_string()]), result.err());
+
+        let email_field3: EmailField<String> = EmailField::new("email");
+        let mut email_field3_clone = email_field3.clone();
+        forms_data.insert("email".to_string(), vec!["john@example.com".to_string()]);
+        let result = email_field3_clone.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!("john@example.com", email_field3.value().await);
+    }
+
+    #[tokio::test]
+    async fn test_email_optional() {
+        let mut email_field: EmailField<Option<String>> = EmailField::new("email");
+        let mut forms_data = FormData::new();
+        let mut files = Files::new();
+        let result = email_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(None, email_field.value().await);
+
+        // Optional field still rejects invalid values.
+        let mut email_field: EmailField<Option<String>> = EmailField::new("email");
+        forms_data.insert("email".to_string(), vec!["invalid".to_string()]);
+        let result = email_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(false, result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_email_vec() {
+        let mut email_field: EmailField<Vec<String>> = EmailField::new("email");
+        let mut forms_data = FormData::new();
+        forms_data.insert(
+            "email".to_string(),
+            vec!["john@example.com".to_string(), "jane@example.org".to_string()],
+        );
+        let mut files = Files::new();
+        let result = email_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(
+            vec!["john@example.com".to_string(), "jane@example.org".to_string()],
+            email_field.value().await
+        );
+
+        let mut email_field: EmailField<Vec<String>> = EmailField::new("email");
+        forms_data.insert(
+            "email".to_string(),
+            vec!["john@example.com".to_string(), "jane".to_string()],
+        );
+        let result = email_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(false, result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_email_optional_vec() {
+        let mut email_field: EmailField<Option<Vec<String>>> = EmailField::new("email");
+        let mut forms_data = FormData::new();
+        let mut files = Files::new();
+        let result = email_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(true, result.is_ok());
+        assert_eq!(None, email_field.value().await);
+    }
+
+    #[tokio::test]
+    async fn test_email_custom_error() {
+        let mut email_field: EmailField<String> =
+            EmailField::new("email").handle_error_message(|error, default_errors| match error {
+                EmailFieldError::InvalidEmail(_, values) => {
+                    vec![format!("{} is not a valid email.", values[0])]
+                }
+                EmailFieldError::MissingField(_) => default_errors,
+            });
+
+        let mut forms_data = FormData::new();
+        forms_data.insert("email".to_string(), vec!["john".to_string()]);
+        let mut files = Files::new();
+        let result = email_field.validate(&mut forms_data, &mut files).await;
+        assert_eq!(
+            Some(vec!["john is not a valid email.".to_string()]),
+            result.err()
+        );
+    }
+}
@@ -14,6 +14,8 @@ use crate::forms::fields::FieldResult;
 
 pub struct UploadedFile {
     pub filename: String,
+    /// `Content-Type` of the file part sent by the client.
+    pub content_type: Option<String>,
     core_file_field: crate::core::forms::FileField,
     pub temp_path: PathBuf,
 }
@@ -22,9 +24,11 @@ impl UploadedFile {
     pub fn from_core_file_field(core_file_field: crate::core::forms::FileField) -> Self {
         let temp_path = core_file_field.temp_path.clone();
         let filename = core_file_field.name.clone();
+        let content_type = core_file_field.content_type.clone();
 
         Self {
             filename,
+            content_type,
             core_file_field,
             temp_path,
         }
@@ -41,6 +45,7 @@ impl UploadedFile {
 
         Self {
             filename,
+            content_type: None,
             core_file_field,
             temp_path,
         }
@@ -310,16 +315,16 @@ pub mod tests {
         let mut temp_file = TempFile::new().await.unwrap();
         let _ = temp_file.write_all(b"Hello World").await;
 
-        let core_file_field = crate::core::forms::FileField::from(
-            "file.txt".to_string(),
-            temp_file,
-        );
+        let core_file_field =
+            crate::core::forms::FileField::from("file.txt".to_string(), temp_file)
+                .content_type(Some("text/plain".to_string()));
 
         let mut file_field: FileField<UploadedFile> = FileField::new("file");
         files.insert("file".to_string(), vec![core_file_field]);
         let result = file_field.validate(&mut form_data, &mut files).await;
 
         let path_field = file_field.value().await;
+        assert_eq!(Some("text/plain".to_string()), path_field.content_type);
         let path_buf = path_field.temp_path;
 
         assert_eq!(true, path_buf.exists());
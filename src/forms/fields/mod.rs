@@ -1,3 +1,6 @@
+pub mod choice_field;
+pub mod date_field;
+pub mod email_field;
 pub mod file_field;
 pub mod input_field;
 pub mod uuid_field;
@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::core::forms::FormFieldError;
 use crate::core::request::Request;
+use crate::core::response::status::ResponseStatus;
+use crate::core::response::{JsonResponse, Response};
 
 use crate::forms::fields::AbstractFields;
 use crate::racoon_error;
@@ -20,6 +22,66 @@ pub struct ValidationError {
     pub others: Vec<String>,
     #[serde(skip_serializing)]
     pub critical_errors: Vec<String>,
+    /// Set if the form could not be parsed because too many files were being uploaded.
+    #[serde(skip)]
+    pub uploads_busy: bool,
+}
+
+impl ValidationError {
+    ///
+    /// Returns `422 Unprocessable Content` JSON response with `field_errors` and `others`, or
+    /// `503 Service Unavailable` if `uploads_busy` is set. Critical errors are not exposed to the
+    /// client.
+    ///
+    /// # Examples
+    /// ```
+    /// use racoon::core::request::Request;
+    /// use racoon::core::response::{HttpResponse, Response};
+    /// use racoon::core::response::status::ResponseStatus;
+    /// use racoon::forms::fields::input_field::InputField;
+    /// use racoon::forms::fields::AbstractFields;
+    /// use racoon::forms::{FormFields, FormValidator};
+    ///
+    /// struct LoginForm {
+    ///     username: InputField<String>,
+    /// }
+    ///
+    /// impl FormValidator for LoginForm {
+    ///     fn new() -> Self {
+    ///         Self {
+    ///             username: InputField::new("username"),
+    ///         }
+    ///     }
+    ///
+    ///     fn form_fields(&mut self) -> FormFields {
+    ///         vec![self.username.wrap()]
+    ///     }
+    /// }
+    ///
+    /// async fn login(request: Request) -> Response {
+    ///     match LoginForm::new().validate(&request).await {
+    ///         Ok(form) => HttpResponse::ok().body(form.username.value().await),
+    ///         Err(error) => error.into_response(),
+    ///     }
+    /// }
+    /// ```
+    ///
+    pub fn into_response(self) -> Response {
+        let json = serde_json::json!({
+            "field_errors": self.field_errors,
+            "others": self.others,
+        });
+        if self.uploads_busy {
+            return JsonResponse::service_unavailable().body(json);
+        }
+        JsonResponse::unprocessable_content().body(json)
+    }
+}
+
+impl From<ValidationError> for Response {
+    fn from(error: ValidationError) -> Self {
+        error.into_response()
+    }
 }
 
 pub trait FormValidator: Sized + Send {
@@ -39,6 +101,7 @@ pub trait FormValidator: Sized + Send {
             let mut field_errors: HashMap<String, Vec<String>> = HashMap::new();
             let mut other_errors: Vec<String> = vec![];
             let mut critical_errors: Vec<String> = vec![];
+            let mut uploads_busy = false;
 
             let (mut form_data, mut files) =
                 match request.parse_body(request.form_constraints.clone()).await {
@@ -53,6 +116,15 @@ pub trait FormValidator: Sized + Send {
                                 other_errors.push("Max header size exceed.".to_string());
                             }
 
+                            FormFieldError::MaxFieldsExceed => {
+                                other_errors.push("Max fields exceed.".to_string());
+                            }
+
+                            FormFieldError::UploadsBusy => {
+                                other_errors.push("Too many uploads in progress.".to_string());
+                                uploads_busy = true;
+                            }
+
                             FormFieldError::MaxFileSizeExceed(field_name) => {
                                 let file_size_exceed_error =
                                     vec!["Max file size exceed.".to_string()];
@@ -93,6 +165,7 @@ pub trait FormValidator: Sized + Send {
                             field_errors,
                             others: other_errors,
                             critical_errors,
+                            uploads_busy,
                         };
                         return Err(validation_error);
                     }
@@ -123,6 +196,7 @@ pub trait FormValidator: Sized + Send {
                     field_errors,
                     others: vec![],
                     critical_errors,
+                    uploads_busy: false,
                 };
                 return Err(validation_error);
             }
@@ -141,3 +215,49 @@ pub trait FormValidator: Sized + Send {
         Box::new(Box::pin(async move { None }))
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashMap;
+
+    use crate::core::response::Response;
+
+    use super::ValidationError;
+
+    #[test]
+    fn test_validation_error_response() {
+        let mut field_errors = HashMap::new();
+        let errors = vec!["This field is required.".to_string()];
+        field_errors.insert("username".to_string(), errors);
+
+        let validation_error = ValidationError {
+            field_errors,
+            others: vec!["Max body size exceed.".to_string()],
+            critical_errors: vec!["Field: Disk full.".to_string()],
+            uploads_busy: false,
+        };
+
+        let mut response: Response = validation_error.into();
+        let (status_code, _) = response.status();
+        assert_eq!(422, status_code);
+
+        let body: serde_json::Value = serde_json::from_slice(response.get_body()).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "field_errors": {"username": ["This field is required."]},
+                "others": ["Max body size exceed."],
+            }),
+            body
+        );
+
+        let validation_error = ValidationError {
+            field_errors: HashMap::new(),
+            others: vec!["Too many uploads in progress.".to_string()],
+            critical_errors: vec![],
+            uploads_busy: true,
+        };
+        let response: Response = validation_error.into();
+        let (status_code, _) = response.status();
+        assert_eq!(503, status_code);
+    }
+}